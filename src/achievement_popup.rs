@@ -0,0 +1,68 @@
+use std::collections::VecDeque;
+
+use raylib::prelude::*;
+
+use crate::{localization::LocaleHandler, utils::get_game_width};
+
+// seconds an unlock stays on screen
+const POPUP_TIME: f32 = 4.;
+
+/// Shows freshly unlocked achievements under the xp bar, one after another
+pub struct AchievementPopup {
+    queue: VecDeque<String>,
+    timer: f32,
+}
+
+impl AchievementPopup {
+    pub fn new() -> Self {
+        Self {
+            queue: VecDeque::new(),
+            timer: 0.,
+        }
+    }
+
+    pub fn push(&mut self, name: String) {
+        self.queue.push_back(name);
+    }
+
+    pub fn update(&mut self, rl: &RaylibHandle) {
+        if self.queue.is_empty() {
+            return;
+        }
+
+        self.timer += rl.get_frame_time();
+        if self.timer >= POPUP_TIME {
+            self.timer = 0.;
+            self.queue.pop_front();
+        }
+    }
+
+    pub fn draw(&self, rl: &mut RaylibDrawHandle, font: &Font, locale_handler: &LocaleHandler) {
+        let Some(name) = self.queue.front() else {
+            return;
+        };
+
+        let text = format!(
+            "{}: {name}",
+            locale_handler
+                .language_data
+                .get("achievement_unlocked")
+                .unwrap()
+        );
+        let width = text.chars().count() as f32 * 12. + 40.;
+        let rect = Rectangle::new(get_game_width(rl) as f32 / 2. - width / 2., 44., width, 40.);
+
+        // fades out over the last second
+        let alpha = (POPUP_TIME - self.timer).min(1.);
+        rl.draw_rectangle_rec(rect, Color::BLACK.alpha(0.75 * alpha));
+        rl.draw_rectangle_lines_ex(rect, 2., Color::GOLD.alpha(alpha));
+        rl.draw_text_ex(
+            font,
+            &text,
+            Vector2::new(rect.x + 20., rect.y + 8.),
+            24.,
+            0.,
+            Color::GOLD.alpha(alpha),
+        );
+    }
+}
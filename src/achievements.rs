@@ -0,0 +1,168 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    content::ContentIds,
+    localization::LocaleHandler,
+    mods,
+    player::Player,
+    processing::ProduceId,
+    sim::{Produce, SimEvent},
+};
+
+/// What an achievement asks for, as written in static/achievements.json
+#[derive(Deserialize)]
+enum GoalEntry {
+    Harvests(usize),
+    HarvestsOf { produce: ProduceId, amount: usize },
+    LandBought(usize),
+    WorkersHired(usize),
+    MoneyEarned(usize),
+    // in minutes
+    PlayTime(u64),
+    Level(usize),
+}
+
+#[derive(Deserialize)]
+struct AchievementEntry {
+    id: String,
+    goal: GoalEntry,
+}
+
+enum Goal {
+    Harvests(usize),
+    HarvestsOf(Produce, usize),
+    LandBought(usize),
+    WorkersHired(usize),
+    MoneyEarned(usize),
+    PlayTime(u64),
+    Level(usize),
+}
+
+impl Goal {
+    /// How far the player got and how far they have to get
+    fn progress(&self, player: &Player) -> (usize, usize) {
+        let stats = &player.stats;
+        match *self {
+            Self::Harvests(amount) => (stats.total_harvests(), amount),
+            Self::HarvestsOf(produce, amount) => (stats.harvests_of(produce), amount),
+            Self::LandBought(amount) => (stats.land_bought, amount),
+            Self::WorkersHired(amount) => (stats.workers_hired, amount),
+            Self::MoneyEarned(amount) => (stats.money_earned, amount),
+            Self::PlayTime(minutes) => ((stats.play_time / 60) as usize, minutes as usize),
+            Self::Level(level) => (player.level, level),
+        }
+    }
+}
+
+struct Achievement {
+    id: String,
+    goal: Goal,
+}
+
+fn load_achievements() -> Vec<Achievement> {
+    let mut entries: HashMap<String, Vec<AchievementEntry>> =
+        mods::load_content("achievements.json").expect("no achievements");
+    let content_ids = ContentIds::load();
+
+    entries
+        .remove("achievements")
+        .unwrap_or_default()
+        .into_iter()
+        .map(|entry| {
+            let goal = match entry.goal {
+                GoalEntry::Harvests(amount) => Goal::Harvests(amount),
+                // goods are made rather than harvested, so they're never counted
+                GoalEntry::HarvestsOf { produce, amount } => Goal::HarvestsOf(
+                    produce.resolve(&content_ids, &[]).unwrap_or_else(|| {
+                        panic!("{} counts produce that can't be harvested", entry.id)
+                    }),
+                    amount,
+                ),
+                GoalEntry::LandBought(amount) => Goal::LandBought(amount),
+                GoalEntry::WorkersHired(amount) => Goal::WorkersHired(amount),
+                GoalEntry::MoneyEarned(amount) => Goal::MoneyEarned(amount),
+                GoalEntry::PlayTime(minutes) => Goal::PlayTime(minutes),
+                GoalEntry::Level(level) => Goal::Level(level),
+            };
+            Achievement { id: entry.id, goal }
+        })
+        .collect()
+}
+
+/// An achievement as listed in the pause menu
+pub struct AchievementLine {
+    pub name: String,
+    pub description: String,
+    pub progress: (usize, usize),
+    pub unlocked: bool,
+}
+
+/// Milestones read from static/achievements.json, unlocked once the stats get there. Unlocks are saved by
+/// id, so they stay put when achievements are added or reordered
+#[derive(Serialize, Deserialize)]
+pub struct AchievementHandler {
+    unlocked: HashSet<String>,
+    #[serde(skip, default = "load_achievements")]
+    achievements: Vec<Achievement>,
+}
+
+impl AchievementHandler {
+    pub fn new() -> Self {
+        Self {
+            unlocked: HashSet::new(),
+            achievements: load_achievements(),
+        }
+    }
+
+    pub fn update(&mut self, player: &Player, events: &mut Vec<SimEvent>) {
+        for (index, achievement) in self.achievements.iter().enumerate() {
+            if self.unlocked.contains(&achievement.id) {
+                continue;
+            }
+
+            let (current, target) = achievement.goal.progress(player);
+            if current >= target {
+                self.unlocked.insert(achievement.id.clone());
+                events.push(SimEvent::AchievementUnlocked { index });
+            }
+        }
+    }
+
+    pub fn name(&self, index: usize, locale_handler: &LocaleHandler) -> String {
+        let key = format!("achievement_{}", self.achievements[index].id);
+        locale_handler
+            .language_data
+            .get(&key)
+            .cloned()
+            .unwrap_or(key)
+    }
+
+    pub fn lines(&self, player: &Player, locale_handler: &LocaleHandler) -> Vec<AchievementLine> {
+        self.achievements
+            .iter()
+            .enumerate()
+            .map(|(index, achievement)| {
+                let key = format!("achievement_description_{}", achievement.id);
+                AchievementLine {
+                    name: self.name(index, locale_handler),
+                    description: locale_handler
+                        .language_data
+                        .get(&key)
+                        .cloned()
+                        .unwrap_or(key),
+                    progress: achievement.goal.progress(player),
+                    unlocked: self.unlocked.contains(&achievement.id),
+                }
+            })
+            .collect()
+    }
+
+    pub fn unlocked_count(&self) -> usize {
+        self.achievements
+            .iter()
+            .filter(|achievement| self.unlocked.contains(&achievement.id))
+            .count()
+    }
+}
@@ -1,11 +1,11 @@
-use std::collections::HashMap;
+use std::collections::HashSet;
 
-use raylib::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    map::{Map, TILE_PIXEL_SIZE, TILE_SIZE, TileType},
-    utils::parse_json,
+    map::{Climate, Map, TILE_SIZE, TileType},
+    mods,
+    upgrades::{UpgradeHandler, UpgradeTarget},
 };
 
 #[derive(Serialize, Deserialize)]
@@ -13,16 +13,68 @@ pub struct Animal {
     animal_type: usize,
     drop_bar: usize,
     pub position: (i32, i32),
-    display_position: (f32, f32),
+    // where it's drawn, eased towards the position every frame
+    pub display_position: (f32, f32),
     #[serde(skip_serializing, skip_deserializing)]
-    direction: (i32, i32),
+    pub direction: (i32, i32),
+    // ticks since it was born or bought
+    #[serde(default)]
+    age: usize,
+    // animals from before breeding are all grown up
+    #[serde(default = "grown_up")]
+    adult: bool,
+    // ticks until it can breed again
+    #[serde(default)]
+    breeding_cooldown: usize,
+    // bought in the shop rather than born in a pen, only those are refunded when sold
+    #[serde(default = "bought_before_breeding")]
+    pub bought: bool,
+}
+
+fn grown_up() -> bool {
+    true
+}
+
+// animals from before breeding all came from the shop
+fn bought_before_breeding() -> bool {
+    true
 }
 
 #[derive(Deserialize)]
 pub struct AnimalData {
+    pub id: String,
+    // the crop it likes best, it eats any other one too but needs twice as much
+    #[serde(default)]
+    pub feed: String,
+    // crops eaten for every drop
+    #[serde(default = "default_feed_per_drop")]
+    pub feed_per_drop: usize,
     time_to_drop: usize,
+    #[serde(default)]
+    pub climate: Climate,
     pub drop_cost: usize,
     pub exp: usize,
+    // drawn while it's young, the grown up texture is used without it
+    #[serde(default)]
+    pub juvenile_texture: Option<String>,
+    // animals without it never breed
+    #[serde(default)]
+    breeding: Option<BreedingData>,
+}
+
+#[derive(Deserialize)]
+pub struct BreedingData {
+    // ticks a newborn takes to grow up
+    time_to_adult: usize,
+    // ticks before a parent can breed again
+    cooldown: usize,
+    // most animals of this kind in one pen, only the pen's size limits the herd without it
+    #[serde(default)]
+    max_herd: Option<usize>,
+}
+
+fn default_feed_per_drop() -> usize {
+    1
 }
 
 #[derive(Deserialize)]
@@ -41,22 +93,18 @@ pub struct AnimalHandler {
 }
 
 impl AnimalHandler {
-    pub fn new() -> Self {
-        let res = parse_json("dynamic/animals_save.json");
+    pub fn new(dynamic_data: Option<AnimalDynamic>) -> Self {
+        let static_data = mods::load_content("animals.json").expect("no animals??");
 
-        let static_data = parse_json("static/animals.json").expect("no animals??");
-
-        match res {
-            Ok(dynamic_data) => Self {
+        match dynamic_data {
+            Some(dynamic_data) => Self {
                 static_data,
                 dynamic_data,
             },
-            Err(_) => {
-                Self {
-                    static_data,
-                    dynamic_data: AnimalDynamic { animals: vec![] },
-                }
-            }
+            None => Self {
+                static_data,
+                dynamic_data: AnimalDynamic { animals: vec![] },
+            },
         }
     }
 
@@ -64,20 +112,89 @@ impl AnimalHandler {
         self.dynamic_data.animals.push(animal);
     }
 
-    pub fn move_animals(&mut self, map: &mut Map) {
+    pub fn remove_animal_at(&mut self, position: (i32, i32)) -> Option<Animal> {
+        let index = self
+            .dynamic_data
+            .animals
+            .iter()
+            .position(|animal| animal.position == position)?;
+        Some(self.dynamic_data.animals.remove(index))
+    }
+
+    pub fn move_animals(&mut self, map: &mut Map, upgrade_handler: &UpgradeHandler) {
         for animal in self.dynamic_data.animals.iter_mut() {
-            animal.move_randomly(
-                map,
-                &self.static_data.animal_data[animal.animal_type as usize],
-            );
+            let animal_data = &self.static_data.animal_data[animal.animal_type];
+            animal.grow(animal_data);
+
+            let speed = upgrade_handler.growth_speed(UpgradeTarget::Animal(animal.animal_type));
+            animal.move_randomly(map, animal_data, speed);
         }
+
+        map.update_pens();
+        self.breed(map);
     }
 
-    pub fn save(&self) {
-        let serialized =
-            serde_json::to_string_pretty(&self.dynamic_data).expect("couldn't save animals data");
-        std::fs::write("dynamic/animals_save.json", serialized)
-            .expect("Couldn't write map data to json file");
+    /// Two grown up animals of the same kind in a pen have a newborn on a free tile of the pen,
+    /// once both are off cooldown and the herd isn't full
+    fn breed(&mut self, map: &Map) {
+        let animals = &mut self.dynamic_data.animals;
+        let mut checked = HashSet::new();
+        let mut newborns = vec![];
+
+        for index in 0..animals.len() {
+            if !animals[index].can_breed() || checked.contains(&animals[index].position) {
+                continue;
+            }
+
+            let Some(pen) = map.pen_at(animals[index].position) else {
+                continue;
+            };
+            checked.extend(pen.iter().copied());
+
+            let mut free: Vec<(i32, i32)> = pen
+                .iter()
+                .copied()
+                .filter(|tile| map.dynamic_data.tiles.get(tile) == Some(&TileType::Grass))
+                .filter(|tile| !animals.iter().any(|animal| animal.position == *tile))
+                .collect();
+
+            for (animal_type, animal_data) in self.static_data.animal_data.iter().enumerate() {
+                let Some(breeding) = &animal_data.breeding else {
+                    continue;
+                };
+
+                let herd: Vec<usize> = (0..animals.len())
+                    .filter(|i| {
+                        animals[*i].animal_type == animal_type
+                            && pen.contains(&animals[*i].position)
+                    })
+                    .collect();
+                if breeding
+                    .max_herd
+                    .is_some_and(|max_herd| herd.len() >= max_herd)
+                {
+                    continue;
+                }
+
+                let parents: Vec<usize> = herd
+                    .into_iter()
+                    .filter(|i| animals[*i].can_breed())
+                    .take(2)
+                    .collect();
+                if parents.len() < 2 || free.is_empty() {
+                    continue;
+                }
+
+                for parent in parents {
+                    animals[parent].breeding_cooldown = breeding.cooldown;
+                }
+
+                let tile = free.swap_remove(rand::random_range(0..free.len()));
+                newborns.push(Animal::newborn(animal_type, tile.0, tile.1));
+            }
+        }
+
+        animals.extend(newborns);
     }
 }
 
@@ -89,11 +206,49 @@ impl Animal {
             position: (x, y),
             display_position: ((x * TILE_SIZE) as f32, (y * TILE_SIZE) as f32),
             direction: (0, 1),
+            age: 0,
+            adult: true,
+            breeding_cooldown: 0,
+            bought: true,
+        }
+    }
+
+    fn newborn(animal_type: usize, x: i32, y: i32) -> Self {
+        Self {
+            adult: false,
+            bought: false,
+            ..Self::new(animal_type, x, y)
         }
     }
 
-    fn move_randomly(&mut self, map: &mut Map, animal_data: &AnimalData) {
+    pub fn animal_type(&self) -> usize {
+        self.animal_type
+    }
+
+    pub fn adult(&self) -> bool {
+        self.adult
+    }
+
+    fn can_breed(&self) -> bool {
+        self.adult && self.breeding_cooldown == 0
+    }
 
+    fn grow(&mut self, animal_data: &AnimalData) {
+        self.age += 1;
+        self.breeding_cooldown = self.breeding_cooldown.saturating_sub(1);
+
+        // animals that can't breed have no time to grow up in, so they're grown right away
+        if !self.adult
+            && animal_data
+                .breeding
+                .as_ref()
+                .is_none_or(|breeding| self.age >= breeding.time_to_adult)
+        {
+            self.adult = true;
+        }
+    }
+
+    fn move_randomly(&mut self, map: &mut Map, animal_data: &AnimalData, speed: usize) {
         if rand::random_bool(0.5) {
             let directions = [(0, 1), (1, 0), (0, -1), (-1, 0)];
             self.direction = directions[rand::random_range(0..4)];
@@ -112,8 +267,17 @@ impl Animal {
             return;
         }
 
-        self.drop_bar += 1;
-        if self.drop_bar >= animal_data.time_to_drop {
+        // young animals only walk around
+        if !self.adult {
+            self.position = new_pos;
+            return;
+        }
+
+        // hungry animals keep walking around, but only drop something once they've eaten
+        self.drop_bar = (self.drop_bar + speed).min(animal_data.time_to_drop);
+        if self.drop_bar >= animal_data.time_to_drop
+            && map.feed_animal(self.position, &animal_data.feed, animal_data.feed_per_drop)
+        {
             self.drop_bar = 0;
             map.dynamic_data.tiles.insert(
                 new_pos,
@@ -121,62 +285,135 @@ impl Animal {
                     animal: self.animal_type,
                 },
             );
+            map.touch(new_pos);
             return;
         }
 
         self.position = new_pos;
     }
+}
 
-    pub fn draw(&mut self, rl: &mut RaylibDrawHandle, textures: &HashMap<String, Texture2D>) {
-        let pixel_position = Vector2::new(
-            (self.position.0 * TILE_SIZE) as f32,
-            (self.position.1 * TILE_SIZE) as f32,
-        );
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        self.display_position.0 = lerp(
-            self.display_position.0,
-            pixel_position.x,
-            10. * rl.get_frame_time(),
-        );
-        self.display_position.1 = lerp(
-            self.display_position.1,
-            pixel_position.y,
-            10. * rl.get_frame_time(),
-        );
+    fn handler_where_the_first_kind(breeding: Option<BreedingData>) -> AnimalHandler {
+        let mut handler = AnimalHandler::new(None);
+        handler.static_data.animal_data[0].breeding = breeding;
+        handler
+    }
+
+    fn breeding(cooldown: usize, max_herd: Option<usize>) -> Option<BreedingData> {
+        Some(BreedingData {
+            time_to_adult: 100,
+            cooldown,
+            max_herd,
+        })
+    }
+
+    // a pen of nine tiles, from (-1, -1) to (1, 1), fenced off at the edge of the starting island
+    fn map_with_pen() -> Map {
+        let mut map = Map::new(None);
+        for x in -2..=2_i32 {
+            for y in -2..=2_i32 {
+                let tile = if x.abs() == 2 || y.abs() == 2 {
+                    TileType::Fence
+                } else {
+                    TileType::Grass
+                };
+                map.dynamic_data.tiles.insert((x, y), tile);
+                map.mark_changed((x, y));
+            }
+        }
+        map.update_pens();
+        map
+    }
+
+    fn grow_all(handler: &mut AnimalHandler) {
+        for animal in handler.dynamic_data.animals.iter_mut() {
+            animal.grow(&handler.static_data.animal_data[animal.animal_type]);
+        }
+    }
 
-        let pixel_position = Vector2::new(self.display_position.0, self.display_position.1);
-
-        let texture_index = match self.direction {
-            (0, 1) => 0,
-            (0, -1) => 1,
-            (1, 0) => 2,
-            (-1, 0) => 3,
-            _ => 4,
-        };
-
-        let source = Rectangle {
-            x: (texture_index * TILE_PIXEL_SIZE) as f32,
-            y: 0.,
-            width: TILE_PIXEL_SIZE as f32,
-            height: TILE_PIXEL_SIZE as f32,
-        };
-
-        let destination = Rectangle {
-            x: pixel_position.x,
-            y: pixel_position.y,
-            width: TILE_SIZE as f32,
-            height: TILE_SIZE as f32,
-        };
-
-        rl.draw_texture_pro(
-            textures
-                .get(&format!("animal{}", self.animal_type as usize))
-                .unwrap(),
-            source,
-            destination,
-            Vector2::zero(),
-            0.,
-            Color::WHITE,
+    #[test]
+    fn a_pair_breeds_only_after_the_cooldown() {
+        let mut handler = handler_where_the_first_kind(breeding(5, None));
+        let map = map_with_pen();
+        handler.add_animal(Animal::new(0, -1, -1));
+        handler.add_animal(Animal::new(0, 1, 1));
+
+        handler.breed(&map);
+        assert_eq!(handler.dynamic_data.animals.len(), 3);
+        assert!(!handler.dynamic_data.animals[2].adult());
+        assert!(!handler.dynamic_data.animals[2].bought);
+
+        for _ in 0..4 {
+            grow_all(&mut handler);
+            handler.breed(&map);
+        }
+        assert_eq!(handler.dynamic_data.animals.len(), 3);
+
+        grow_all(&mut handler);
+        handler.breed(&map);
+        assert_eq!(handler.dynamic_data.animals.len(), 4);
+    }
+
+    #[test]
+    fn a_full_herd_stops_breeding() {
+        let mut handler = handler_where_the_first_kind(breeding(0, Some(2)));
+        let map = map_with_pen();
+        handler.add_animal(Animal::new(0, -1, -1));
+        handler.add_animal(Animal::new(0, 1, 1));
+
+        handler.breed(&map);
+
+        assert_eq!(handler.dynamic_data.animals.len(), 2);
+    }
+
+    #[test]
+    fn a_juvenile_never_drops_produce() {
+        let handler = handler_where_the_first_kind(breeding(0, None));
+        let mut map = map_with_pen();
+        let mut young = Animal::newborn(0, 0, 0);
+
+        for _ in 0..200 {
+            young.move_randomly(&mut map, &handler.static_data.animal_data[0], 100);
+        }
+
+        assert_eq!(young.drop_bar, 0);
+        assert!(
+            !map.dynamic_data
+                .tiles
+                .values()
+                .any(|tile| matches!(tile, TileType::AnimalDrop { .. }))
         );
     }
+
+    #[test]
+    fn a_juvenile_grows_up_at_time_to_adult() {
+        let mut handler = handler_where_the_first_kind(breeding(0, None));
+        handler.static_data.animal_data[0]
+            .breeding
+            .as_mut()
+            .unwrap()
+            .time_to_adult = 3;
+        let mut young = Animal::newborn(0, 0, 0);
+
+        young.grow(&handler.static_data.animal_data[0]);
+        young.grow(&handler.static_data.animal_data[0]);
+        assert!(!young.adult());
+
+        young.grow(&handler.static_data.animal_data[0]);
+        assert!(young.adult());
+    }
+
+    #[test]
+    fn kinds_that_dont_breed_grow_up_right_away() {
+        let handler = handler_where_the_first_kind(None);
+        let mut young = Animal::newborn(0, 0, 0);
+
+        young.grow(&handler.static_data.animal_data[0]);
+
+        assert!(young.adult());
+    }
 }
@@ -0,0 +1,20 @@
+use serde::de::{self, Error};
+use std::{
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+pub fn parse_json<T: de::DeserializeOwned>(path: &str) -> Result<T, serde_json::Error> {
+    let res = fs::read_to_string(path);
+    match res {
+        Ok(s) => serde_json::from_str(&s),
+        Err(_) => Result::Err(Error::custom("No such file")),
+    }
+}
+
+pub fn get_unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
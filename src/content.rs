@@ -0,0 +1,261 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Deserialize;
+
+use crate::{common::parse_json, localization::load_language, mods};
+
+#[derive(Deserialize)]
+struct Entry {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct SeasonEntry {
+    id: String,
+    weather: Vec<(String, usize)>,
+}
+
+// only as much of static/weather.json as is checked
+#[derive(Deserialize)]
+struct WeatherEntries {
+    seasons: Vec<SeasonEntry>,
+    weather: Vec<Entry>,
+}
+
+#[derive(Deserialize)]
+struct AnimalEntry {
+    id: String,
+    #[serde(default)]
+    feed: String,
+    #[serde(default)]
+    juvenile_texture: Option<String>,
+}
+
+/// String ids of all content, in the same order as the static data.
+/// Anything that outlives a session (saves, upgrades, textures, translations) refers to content by these,
+/// while the game itself keeps using positions in the static data
+pub struct ContentIds {
+    pub crops: Vec<String>,
+    pub trees: Vec<String>,
+    pub animals: Vec<String>,
+    pub hives: Vec<String>,
+    pub flowers: Vec<String>,
+    pub beekeeping: Vec<String>,
+    pub misc: Vec<String>,
+    pub buildings: Vec<String>,
+    pub goods: Vec<String>,
+}
+
+impl ContentIds {
+    pub fn load() -> Self {
+        let mut tiles: HashMap<String, Vec<Entry>> =
+            mods::load_content("tiles.json").expect("Can't deserialize");
+        let mut animals: HashMap<String, Vec<Entry>> =
+            mods::load_content("animals.json").expect("no animals??");
+        let mut toolbar: HashMap<String, Vec<Entry>> =
+            mods::load_content("toolbar.json").expect("no toolbar");
+        let mut recipes: HashMap<String, Vec<Entry>> =
+            mods::load_content("recipes.json").expect("no recipes");
+
+        let take = |data: &mut HashMap<String, Vec<Entry>>, key: &str| -> Vec<String> {
+            data.remove(key)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|entry| entry.id)
+                .collect()
+        };
+
+        Self {
+            crops: take(&mut tiles, "crops_data"),
+            trees: take(&mut tiles, "tree_data"),
+            hives: take(&mut tiles, "hive_data"),
+            flowers: take(&mut tiles, "flower_data"),
+            animals: take(&mut animals, "animal_data"),
+            beekeeping: take(&mut toolbar, "beekeeping"),
+            misc: take(&mut toolbar, "misc"),
+            buildings: take(&mut recipes, "buildings"),
+            goods: take(&mut recipes, "goods"),
+        }
+    }
+
+    pub fn index_of(ids: &[String], id: &str) -> Option<usize> {
+        ids.iter().position(|other| other == id)
+    }
+
+    /// Checks that every piece of content has its textures and translations, and that the toolbar
+    /// lines up with the content it sells. Returns the problems found, an empty list means all is fine
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = vec![];
+
+        let toolbar: HashMap<String, Vec<Entry>> =
+            mods::load_content("toolbar.json").expect("no toolbar");
+        let toolbar_ids = |key: &str| -> Vec<String> {
+            toolbar
+                .get(key)
+                .map(|entries| entries.iter().map(|entry| entry.id.clone()).collect())
+                .unwrap_or_default()
+        };
+
+        // the toolbar is matched to the content by position, so the order has to be the same
+        let mut beekeeping = self.hives.clone();
+        beekeeping.extend(self.flowers.iter().cloned());
+        for (key, expected) in [
+            ("crops", &self.crops),
+            ("trees", &self.trees),
+            ("animals", &self.animals),
+            ("beekeeping", &beekeeping),
+            ("buildings", &self.buildings),
+        ] {
+            if toolbar_ids(key) != *expected {
+                problems.push(format!(
+                    "toolbar {key} {:?} don't match the content {:?}",
+                    toolbar_ids(key),
+                    expected
+                ));
+            }
+        }
+
+        for (kind, ids) in [
+            ("crop", &self.crops),
+            ("tree", &self.trees),
+            ("animal", &self.animals),
+            ("flower", &self.flowers),
+            ("beekeeping", &self.beekeeping),
+            ("misc", &self.misc),
+            ("building", &self.buildings),
+            ("good", &self.goods),
+        ] {
+            let mut seen = HashSet::new();
+            for id in ids.iter() {
+                if !seen.insert(id) {
+                    problems.push(format!("{kind} id {id} is used twice"));
+                }
+            }
+        }
+
+        let animals: HashMap<String, Vec<AnimalEntry>> =
+            mods::load_content("animals.json").expect("no animals??");
+        for animal in animals.get("animal_data").into_iter().flatten() {
+            if !animal.feed.is_empty() && !self.crops.contains(&animal.feed) {
+                problems.push(format!(
+                    "animal {} eats {}, which isn't a crop",
+                    animal.id, animal.feed
+                ));
+            }
+        }
+
+        let mut textures = vec![];
+        textures.extend(self.crops.iter().map(|id| format!("crop_{id}")));
+        textures.extend(self.trees.iter().map(|id| format!("tree_{id}")));
+        textures.extend(self.animals.iter().map(|id| format!("animal_{id}")));
+        textures.extend(self.animals.iter().map(|id| format!("animal_drop_{id}")));
+        textures.extend(
+            animals
+                .get("animal_data")
+                .into_iter()
+                .flatten()
+                .filter_map(|animal| animal.juvenile_texture.clone()),
+        );
+        textures.extend(self.beekeeping.iter().map(|id| format!("beekeeping_{id}")));
+        textures.extend(self.misc.iter().map(|id| format!("misc_{id}")));
+        textures.extend(self.buildings.iter().map(|id| format!("building_{id}")));
+
+        let texture_dirs = mods::asset_dirs("textures");
+        for texture in textures {
+            if !texture_dirs
+                .iter()
+                .any(|dir| dir.join(format!("{texture}.png")).exists())
+            {
+                problems.push(format!("missing texture {texture}.png"));
+            }
+        }
+
+        let mut keys = vec![];
+        keys.extend(self.crops.iter().map(|id| format!("plant_{id}")));
+        keys.extend(self.trees.iter().map(|id| format!("tree_{id}")));
+        keys.extend(self.trees.iter().map(|id| format!("fruit_{id}")));
+        keys.extend(self.animals.iter().map(|id| format!("animal_{id}")));
+        keys.extend(self.animals.iter().map(|id| format!("drop_{id}")));
+        keys.extend(self.beekeeping.iter().map(|id| format!("beekeeping_{id}")));
+        keys.extend(self.misc.iter().map(|id| format!("misc_{id}")));
+        keys.extend(self.buildings.iter().map(|id| format!("building_{id}")));
+        keys.extend(self.goods.iter().map(|id| format!("good_{id}")));
+
+        let upgrades: HashMap<String, Vec<Entry>> =
+            mods::load_content("upgrades.json").expect("no upgrade data");
+        for upgrade in upgrades.get("upgrades").into_iter().flatten() {
+            keys.push(format!("upgrade_{}", upgrade.id));
+            keys.push(format!("upgrade_description_{}", upgrade.id));
+        }
+
+        let achievements: HashMap<String, Vec<Entry>> =
+            mods::load_content("achievements.json").expect("no achievements");
+        for achievement in achievements.get("achievements").into_iter().flatten() {
+            keys.push(format!("achievement_{}", achievement.id));
+            keys.push(format!("achievement_description_{}", achievement.id));
+        }
+
+        let weather: WeatherEntries =
+            parse_json("static/weather.json").expect("no weather settings");
+        let weather_ids: Vec<&String> = weather.weather.iter().map(|kind| &kind.id).collect();
+        for season in weather.seasons.iter() {
+            keys.push(format!("season_{}", season.id));
+            for (id, _) in season.weather.iter() {
+                if !weather_ids.contains(&id) {
+                    problems.push(format!(
+                        "season {} brings weather {id}, which doesn't exist",
+                        season.id
+                    ));
+                }
+            }
+        }
+        keys.extend(weather_ids.iter().map(|id| format!("weather_{id}")));
+
+        let localizations: HashMap<String, HashMap<String, String>> =
+            parse_json("static/localizations.json").expect("no localization data provieded");
+        let mut codes: Vec<&String> = localizations
+            .get("localizations")
+            .map(|localizations| localizations.keys().collect())
+            .unwrap_or_default();
+        codes.sort();
+
+        let languages: Vec<(&String, HashMap<String, String>)> = codes
+            .into_iter()
+            .map(|code| (code, load_language(code)))
+            .collect();
+
+        // every language should have whatever any other language has
+        let all_keys: HashSet<&String> = languages
+            .iter()
+            .flat_map(|(_, data)| data.keys())
+            .chain(keys.iter())
+            .collect();
+
+        for (code, data) in languages.iter() {
+            let mut missing: Vec<&&String> = all_keys
+                .iter()
+                .filter(|key| !data.contains_key(**key))
+                .collect();
+            missing.sort();
+
+            for key in missing {
+                problems.push(format!("{code} is missing the translation for {key}"));
+            }
+        }
+
+        problems
+    }
+}
+
+/// Prints whatever `ContentIds::validate` found
+pub fn report_problems(content_ids: &ContentIds) {
+    let problems = content_ids.validate();
+    if problems.is_empty() {
+        return;
+    }
+
+    println!("content check found {} problems:", problems.len());
+    for problem in problems {
+        println!("  {problem}");
+    }
+}
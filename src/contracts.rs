@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    animal::AnimalHandler,
+    common::parse_json,
+    inventory::{Inventory, unit_price},
+    map::Map,
+    mods,
+    sim::Produce,
+    upgrades::UpgradeHandler,
+    worker::TICKS_PER_MINUTE,
+};
+
+/// How often orders come in and what they ask for, read from static/contracts.json
+#[derive(Deserialize)]
+struct ContractSettings {
+    // orders on the board at once
+    board_size: usize,
+    // ticks between a spot on the board freeing up and a new order taking it
+    new_contract_ticks: usize,
+    min_minutes: usize,
+    max_minutes: usize,
+    // an order is worth value_per_level * level ^ level_exponent at base prices
+    value_per_level: f32,
+    level_exponent: f32,
+    // most kinds of goods a single order asks for
+    max_kinds: usize,
+    // rewards as shares of what the order is worth
+    money_bonus: f32,
+    exp_share: f32,
+    exp_chance: f64,
+    worker_chance: f64,
+}
+
+impl ContractSettings {
+    fn load() -> Self {
+        parse_json("static/contracts.json").expect("no contract settings")
+    }
+}
+
+#[derive(Deserialize)]
+struct UnlockEntry {
+    #[serde(default)]
+    id: String,
+    unlock_level: usize,
+}
+
+/// The level each kind of goods can first be made at, taken from the shop, earliest first
+fn unlock_levels() -> Vec<(Produce, usize)> {
+    let mut toolbar: HashMap<String, Vec<UnlockEntry>> =
+        mods::load_content("toolbar.json").expect("no toolbar");
+    let mut levels = |key: &str, produce: fn(usize) -> Produce| -> Vec<(Produce, usize)> {
+        toolbar
+            .remove(key)
+            .unwrap_or_default()
+            .into_iter()
+            .enumerate()
+            .map(|(index, entry)| (produce(index), entry.unlock_level))
+            .collect()
+    };
+
+    let mut unlocks = levels("crops", Produce::Crop);
+    unlocks.extend(levels("trees", Produce::Tree));
+    unlocks.extend(levels("animals", Produce::Animal));
+    // the first beekeeping item is the hive itself
+    unlocks.extend(levels("beekeeping", |_| Produce::Honey).into_iter().take(1));
+    unlocks.sort_by_key(|(_, level)| *level);
+    unlocks
+}
+
+/// The level storage barns come in the shop at. Orders are filled from storage, so there are none before
+fn storage_unlock_level() -> usize {
+    let mut toolbar: HashMap<String, Vec<UnlockEntry>> =
+        mods::load_content("toolbar.json").expect("no toolbar");
+    toolbar
+        .remove("misc")
+        .unwrap_or_default()
+        .into_iter()
+        .find(|entry| entry.id == "storage")
+        .map_or(0, |entry| entry.unlock_level)
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Reward {
+    Money(usize),
+    Exp(usize),
+    // a worker that comes for free
+    Worker,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Contract {
+    // goods asked for, with how many are needed and how many were delivered so far
+    pub requirements: Vec<(Produce, usize, usize)>,
+    pub ticks_left: usize,
+    pub reward: Reward,
+}
+
+/// Timed orders for goods from storage, scaled to the player's level. Finishing one pays a bonus on top
+/// of what the goods are worth, running out of time just takes it off the board. Goods are only handed
+/// over once storage has all of them, so nothing is lost with an order that runs out
+#[derive(Serialize, Deserialize)]
+pub struct ContractBoard {
+    pub contracts: Vec<Contract>,
+    // ticks until the next order comes in
+    next_contract: usize,
+    #[serde(skip, default = "ContractSettings::load")]
+    settings: ContractSettings,
+    #[serde(skip, default = "unlock_levels")]
+    unlocks: Vec<(Produce, usize)>,
+    #[serde(skip, default = "storage_unlock_level")]
+    storage_level: usize,
+}
+
+impl ContractBoard {
+    pub fn new() -> Self {
+        Self {
+            contracts: vec![],
+            next_contract: 0,
+            settings: ContractSettings::load(),
+            unlocks: unlock_levels(),
+            storage_level: storage_unlock_level(),
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        level: usize,
+        map: &Map,
+        animal_handler: &AnimalHandler,
+        upgrade_handler: &UpgradeHandler,
+    ) {
+        for contract in self.contracts.iter_mut() {
+            contract.ticks_left = contract.ticks_left.saturating_sub(1);
+        }
+        self.contracts.retain(|contract| contract.ticks_left > 0);
+
+        if level < self.storage_level || self.contracts.len() >= self.settings.board_size {
+            return;
+        }
+
+        if self.next_contract > 0 {
+            self.next_contract -= 1;
+            return;
+        }
+
+        let unlocked: Vec<(Produce, usize)> = self
+            .unlocks
+            .iter()
+            .filter(|(_, unlock_level)| *unlock_level <= level)
+            .map(|(produce, _)| {
+                (
+                    *produce,
+                    unit_price(*produce, map, animal_handler, upgrade_handler),
+                )
+            })
+            .collect();
+        if let Some(contract) = self.generate(level, &unlocked) {
+            self.contracts.push(contract);
+        }
+        self.next_contract = self.settings.new_contract_ticks;
+    }
+
+    /// A new order for some of the unlocked goods, the newest ones more likely
+    fn generate(&self, level: usize, unlocked: &[(Produce, usize)]) -> Option<Contract> {
+        let settings = &self.settings;
+        if unlocked.is_empty() {
+            return None;
+        }
+
+        let value = settings.value_per_level
+            * (level as f32).powf(settings.level_exponent)
+            * rand::random_range(0.8..1.2);
+
+        // picking from the newest half of what's unlocked keeps orders in step with the farm
+        let mut candidates = unlocked[unlocked.len() / 2..].to_vec();
+        let kinds = rand::random_range(1..=settings.max_kinds.clamp(1, candidates.len()));
+
+        let mut worth = 0.;
+        let requirements = (0..kinds)
+            .map(|_| {
+                let (produce, price) =
+                    candidates.swap_remove(rand::random_range(0..candidates.len()));
+                let amount = (value / kinds as f32 / price as f32).max(1.) as usize;
+                worth += (amount * price) as f32;
+                (produce, amount, 0)
+            })
+            .collect();
+
+        let reward = if rand::random_bool(settings.worker_chance) {
+            Reward::Worker
+        } else if rand::random_bool(settings.exp_chance) {
+            Reward::Exp((worth * settings.exp_share) as usize)
+        } else {
+            Reward::Money((worth * settings.money_bonus) as usize)
+        };
+
+        let minutes = rand::random_range(settings.min_minutes..=settings.max_minutes);
+        Some(Contract {
+            requirements,
+            ticks_left: minutes * TICKS_PER_MINUTE,
+            reward,
+        })
+    }
+
+    /// Hands over what the order still needs from storage, if storage has all of it, and returns the reward.
+    /// Orders from older saves may have been partly delivered already, only the rest is taken for those
+    pub fn deliver(&mut self, index: usize, inventory: &mut Inventory) -> Option<Reward> {
+        let contract = self.contracts.get_mut(index)?;
+        if contract
+            .requirements
+            .iter()
+            .any(|(produce, needed, delivered)| delivered + inventory.amount(*produce) < *needed)
+        {
+            return None;
+        }
+
+        for (produce, needed, delivered) in contract.requirements.iter_mut() {
+            *delivered += inventory.take(*produce, needed.saturating_sub(*delivered));
+        }
+
+        Some(self.contracts.remove(index).reward)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{content::ContentIds, localization::load_language, map::TileType, sim::Simulation};
+
+    // a farm with a storage barn and an order for the goods
+    fn farm_with_order(requirements: Vec<(Produce, usize, usize)>) -> Simulation {
+        let mut sim = Simulation::new(&load_language("en"), &ContentIds::load(), None);
+        sim.map.dynamic_data.tiles.insert((0, 0), TileType::Storage);
+        sim.map.mark_changed((0, 0));
+        sim.contracts.contracts.push(Contract {
+            requirements,
+            ticks_left: 10,
+            reward: Reward::Money(100),
+        });
+        sim
+    }
+
+    fn store(sim: &mut Simulation, produce: Produce, amount: usize) {
+        sim.player.inventory.add(produce, amount, &sim.map);
+    }
+
+    fn update_board(sim: &mut Simulation, level: usize) {
+        sim.contracts
+            .update(level, &sim.map, &sim.animal_handler, &sim.upgrade_handler);
+    }
+
+    #[test]
+    fn nothing_is_taken_until_the_whole_order_can_be_filled() {
+        let order = vec![(Produce::Crop(0), 5, 0), (Produce::Honey, 3, 0)];
+        let mut sim = farm_with_order(order.clone());
+        store(&mut sim, Produce::Crop(0), 10);
+        store(&mut sim, Produce::Honey, 2);
+
+        let inventory = &mut sim.player.inventory;
+        assert_eq!(sim.contracts.deliver(0, inventory), None);
+        assert_eq!(inventory.amount(Produce::Crop(0)), 10);
+        assert_eq!(inventory.amount(Produce::Honey), 2);
+
+        // running out of time loses nothing
+        sim.contracts.contracts[0].ticks_left = 1;
+        update_board(&mut sim, 1);
+        assert!(sim.contracts.contracts.is_empty());
+        assert_eq!(sim.player.inventory.amount(Produce::Crop(0)), 10);
+
+        let mut sim = farm_with_order(order);
+        store(&mut sim, Produce::Crop(0), 10);
+        store(&mut sim, Produce::Honey, 3);
+        let inventory = &mut sim.player.inventory;
+        assert_eq!(
+            sim.contracts.deliver(0, inventory),
+            Some(Reward::Money(100))
+        );
+        assert_eq!(inventory.amount(Produce::Crop(0)), 5);
+        assert_eq!(inventory.amount(Produce::Honey), 0);
+    }
+
+    #[test]
+    fn partly_delivered_orders_only_take_the_rest() {
+        let mut sim = farm_with_order(vec![(Produce::Crop(0), 5, 4)]);
+        store(&mut sim, Produce::Crop(0), 2);
+
+        let inventory = &mut sim.player.inventory;
+        assert_eq!(
+            sim.contracts.deliver(0, inventory),
+            Some(Reward::Money(100))
+        );
+        assert_eq!(inventory.amount(Produce::Crop(0)), 1);
+    }
+
+    #[test]
+    fn no_orders_come_in_before_storage() {
+        let mut sim = Simulation::new(&load_language("en"), &ContentIds::load(), None);
+        let level = sim.contracts.storage_level;
+        assert_eq!(level, 6);
+
+        for _ in 0..1000 {
+            update_board(&mut sim, level - 1);
+        }
+        assert!(sim.contracts.contracts.is_empty());
+
+        update_board(&mut sim, level);
+        assert_eq!(sim.contracts.contracts.len(), 1);
+    }
+}
@@ -0,0 +1,230 @@
+use raylib::prelude::*;
+
+use crate::{
+    TILE_UPDATE_TIME, UI_BUTTON_SIZE, UI_GAPS,
+    contracts::Reward,
+    inventory::produce_name,
+    localization::LocaleHandler,
+    pause_menu::{Button, ButtonState},
+    settings::GameSettigns,
+    sim::Simulation,
+    utils::{get_game_height, get_game_width, shrink_number_for_display},
+};
+
+const WIDTH: f32 = 320.;
+const LINE_HEIGHT: f32 = 28.;
+const BUTTON_HEIGHT: f32 = 36.;
+const GAPS: f32 = 10.;
+
+struct ContractCard {
+    // what's asked for, and whether storage covers the rest of it
+    requirements: Vec<(String, bool)>,
+    time_left: String,
+    reward: String,
+    deliver: Button,
+    rect: Rectangle,
+}
+
+/// The orders on the contracts board, next to the upgrades. Delivering hands over what storage has of
+/// the goods asked for, the reward comes once everything is in
+pub struct ContractsPanel {
+    pub open: bool,
+    cards: Vec<ContractCard>,
+    rect: Rectangle,
+}
+
+impl ContractsPanel {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            cards: vec![],
+            rect: Rectangle::default(),
+        }
+    }
+
+    pub fn blocks_mouse(&self, mouse_position: Vector2) -> bool {
+        self.open && self.rect.check_collision_point_rec(mouse_position)
+    }
+
+    fn layout(
+        &mut self,
+        rl: &mut RaylibHandle,
+        sim: &Simulation,
+        locale_handler: &LocaleHandler,
+        settings: &GameSettigns,
+    ) {
+        let lang = &locale_handler.language_data;
+        let (map, animal_handler) = (&sim.map, &sim.animal_handler);
+
+        // left of the upgrades grid, which is three buttons wide
+        let left =
+            get_game_width(rl) as f32 - 3. * (UI_BUTTON_SIZE + UI_GAPS / 2.) - WIDTH - GAPS * 2.;
+        let top = UI_GAPS / 2.;
+        let bottom = get_game_height(rl) as f32 - GAPS;
+
+        let mut y = top + 50.;
+        self.cards.clear();
+        for contract in sim.contracts.contracts.iter() {
+            let height = contract.requirements.len() as f32 * LINE_HEIGHT
+                + LINE_HEIGHT
+                + BUTTON_HEIGHT
+                + GAPS * 2.;
+            if y + height > bottom {
+                break;
+            }
+
+            let requirements = contract
+                .requirements
+                .iter()
+                .map(|(produce, needed, delivered)| {
+                    let stored = sim.player.inventory.amount(*produce);
+                    (
+                        format!(
+                            "{} {delivered}/{needed}",
+                            produce_name(*produce, map, animal_handler, locale_handler)
+                        ),
+                        delivered + stored >= *needed,
+                    )
+                })
+                .collect();
+
+            let seconds = (contract.ticks_left as f32 * TILE_UPDATE_TIME) as usize;
+            let reward = match contract.reward {
+                Reward::Money(money) => {
+                    format!(
+                        "+{}",
+                        shrink_number_for_display(money, locale_handler, settings)
+                    )
+                }
+                Reward::Exp(exp) => format!(
+                    "+{} {}",
+                    shrink_number_for_display(exp, locale_handler, settings),
+                    lang.get("reward_exp").unwrap()
+                ),
+                Reward::Worker => lang.get("reward_worker").unwrap().to_string(),
+            };
+
+            let rect = Rectangle::new(left + GAPS, y, WIDTH - GAPS * 2., height - GAPS);
+            let deliver = Button::new(
+                Rectangle::new(
+                    rect.x + GAPS,
+                    rect.y + rect.height - BUTTON_HEIGHT - GAPS,
+                    rect.width - GAPS * 2.,
+                    BUTTON_HEIGHT,
+                ),
+                lang.get("deliver").unwrap().to_string(),
+            );
+
+            self.cards.push(ContractCard {
+                requirements,
+                time_left: format!("{}:{:02}", seconds / 60, seconds % 60),
+                reward,
+                deliver,
+                rect,
+            });
+            y += height;
+        }
+
+        self.rect = Rectangle::new(left, top, WIDTH, (y - top).max(100.) + GAPS);
+    }
+
+    /// Returns whether goods were handed over to an order
+    pub fn update(
+        &mut self,
+        rl: &mut RaylibHandle,
+        sim: &mut Simulation,
+        locale_handler: &LocaleHandler,
+        settings: &GameSettigns,
+    ) -> bool {
+        if rl.is_key_pressed(KeyboardKey::KEY_O) {
+            self.open = !self.open;
+        }
+
+        if !self.open {
+            return false;
+        }
+
+        // orders count down every tick, so the cards are rebuilt every frame
+        self.layout(rl, sim, locale_handler, settings);
+
+        for (index, card) in self.cards.iter_mut().enumerate() {
+            card.deliver.update(rl);
+            if card.deliver.state == ButtonState::Pressed {
+                sim.deliver_contract(index);
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn draw(&self, rl: &mut RaylibDrawHandle, font: &Font, locale_handler: &LocaleHandler) {
+        if !self.open {
+            return;
+        }
+
+        let lang = &locale_handler.language_data;
+
+        rl.draw_rectangle_rec(self.rect, Color::BLACK.alpha(0.75));
+        rl.draw_text_ex(
+            font,
+            lang.get("contracts").unwrap(),
+            Vector2::new(self.rect.x + GAPS * 2., self.rect.y + GAPS),
+            32.,
+            0.,
+            Color::RAYWHITE,
+        );
+
+        if self.cards.is_empty() {
+            rl.draw_text_ex(
+                font,
+                lang.get("no_contracts").unwrap(),
+                Vector2::new(self.rect.x + GAPS * 2., self.rect.y + 56.),
+                24.,
+                0.,
+                Color::LIGHTGRAY,
+            );
+            return;
+        }
+
+        for card in self.cards.iter() {
+            rl.draw_rectangle_rec(card.rect, Color::DARKGRAY.alpha(0.5));
+
+            rl.draw_text_ex(
+                font,
+                &card.reward,
+                Vector2::new(card.rect.x + GAPS, card.rect.y + GAPS / 2.),
+                24.,
+                0.,
+                Color::GOLD,
+            );
+            rl.draw_text_ex(
+                font,
+                &card.time_left,
+                Vector2::new(card.rect.x + card.rect.width - 70., card.rect.y + GAPS / 2.),
+                24.,
+                0.,
+                Color::RAYWHITE,
+            );
+
+            for (index, (requirement, ready)) in card.requirements.iter().enumerate() {
+                rl.draw_text_ex(
+                    font,
+                    requirement,
+                    Vector2::new(
+                        card.rect.x + GAPS,
+                        card.rect.y + GAPS / 2. + (index + 1) as f32 * LINE_HEIGHT,
+                    ),
+                    24.,
+                    0.,
+                    if *ready {
+                        Color::LIME
+                    } else {
+                        Color::LIGHTGRAY
+                    },
+                );
+            }
+
+            card.deliver.draw(rl, font);
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+use crate::{
+    animal::AnimalHandler,
+    localization::LocaleHandler,
+    map::Map,
+    sim::Produce,
+    upgrades::{UpgradeHandler, UpgradeTarget},
+};
+
+/// Goods waiting in storage to be sold. Only the kinds the player chose to keep end up here,
+/// everything else is still sold as soon as it's harvested
+#[serde_as]
+#[derive(Serialize, Deserialize, Default)]
+pub struct Inventory {
+    #[serde_as(as = "Vec<(_, _)>")]
+    items: HashMap<Produce, usize>,
+    // kinds of goods workers take to storage instead of selling
+    kept: HashSet<Produce>,
+}
+
+impl Inventory {
+    pub fn amount(&self, produce: Produce) -> usize {
+        self.items.get(&produce).copied().unwrap_or(0)
+    }
+
+    pub fn total(&self) -> usize {
+        self.items.values().sum()
+    }
+
+    pub fn is_kept(&self, produce: Produce) -> bool {
+        self.kept.contains(&produce)
+    }
+
+    pub fn toggle_kept(&mut self, produce: Produce) {
+        if !self.kept.remove(&produce) {
+            self.kept.insert(produce);
+        }
+    }
+
+    pub fn has_room(&self, map: &Map) -> bool {
+        self.total() < map.storage_capacity()
+    }
+
+    /// Puts as much as fits into storage and returns how much did
+    pub fn add(&mut self, produce: Produce, amount: usize, map: &Map) -> usize {
+        let added = amount.min(map.storage_capacity().saturating_sub(self.total()));
+        if added > 0 {
+            *self.items.entry(produce).or_insert(0) += added;
+        }
+        added
+    }
+
+    /// Puts goods back that were taken out of storage or were on their way there. They were already
+    /// counted against the capacity, so they go back even if storage has filled up since
+    pub fn put_back(&mut self, produce: Produce, amount: usize) {
+        if amount > 0 {
+            *self.items.entry(produce).or_insert(0) += amount;
+        }
+    }
+
+    /// Takes up to the amount out of storage and returns how much there was
+    pub fn take(&mut self, produce: Produce, amount: usize) -> usize {
+        let Some(stored) = self.items.get_mut(&produce) else {
+            return 0;
+        };
+
+        let taken = amount.min(*stored);
+        *stored -= taken;
+        if *stored == 0 {
+            self.items.remove(&produce);
+        }
+        taken
+    }
+}
+
+/// Every kind of goods the farm can make, in the order of the static data
+pub fn all_produce(map: &Map, animal_handler: &AnimalHandler) -> Vec<Produce> {
+    let static_data = &map.static_data;
+
+    let mut produce: Vec<Produce> = (0..static_data.crops_data.len())
+        .map(Produce::Crop)
+        .collect();
+    produce.extend((0..static_data.tree_data.len()).map(Produce::Tree));
+    produce.extend((0..animal_handler.static_data.animal_data.len()).map(Produce::Animal));
+    produce.push(Produce::Honey);
+    produce.extend((0..map.recipes.goods.len()).map(Produce::Good));
+    produce
+}
+
+/// What one piece of goods sells for. Climate and skill bonuses give more pieces per harvest instead,
+/// and so do the flowers around a hive, a piece of honey is worth what a hive without flowers makes
+pub fn unit_price(
+    produce: Produce,
+    map: &Map,
+    animal_handler: &AnimalHandler,
+    upgrade_handler: &UpgradeHandler,
+) -> usize {
+    let static_data = &map.static_data;
+
+    let (target, price) = match produce {
+        Produce::Crop(crop) => (
+            UpgradeTarget::Crop(crop),
+            static_data.crops_data[crop].sell_price,
+        ),
+        Produce::Tree(tree) => (
+            UpgradeTarget::Tree(tree),
+            static_data.tree_data[tree].sell_price,
+        ),
+        Produce::Animal(animal) => (
+            UpgradeTarget::Animal(animal),
+            animal_handler.static_data.animal_data[animal].drop_cost,
+        ),
+        Produce::Honey => return static_data.hive_data[0].sell_price.max(1),
+        Produce::Good(good) => return map.recipes.goods[good].sell_price.max(1),
+    };
+
+    upgrade_handler.apply(target, price, 0).0.max(1)
+}
+
+/// The name of what a harvest gives, a carrot, an apple or an egg rather than the plant or animal
+pub fn produce_name(
+    produce: Produce,
+    map: &Map,
+    animal_handler: &AnimalHandler,
+    locale_handler: &LocaleHandler,
+) -> String {
+    let key = match produce {
+        Produce::Crop(crop) => format!("plant_{}", map.static_data.crops_data[crop].id),
+        Produce::Tree(tree) => format!("fruit_{}", map.static_data.tree_data[tree].id),
+        Produce::Animal(animal) => {
+            format!("drop_{}", animal_handler.static_data.animal_data[animal].id)
+        }
+        Produce::Honey => "honey".to_string(),
+        Produce::Good(good) => format!("good_{}", map.recipes.goods[good].id),
+    };
+
+    locale_handler
+        .language_data
+        .get(&key)
+        .cloned()
+        .unwrap_or(key)
+}
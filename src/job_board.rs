@@ -0,0 +1,320 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+use crate::{map::Map, worker::JobType};
+
+// ticks a worker gets on top of the walk before its reservation runs out
+const RESERVATION_SLACK: usize = 60;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct Reservation {
+    pub worker: usize,
+    pub job: JobType,
+    // the tick the job has to be done by
+    pub deadline: usize,
+}
+
+/// Keeps track of which worker is on the way to do what, so no two workers go for the same tile.
+/// Reservations are dropped when the job is done, when the tile stops needing that job or when
+/// the worker takes too long
+#[serde_as]
+#[derive(Serialize, Deserialize, Default)]
+pub struct JobBoard {
+    #[serde_as(as = "Vec<(_, _)>")]
+    reservations: HashMap<(i32, i32), Reservation>,
+    tick: usize,
+    // every tile that needs a job done, by job, so workers don't have to look over the whole island.
+    // It's filled in once and then kept up with the tiles the map reports as touched
+    #[serde(skip)]
+    open_jobs: HashMap<JobType, HashSet<(i32, i32)>>,
+    #[serde(skip)]
+    indexed: bool,
+}
+
+impl JobBoard {
+    pub fn holder(&self, position: (i32, i32)) -> Option<usize> {
+        self.reservations
+            .get(&position)
+            .map(|reservation| reservation.worker)
+    }
+
+    pub fn is_free_for(&self, position: (i32, i32), worker: usize) -> bool {
+        self.holder(position).is_none_or(|holder| holder == worker)
+    }
+
+    /// Takes a tile for a worker that is `distance` ticks away from it
+    pub fn reserve(&mut self, position: (i32, i32), worker: usize, job: JobType, distance: usize) {
+        debug_assert!(
+            self.is_free_for(position, worker),
+            "worker {worker} took {position:?} from worker {:?}",
+            self.holder(position)
+        );
+        debug_assert!(
+            self.reservations
+                .iter()
+                .all(|(held, reservation)| reservation.worker != worker || *held == position),
+            "worker {worker} holds more than one job"
+        );
+
+        self.reservations.insert(
+            position,
+            Reservation {
+                worker,
+                job,
+                deadline: self.tick + distance + RESERVATION_SLACK,
+            },
+        );
+    }
+
+    pub fn release(&mut self, position: (i32, i32), worker: usize) {
+        if self.holder(position) == Some(worker) {
+            self.reservations.remove(&position);
+        }
+    }
+
+    /// Drops everything a worker held, for workers that give up on their job or leave
+    pub fn release_all(&mut self, worker: usize) {
+        self.reservations
+            .retain(|_, reservation| reservation.worker != worker);
+    }
+
+    /// Whether any tile needs this job done with nobody else on the way to it
+    pub fn has_free_job(&self, job: JobType, worker: usize) -> bool {
+        self.open_jobs.get(&job).is_some_and(|positions| {
+            positions
+                .iter()
+                .any(|position| self.is_free_for(*position, worker))
+        })
+    }
+
+    /// Looks at what a tile needs again, for tiles a worker just did a job on
+    pub fn refresh(&mut self, position: (i32, i32), map: &Map) {
+        for positions in self.open_jobs.values_mut() {
+            positions.remove(&position);
+        }
+        if let Some(job) = map
+            .dynamic_data
+            .tiles
+            .get(&position)
+            .and_then(|tile| JobType::for_tile(map, tile))
+        {
+            self.open_jobs.entry(job).or_default().insert(position);
+        }
+    }
+
+    /// Looks again at the tiles touched since the last tick. The first time round it looks at every tile
+    pub fn sync(&mut self, map: &mut Map) {
+        let touched = std::mem::take(&mut map.touched_tiles);
+        if self.indexed {
+            for position in touched {
+                self.refresh(position, map);
+            }
+            return;
+        }
+
+        self.indexed = true;
+        for (position, tile) in map.dynamic_data.tiles.iter() {
+            if let Some(job) = JobType::for_tile(map, tile) {
+                self.open_jobs.entry(job).or_default().insert(*position);
+            }
+        }
+    }
+
+    /// Advances the clock and cancels reservations on tiles that changed underneath them or that ran
+    /// out of time. Returns the workers that lost their job
+    pub fn update(&mut self, map: &Map) -> Vec<usize> {
+        self.tick += 1;
+
+        let mut cancelled = vec![];
+        self.reservations.retain(|position, reservation| {
+            let still_needed = map
+                .dynamic_data
+                .tiles
+                .get(position)
+                .and_then(|tile| JobType::for_tile(map, tile))
+                == Some(reservation.job);
+
+            if still_needed && reservation.deadline >= self.tick {
+                return true;
+            }
+
+            cancelled.push(reservation.worker);
+            false
+        });
+
+        cancelled
+    }
+
+    pub fn tick(&self) -> usize {
+        self.tick
+    }
+
+    pub fn reservations(&self) -> impl Iterator<Item = (&(i32, i32), &Reservation)> {
+        self.reservations.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::{
+        content::ContentIds,
+        localization::load_language,
+        map::TileType,
+        sim::{SimEvent, Simulation},
+        upgrades::UpgradeHandler,
+        weather::Weather,
+        worker::{Worker, WorkerRole},
+    };
+
+    fn ripe_crop(map: &Map) -> TileType {
+        TileType::Farmland {
+            crop: 0,
+            stage: map.static_data.crops_data[0].time_to_grow,
+        }
+    }
+
+    fn map_with_ripe_crop_at(position: (i32, i32)) -> Map {
+        let mut map = Map::new(None);
+        let tile = ripe_crop(&map);
+        map.dynamic_data.tiles.insert(position, tile);
+        map
+    }
+
+    #[test]
+    fn workers_never_share_a_tile() {
+        let mut sim = Simulation::new(&load_language("en"), &ContentIds::load(), None);
+        let tile = ripe_crop(&sim.map);
+        sim.map
+            .dynamic_data
+            .tiles
+            .values_mut()
+            .for_each(|field_tile| *field_tile = tile);
+        let field = sim.map.dynamic_data.tiles.len();
+
+        // harvesters only, so a harvested field stays fallow and nothing can be harvested twice
+        sim.player.money = usize::MAX / 2;
+        sim.worker_handler.workers.clear();
+        for position in [(-2, -2), (2, 2), (0, 0)] {
+            let mut worker = Worker::new(position.0, position.1);
+            worker.set_role(WorkerRole::Harvester);
+            sim.worker_handler.add_worker(worker);
+        }
+
+        let mut harvested = HashSet::new();
+        for _ in 0..300 {
+            sim.tick();
+
+            for event in sim.drain_events() {
+                if let SimEvent::Harvest { position, .. } = event {
+                    assert!(
+                        harvested.insert(position),
+                        "{position:?} was harvested twice"
+                    );
+                }
+            }
+
+            let board = &sim.worker_handler.job_board;
+            let mut targets = HashSet::new();
+            for worker in sim.worker_handler.workers.iter() {
+                let Some(target) = worker.target().filter(|_| worker.job.is_some()) else {
+                    continue;
+                };
+                assert!(
+                    targets.insert(target),
+                    "two workers are headed for {target:?}"
+                );
+                assert_eq!(board.holder(target), Some(worker.id));
+            }
+
+            let holders: Vec<usize> = board
+                .reservations()
+                .map(|(_, reservation)| reservation.worker)
+                .collect();
+            let unique_holders: HashSet<&usize> = holders.iter().collect();
+            assert_eq!(
+                holders.len(),
+                unique_holders.len(),
+                "a worker holds two jobs"
+            );
+        }
+
+        assert_eq!(harvested.len(), field);
+    }
+
+    #[test]
+    fn reservations_run_out() {
+        let map = map_with_ripe_crop_at((0, 0));
+        let mut board = JobBoard::default();
+        board.reserve((0, 0), 7, JobType::Harvest, 5);
+
+        for _ in 0..5 + RESERVATION_SLACK {
+            assert!(board.update(&map).is_empty());
+        }
+        assert_eq!(board.holder((0, 0)), Some(7));
+
+        assert_eq!(board.update(&map), vec![7]);
+        assert_eq!(board.holder((0, 0)), None);
+    }
+
+    #[test]
+    fn reservations_are_released_when_given_up() {
+        let mut board = JobBoard::default();
+        board.reserve((0, 0), 7, JobType::Harvest, 5);
+
+        // nobody else can let go of it
+        board.release((0, 0), 3);
+        assert_eq!(board.holder((0, 0)), Some(7));
+        assert!(!board.is_free_for((0, 0), 3));
+
+        board.release_all(7);
+        assert_eq!(board.holder((0, 0)), None);
+        assert!(board.is_free_for((0, 0), 3));
+    }
+
+    #[test]
+    fn reservations_are_cancelled_when_the_tile_changes() {
+        let mut map = map_with_ripe_crop_at((0, 0));
+        let mut board = JobBoard::default();
+        board.reserve((0, 0), 7, JobType::Harvest, 5);
+
+        map.dynamic_data.tiles.insert((0, 0), TileType::Grass);
+
+        assert_eq!(board.update(&map), vec![7]);
+        assert_eq!(board.holder((0, 0)), None);
+    }
+
+    #[test]
+    fn open_jobs_follow_the_touched_tiles() {
+        let mut map = map_with_ripe_crop_at((0, 0));
+        let mut board = JobBoard::default();
+        board.sync(&mut map);
+        assert!(board.has_free_job(JobType::Harvest, 7));
+
+        map.dynamic_data
+            .tiles
+            .insert((0, 0), TileType::Fallow { crop: 0 });
+        map.touch((0, 0));
+        board.sync(&mut map);
+        assert!(!board.has_free_job(JobType::Harvest, 7));
+        assert!(board.has_free_job(JobType::Replant, 7));
+
+        // crops ripening on their own are reported by the map
+        map.dynamic_data
+            .tiles
+            .insert((1, 1), TileType::Farmland { crop: 0, stage: 0 });
+        let upgrade_handler = UpgradeHandler::new(&load_language("en"), &ContentIds::load(), None);
+        let mut weather = Weather::new(0);
+        let ripe = ripe_crop(&map);
+        while map.dynamic_data.tiles[&(1, 1)] != ripe {
+            weather.update();
+            map.update_tiles(&upgrade_handler, &weather);
+        }
+        board.sync(&mut map);
+        assert!(board.has_free_job(JobType::Harvest, 7));
+    }
+}
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use serde::Deserialize;
 
-use crate::utils::parse_json;
+use crate::{common::parse_json, mods};
 
 #[derive(Deserialize)]
 pub struct LocaleHandler {
@@ -20,6 +20,29 @@ impl LocaleHandler {
 
     pub fn set_locale(&mut self, code: String) {
         self.current_locale = code.clone();
-        self.language_data = parse_json(&format!("static/localizations/{}.json", code)).expect("no such language");
+        self.language_data = load_language(&code);
     }
+}
+
+/// The game's translations for a language with every content pack's translations for it on top
+pub fn load_language(code: &str) -> HashMap<String, String> {
+    let mut language_data: HashMap<String, String> = HashMap::new();
+
+    for dir in mods::asset_dirs("localizations") {
+        let path = dir.join(format!("{code}.json"));
+        if !path.exists() {
+            continue;
+        }
+
+        match parse_json::<HashMap<String, String>>(path.to_str().unwrap()) {
+            Ok(data) => language_data.extend(data),
+            Err(e) => println!("couldn't read {} ({e})", path.display()),
+        }
+    }
+
+    if language_data.is_empty() {
+        panic!("no such language");
+    }
+
+    language_data
 }
\ No newline at end of file
@@ -1,41 +1,93 @@
-use std::collections::HashMap;
-use std::fs;
+// without the gui feature only the headless simulation is built, and most of the farm's api is
+// only called from the window
+#![cfg_attr(not(feature = "gui"), allow(dead_code))]
 
+#[cfg(feature = "gui")]
+use std::{collections::HashMap, fs};
+
+#[cfg(feature = "gui")]
 use raylib::prelude::*;
 
-mod texture_handler;
-use crate::animal::AnimalHandler;
+use crate::content::ContentIds;
 use crate::localization::LocaleHandler;
-use crate::pause_menu::{ButtonState, GameSettigns, PauseMenu, PauseMenuState};
-use crate::texture_handler::TextureHandler;
-
+use crate::settings::GameSettigns;
+#[cfg(feature = "gui")]
+use crate::{
+    achievement_popup::AchievementPopup,
+    animal::AnimalHandler,
+    camera_controller::CameraController,
+    contracts_panel::ContractsPanel,
+    map::{Map, TILE_PIXEL_SIZE, TILE_SIZE},
+    market_panel::MarketPanel,
+    offline_progress::OfflineProgress,
+    pause_menu::{AUTOSAVE_INTERVALS, ButtonState, PauseMenu, PauseMenuState},
+    player::Player,
+    prestige_panel::PrestigePanel,
+    roster::RosterPanel,
+    sim::{Produce, SimEvent, Simulation},
+    stats::session_summary,
+    storage_panel::StoragePanel,
+    texture_handler::TextureHandler,
+    toolbar::{Canvas, MenuMode},
+    tutorial::Tutorial,
+    upgrades::UpgradeHandler,
+    worker::WorkerHandler,
+};
+
+mod achievements;
+mod animal;
+mod common;
+mod content;
+mod contracts;
+mod inventory;
+mod job_board;
+mod localization;
 mod map;
-use crate::map::{Map, TILE_PIXEL_SIZE, TILE_SIZE};
-
-mod camera_controller;
-use crate::camera_controller::CameraController;
-
+mod market;
+mod mods;
+mod pathfinding;
 mod player;
-use crate::player::Player;
-
+mod prestige;
+mod processing;
+mod save;
+mod settings;
+mod shop_actions;
+mod sim;
+mod stats;
+mod toolbar;
+mod tutorial;
+mod upgrades;
+mod utils;
+mod weather;
 mod worker;
-use crate::shop_ui::{Canvas, MenuMode};
-use crate::tutorial::Tutorial;
-use crate::upgrades::UpgradeHandler;
-use crate::worker::WorkerHandler;
 
+// the window, everything in here draws or plays something
+#[cfg(feature = "gui")]
+mod achievement_popup;
+#[cfg(feature = "gui")]
+mod camera_controller;
+#[cfg(feature = "gui")]
+mod contracts_panel;
+#[cfg(feature = "gui")]
+mod market_panel;
+#[cfg(feature = "gui")]
+mod offline_progress;
+#[cfg(feature = "gui")]
 mod pause_menu;
-mod shop_ui;
-
+#[cfg(feature = "gui")]
+mod prestige_panel;
+#[cfg(feature = "gui")]
 mod renderer;
-mod tutorial;
-mod utils;
-
-mod animal;
-
-mod upgrades;
-
-mod localization;
+#[cfg(feature = "gui")]
+mod roster;
+#[cfg(feature = "gui")]
+mod shop_ui;
+#[cfg(feature = "gui")]
+mod slot_picker;
+#[cfg(feature = "gui")]
+mod storage_panel;
+#[cfg(feature = "gui")]
+mod texture_handler;
 
 const SCREEN_WIDTH: i32 = 1280;
 const SCREEN_HEIGHT: i32 = 720;
@@ -45,6 +97,7 @@ const TILE_UPDATE_TIME: f32 = 0.5;
 const UI_BUTTON_SIZE: f32 = 60.;
 const UI_GAPS: f32 = 20.;
 
+#[cfg(feature = "gui")]
 fn init_shader(shader: &mut Shader, rl: &mut RaylibHandle) {
     let freq_xloc = shader.get_shader_location("freqX");
     let freq_yloc = shader.get_shader_location("freqY");
@@ -70,7 +123,85 @@ fn init_shader(shader: &mut Shader, rl: &mut RaylibHandle) {
     shader.set_shader_value(speed_yloc, speed_y);
 }
 
+#[cfg(feature = "gui")]
+fn play_event_sound(sounds: &HashMap<String, Sound<'_>>, event: &SimEvent) {
+    match event {
+        SimEvent::Harvest { produce, .. } | SimEvent::Sold { produce, .. } => {
+            let sound = match produce {
+                Produce::Crop(_) | Produce::Tree(_) => sounds
+                    .get(&format!("harvest{}", rand::random_range(0..5)))
+                    .unwrap(),
+                Produce::Animal(_) => sounds.get("grass").unwrap(),
+                Produce::Honey | Produce::Good(_) => return,
+            };
+            sound.set_pitch(rand::random_range(0.9..1.1));
+            sound.play();
+        }
+        SimEvent::LevelUp { .. } | SimEvent::AchievementUnlocked { .. } => {
+            sounds.get("level_up").unwrap().play();
+        }
+        SimEvent::WagesPaid { .. } => {}
+    }
+}
+
 fn main() {
+    save::install_panic_hook();
+    mods::report();
+    // every save and load maps content between ids and positions, so the ids are only read once
+    let content_ids = ContentIds::load();
+    content::report_problems(&content_ids);
+
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(index) = args.iter().position(|arg| arg == "--simulate") {
+        let ticks = args
+            .get(index + 1)
+            .and_then(|ticks| ticks.parse().ok())
+            .unwrap_or(1000);
+
+        // the slot to simulate can be given by name, otherwise it's the last played one
+        if let Err(e) = save::adopt_unslotted_save() {
+            println!("couldn't move the old save into a slot ({e})");
+        }
+        let slot = save::list_slots()
+            .into_iter()
+            .find(|slot| args.get(index + 2).is_none_or(|name| *name == slot.name));
+        let Ok(save) = slot.map_or(Ok(None), |slot| save::load(&slot, &content_ids)) else {
+            return;
+        };
+
+        let mut locale_handler = LocaleHandler::new();
+        locale_handler.set_locale(GameSettigns::new().language);
+        sim::run_headless(ticks, &locale_handler.language_data, &content_ids, save);
+        return;
+    }
+
+    if let Some(index) = args.iter().position(|arg| arg == "--benchmark-paths") {
+        let arg = |offset: usize, default: usize| {
+            args.get(index + offset)
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(default)
+        };
+
+        let mut locale_handler = LocaleHandler::new();
+        locale_handler.set_locale(GameSettigns::new().language);
+        sim::run_pathfinding_benchmark(
+            arg(1, 40),
+            arg(2, 20),
+            arg(3, 200),
+            &locale_handler.language_data,
+            &content_ids,
+        );
+        return;
+    }
+
+    #[cfg(feature = "gui")]
+    run_game(content_ids);
+    #[cfg(not(feature = "gui"))]
+    println!("built without the gui feature, only --simulate and --benchmark-paths work");
+}
+
+#[cfg(feature = "gui")]
+fn run_game(content_ids: ContentIds) {
     let (mut rl, thread) = raylib::init()
         .size(SCREEN_WIDTH, SCREEN_HEIGHT)
         .resizable()
@@ -80,35 +211,32 @@ fn main() {
     let rl_audio = RaylibAudio::init_audio_device().expect("error init audio device");
     let mut sounds = HashMap::new();
 
-    let filenames = fs::read_dir("static/sfx/").unwrap();
-    for filename in filenames {
-        let file = match filename {
-            Ok(f) => f,
-            Err(e) => panic!("couldn't load this particular sfx {e}"),
-        };
-
-        let name = file
-            .file_name()
-            .into_string()
-            .unwrap()
-            .split('.')
-            .next()
-            .unwrap()
-            .to_string();
-
-        let sound: Sound = rl_audio
-            .new_sound(file.path().to_str().unwrap())
-            .expect("error loading this particular sound");
-        sounds.insert(name, sound);
+    for dir in mods::asset_dirs("sfx") {
+        let filenames = fs::read_dir(dir).unwrap();
+        for filename in filenames {
+            let file = match filename {
+                Ok(f) => f,
+                Err(e) => panic!("couldn't load this particular sfx {e}"),
+            };
+
+            let name = file
+                .file_name()
+                .into_string()
+                .unwrap()
+                .split('.')
+                .next()
+                .unwrap()
+                .to_string();
+
+            let sound: Sound = rl_audio
+                .new_sound(file.path().to_str().unwrap())
+                .expect("error loading this particular sound");
+            sounds.insert(name, sound);
+        }
     }
 
     let texture_handler = TextureHandler::new(&mut rl, &thread);
     let mut camera_controller = CameraController::new();
-    let mut map = Map::new();
-    let mut player = Player::new();
-    let mut worker_handler = WorkerHandler::new();
-    let mut animal_handler = AnimalHandler::new();
-
     let mut game_settings = GameSettigns::new();
     rl_audio.set_master_volume(game_settings.master_volume);
     if game_settings.is_fullscreen && !rl.is_window_fullscreen() {
@@ -118,19 +246,12 @@ fn main() {
     let mut locale_handler = LocaleHandler::new();
     locale_handler.set_locale(game_settings.language.clone());
 
-    let mut canvas = Canvas::new(&locale_handler.language_data);
-    let mut upgrade_handler = UpgradeHandler::new(&locale_handler.language_data);
-
-    let mut pause_menu = PauseMenu::new(&mut rl, &locale_handler);
-
-    let mut tutorial = Tutorial::new(game_settings.language.clone());
-
     let font = rl
         .load_font_ex(
             &thread,
             "static/tilita.ttf",
             32,
-            Some("ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyzАБВГДЕЁЖЗИЙКЛМНОПРСТУФХЦЧШЩЪЫЬЭЮЯабвгдеёжзийклмнопрстуфхцчшщъыьэюя0123456789+-%[](),.:!?/"),
+            Some("ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyzАБВГДЕЁЖЗИЙКЛМНОПРСТУФХЦЧШЩЪЫЬЭЮЯабвгдеёжзийклмнопрстуфхцчшщъыьэюя0123456789+-_%[](),.:!?/"),
         )
         .expect("no font???");
 
@@ -142,6 +263,41 @@ fn main() {
 
     rl.set_exit_key(None);
 
+    let Some((slot, mut save)) = slot_picker::pick_slot(
+        &mut rl,
+        &thread,
+        &font,
+        &locale_handler,
+        &game_settings,
+        &content_ids,
+    ) else {
+        return;
+    };
+
+    let mut play_time = save.as_ref().map_or(0., |save| save.play_time as f64);
+    let saved_at = save.as_ref().map(|save| save.saved_at);
+    let toolbar_save = save.as_mut().and_then(|save| save.toolbar.take());
+    let is_new_game = save.is_none();
+
+    let mut canvas = Canvas::new(&locale_handler.language_data, toolbar_save);
+    let mut sim = Simulation::new(&locale_handler.language_data, &content_ids, save);
+
+    let mut pause_menu = PauseMenu::new(&mut rl, &locale_handler);
+
+    let mut tutorial = Tutorial::new(game_settings.language.clone(), is_new_game);
+
+    let mut offline_progress = OfflineProgress::catch_up(&mut sim, saved_at);
+
+    // whatever happened while the game was closed isn't part of the session
+    let session_start = sim.player.stats.clone();
+    let mut achievement_popup = AchievementPopup::new();
+
+    let mut roster = RosterPanel::new();
+    let mut storage_panel = StoragePanel::new();
+    let mut market_panel = MarketPanel::new();
+    let mut contracts_panel = ContractsPanel::new();
+    let mut prestige_panel = PrestigePanel::new();
+
     let image = Image::gen_image_checked(
         SCREEN_WIDTH,
         SCREEN_HEIGHT,
@@ -158,14 +314,33 @@ fn main() {
 
     let seconds_loc = shader.get_shader_location("seconds");
     let mut seconds = 0.;
+    let tint_loc = shader.get_shader_location("tint");
+    let waves_loc = shader.get_shader_location("waves");
 
     let mut timer = 0.0;
 
+    let mut ticks_since_save = 0;
+    // set when the player bought, sold or built something. What the farm does on its own is caught
+    // up on by the offline progress after a crash, so only those changes need a snapshot
+    let mut player_acted = false;
+
+    save::keep_emergency_snapshot(
+        &slot,
+        &sim,
+        &canvas.toolbar_data.dynamic_data,
+        play_time as u64,
+        &content_ids,
+    );
+
     while !rl.window_should_close() {
         seconds += rl.get_frame_time();
+        play_time += rl.get_frame_time() as f64;
+        sim.player.stats.play_time = play_time as u64;
         timer += rl.get_frame_time();
 
         shader.set_shader_value(seconds_loc, seconds);
+        shader.set_shader_value(tint_loc, sim.weather.shader_tint());
+        shader.set_shader_value(waves_loc, sim.weather.current().waves);
 
         pause_menu.toggle_pause(&mut rl, &locale_handler);
         let pause_blocks_mouse = pause_menu.update_buttons(&mut rl, &locale_handler);
@@ -173,13 +348,22 @@ fn main() {
         match pause_menu.state {
             PauseMenuState::Main => {
                 // todo: replace with a map maybe
-                if pause_menu.buttons[1].state == ButtonState::Pressed {
+                if pause_menu.buttons[3].state == ButtonState::Pressed {
                     break;
                 }
 
                 if pause_menu.buttons[0].state == ButtonState::Pressed {
                     pause_menu.switch_state(&mut rl, PauseMenuState::Settings, &locale_handler);
                 }
+
+                if pause_menu.buttons[1].state == ButtonState::Pressed {
+                    pause_menu.backups = save::list_backups(&slot);
+                    pause_menu.switch_state(&mut rl, PauseMenuState::Backups, &locale_handler);
+                }
+
+                if pause_menu.buttons[2].state == ButtonState::Pressed {
+                    pause_menu.switch_state(&mut rl, PauseMenuState::Achievements, &locale_handler);
+                }
             }
             PauseMenuState::Settings => {
                 let format = if game_settings.short_numbers {
@@ -200,6 +384,12 @@ fn main() {
                     locale_handler.language_data.get("number_display").unwrap(),
                     format,
                 );
+                pause_menu.buttons[5].label = format!(
+                    "{}: {} {}",
+                    locale_handler.language_data.get("autosave").unwrap(),
+                    game_settings.autosave_interval,
+                    locale_handler.language_data.get("seconds").unwrap(),
+                );
 
                 if pause_menu.buttons[0].state == ButtonState::Pressed {
                     let new_volume = (rl_audio.get_master_volume() - 0.1).max(0.);
@@ -231,20 +421,87 @@ fn main() {
                     pause_menu.switch_state(&mut rl, pause_menu.state, &locale_handler);
                     game_settings.language = codes[index].clone();
                     canvas.reload_toolbar_static(&locale_handler.language_data);
-                    upgrade_handler.reload_static(&locale_handler.language_data);
+                    sim.upgrade_handler.translate(&locale_handler.language_data);
                 }
                 if pause_menu.buttons[4].state == ButtonState::Pressed {
                     rl.toggle_fullscreen();
                     game_settings.is_fullscreen = rl.is_window_fullscreen();
                 }
                 if pause_menu.buttons[5].state == ButtonState::Pressed {
+                    let index = AUTOSAVE_INTERVALS
+                        .iter()
+                        .position(|interval| *interval == game_settings.autosave_interval)
+                        .map_or(0, |index| (index + 1) % AUTOSAVE_INTERVALS.len());
+                    game_settings.autosave_interval = AUTOSAVE_INTERVALS[index];
+                }
+                if pause_menu.buttons[6].state == ButtonState::Pressed {
                     game_settings.save();
                     pause_menu.switch_state(&mut rl, PauseMenuState::Main, &locale_handler);
                 }
             }
+            PauseMenuState::Backups => {
+                let back_index = pause_menu.buttons.len() - 1;
+                if pause_menu.buttons[back_index].state == ButtonState::Pressed {
+                    pause_menu.switch_state(&mut rl, PauseMenuState::Main, &locale_handler);
+                }
+
+                let pressed = pause_menu.buttons[..back_index]
+                    .iter()
+                    .position(|button| button.state == ButtonState::Pressed);
+
+                if let Some(mut save) = pressed
+                    .and_then(|index| save::load_backup(&pause_menu.backups[index], &content_ids))
+                {
+                    canvas.reload_toolbar_dynamic(save.toolbar.take());
+                    sim = Simulation::new(&locale_handler.language_data, &content_ids, Some(save));
+                    if let Err(e) = save::write(
+                        &slot,
+                        &sim,
+                        &canvas.toolbar_data.dynamic_data,
+                        play_time as u64,
+                        &content_ids,
+                    ) {
+                        println!("couldn't save the restored backup ({e})");
+                    }
+
+                    pause_menu.is_paused = false;
+                    pause_menu.switch_state(&mut rl, PauseMenuState::Main, &locale_handler);
+                }
+            }
+            PauseMenuState::Achievements => {
+                // the farm keeps going while the menu is open
+                pause_menu.achievements = sim.achievements.lines(&sim.player, &locale_handler);
+                pause_menu.stats = sim.player.stats.lines(
+                    &sim.map,
+                    &sim.animal_handler,
+                    &locale_handler,
+                    &game_settings,
+                );
+
+                if pause_menu.buttons[0].state == ButtonState::Pressed {
+                    let summary =
+                        session_summary(&sim, &session_start, &locale_handler, &game_settings);
+                    match save::write_summary(&slot, &summary) {
+                        Ok(path) => {
+                            let lang = &locale_handler.language_data;
+                            pause_menu.buttons[0].label = format!(
+                                "{} {}",
+                                lang.get("summary_exported").unwrap(),
+                                path.file_name().unwrap_or_default().to_string_lossy()
+                            );
+                        }
+                        Err(e) => println!("couldn't export the summary ({e})"),
+                    }
+                }
+                if pause_menu.buttons[1].state == ButtonState::Pressed {
+                    pause_menu.switch_state(&mut rl, PauseMenuState::Main, &locale_handler);
+                }
+            }
         }
 
-        camera_controller.update_position(&mut rl, &mut tutorial);
+        if !roster.is_renaming() {
+            camera_controller.update_position(&mut rl, &mut tutorial);
+        }
 
         let world_pos = rl.get_screen_to_world2D(rl.get_mouse_position(), camera_controller.camera);
         let selected_tile = (
@@ -252,54 +509,102 @@ fn main() {
             (world_pos.y / TILE_SIZE as f32).floor() as i32,
         );
 
-        if !pause_blocks_mouse {
-            handle_input(
+        offline_progress.close(&mut rl);
+
+        if !pause_blocks_mouse && !offline_progress.blocks_input() {
+            roster.update(&mut rl, &mut sim.worker_handler, &locale_handler);
+        }
+
+        if !pause_blocks_mouse && !offline_progress.blocks_input() && !roster.is_renaming() {
+            player_acted |=
+                storage_panel.update(&mut rl, &mut sim, &locale_handler, &game_settings);
+            market_panel.update(&mut rl, &sim, &locale_handler);
+            player_acted |=
+                contracts_panel.update(&mut rl, &mut sim, &locale_handler, &game_settings);
+            if prestige_panel.update(&mut rl, &mut sim, &locale_handler, &game_settings) {
+                canvas.reload_toolbar_dynamic(None);
+                player_acted = true;
+            }
+        }
+
+        if !pause_blocks_mouse
+            && !offline_progress.blocks_input()
+            && !roster.blocks_mouse(rl.get_mouse_position())
+            && !storage_panel.blocks_mouse(rl.get_mouse_position())
+            && !market_panel.blocks_mouse(rl.get_mouse_position())
+            && !contracts_panel.blocks_mouse(rl.get_mouse_position())
+            && !prestige_panel.blocks_mouse(rl.get_mouse_position())
+            && !roster.is_renaming()
+        {
+            player_acted |= handle_input(
                 &mut rl,
                 &mut canvas,
-                &upgrade_handler,
-                &mut map,
-                &mut player,
-                &mut worker_handler,
-                &mut animal_handler,
+                &sim.upgrade_handler,
+                &mut sim.map,
+                &mut sim.player,
+                &mut sim.worker_handler,
+                &mut sim.animal_handler,
                 selected_tile,
                 &mut tutorial,
             );
         }
 
-        player.update_money();
-        player.update_exp(&sounds);
-
         tutorial.close_tutorial(&mut rl);
+        achievement_popup.update(&rl);
 
         // call on tick
         if timer >= TILE_UPDATE_TIME {
             timer = 0.;
 
-            map.update_tiles(
-                &upgrade_handler,
-                animal_handler.static_data.animal_data.len(),
-            );
+            sim.tick();
+            let events: Vec<SimEvent> = sim.drain_events().collect();
+            for event in events {
+                play_event_sound(&sounds, &event);
+                if let SimEvent::AchievementUnlocked { index } = event {
+                    achievement_popup.push(sim.achievements.name(index, &locale_handler));
+                }
+            }
 
-            worker_handler.advance_workers(
-                &mut player,
-                &mut map,
-                &animal_handler,
-                &upgrade_handler,
-                &sounds,
-            );
-            animal_handler.move_animals(&mut map);
+            // the autosave keeps its own snapshot
+            ticks_since_save += 1;
+            if ticks_since_save as f32 * TILE_UPDATE_TIME >= game_settings.autosave_interval as f32
+            {
+                ticks_since_save = 0;
+                if let Err(e) = save::write(
+                    &slot,
+                    &sim,
+                    &canvas.toolbar_data.dynamic_data,
+                    play_time as u64,
+                    &content_ids,
+                ) {
+                    println!("autosave failed ({e})");
+                }
+            } else if player_acted {
+                save::keep_emergency_snapshot(
+                    &slot,
+                    &sim,
+                    &canvas.toolbar_data.dynamic_data,
+                    play_time as u64,
+                    &content_ids,
+                );
+            }
+            player_acted = false;
         }
 
+        // the shop tooltip shows whether the tile under the mouse is in a pen
+        sim.map.update_pens();
+
         let mut d = rl.begin_drawing(&thread);
 
         renderer::draw_bg(&mut d, &mut shader, &bg_texture);
         renderer::draw_for_camera(
             &mut d,
-            &map,
+            &sim.map,
+            &canvas,
             &camera_controller,
             &texture_handler,
-            &mut worker_handler,
-            &mut animal_handler,
+            &mut sim.worker_handler,
+            &mut sim.animal_handler,
             &font,
             selected_tile,
             &game_settings,
@@ -308,13 +613,21 @@ fn main() {
         renderer::draw_fg(
             &mut d,
             &mut canvas, // basically a shop ui at this point, as i've separated the ui
-            &mut upgrade_handler,
-            &map,
-            &animal_handler,
+            &mut sim.upgrade_handler,
+            &sim.map,
+            &sim.animal_handler,
             &texture_handler,
-            &mut player,
+            &mut sim.player,
             &pause_menu,
+            &roster,
+            &storage_panel,
+            &market_panel,
+            &contracts_panel,
+            &prestige_panel,
+            &achievement_popup,
             &tutorial,
+            &offline_progress,
+            &sim.weather,
             &font,
             &locale_handler,
             rl_audio.get_master_volume(),
@@ -323,14 +636,26 @@ fn main() {
         );
     }
 
-    canvas.toolbar_data.save();
-    upgrade_handler.save();
-    worker_handler.save();
-    animal_handler.save();
-    player.save();
-    map.save();
+    save::forget_emergency_snapshot();
+    if let Err(e) = save::write(
+        &slot,
+        &sim,
+        &canvas.toolbar_data.dynamic_data,
+        play_time as u64,
+        &content_ids,
+    ) {
+        println!("couldn't save the game ({e})");
+    }
+
+    let summary = session_summary(&sim, &session_start, &locale_handler, &game_settings);
+    match save::write_summary(&slot, &summary) {
+        Ok(path) => println!("session summary written to {}", path.display()),
+        Err(e) => println!("couldn't write the session summary ({e})"),
+    }
 }
 
+/// Uses the selected shop tool on the tile under the mouse, returns whether it changed the farm
+#[cfg(feature = "gui")]
 fn handle_input(
     rl: &mut RaylibHandle,
     canvas: &mut Canvas,
@@ -341,29 +666,72 @@ fn handle_input(
     animal_handler: &mut AnimalHandler,
     selected_tile: (i32, i32),
     tutorial: &mut Tutorial,
-) {
-    if !canvas.blocks_mouse(rl.get_mouse_position())
-        && !upgrade_handler.ui_blocks_mouse
-        && rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+) -> bool {
+    if rl.is_key_pressed(KeyboardKey::KEY_V) {
+        canvas.climate_overlay = !canvas.climate_overlay;
+    }
+
+    // debug view of which worker is headed where
+    if rl.is_key_pressed(KeyboardKey::KEY_F3) {
+        canvas.job_board_overlay = !canvas.job_board_overlay;
+    }
+
+    if canvas.blocks_mouse(rl.get_mouse_position()) || upgrade_handler.ui_blocks_mouse {
+        return false;
+    }
+
+    // holding shift marks fields with blueprints for workers to plant, dragging marks a whole area
+    let planning = canvas.mode == MenuMode::Crops && rl.is_key_down(KeyboardKey::KEY_LEFT_SHIFT);
+    if planning && rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT) {
+        return player.plant_crops(canvas, map, &selected_tile, tutorial, upgrade_handler, true);
+    }
+
+    // fences go up along wherever the mouse is dragged
+    let fencing = canvas.selected_misc() == Some("fence");
+    if fencing
+        && rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT)
+        && !rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
     {
-        match canvas.mode {
-            MenuMode::Crops => {
-                player.plant_crops(canvas, map, &selected_tile, tutorial);
-            }
-            MenuMode::Trees => {
-                player.plant_trees(canvas, map, &selected_tile);
-            }
-            MenuMode::Animals => {
-                player.spawn_animals(canvas, map, &selected_tile, animal_handler);
-            }
-            MenuMode::Beekeeping => {
-                player.perform_beekeeping(canvas, &selected_tile, map);
-            }
-            MenuMode::Misc => {
-                player.perform_misc(canvas, worker_handler, &selected_tile, map);
-            }
-        }
+        return player.perform_misc(
+            canvas,
+            worker_handler,
+            animal_handler,
+            &selected_tile,
+            map,
+            upgrade_handler,
+        );
+    }
 
-        map.buy_land(selected_tile, player);
+    if !rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+        return false;
     }
+
+    let changed = match canvas.mode {
+        MenuMode::Crops => player.plant_crops(
+            canvas,
+            map,
+            &selected_tile,
+            tutorial,
+            upgrade_handler,
+            false,
+        ),
+        MenuMode::Trees => player.plant_trees(canvas, map, &selected_tile, upgrade_handler),
+        MenuMode::Animals => {
+            player.spawn_animals(canvas, map, &selected_tile, animal_handler, upgrade_handler)
+        }
+        MenuMode::Beekeeping => {
+            player.perform_beekeeping(canvas, &selected_tile, map, upgrade_handler)
+        }
+        MenuMode::Misc => player.perform_misc(
+            canvas,
+            worker_handler,
+            animal_handler,
+            &selected_tile,
+            map,
+            upgrade_handler,
+        ),
+        MenuMode::Buildings => player.place_building(canvas, animal_handler, &selected_tile, map),
+    };
+
+    map.buy_land(selected_tile, player) || changed
 }
@@ -1,18 +1,20 @@
 use itertools::Itertools;
 use noise::{NoiseFn, Perlin};
-use raylib::prelude::*;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
-use std::collections::HashMap;
+use std::{
+    cell::Cell,
+    collections::{HashMap, HashSet},
+};
 
 use crate::{
-    animal::AnimalHandler,
-    localization::LocaleHandler,
-    pause_menu::GameSettigns,
+    common::parse_json,
+    inventory::Inventory,
+    mods,
     player::Player,
-    upgrades::UpgradeHandler,
-    utils::{parse_json, shrink_number_for_display},
-    worker::WorkerHandler,
+    processing::{BuildingState, Recipes},
+    upgrades::{UpgradeHandler, UpgradeTarget},
+    weather::Weather,
 };
 
 pub const CHUNK_WIDTH: usize = 5;
@@ -22,19 +24,26 @@ pub const TILE_SCALE: i32 = 4;
 
 pub const TILE_SIZE: i32 = TILE_PIXEL_SIZE * TILE_SCALE;
 
+// anything bigger than this is a field with a fence somewhere, not a pen
+const MAX_PEN_TILES: usize = 100;
+pub const TROUGH_CAPACITY: usize = 10;
+// goods each storage building holds
+pub const STORAGE_CAPACITY: usize = 100;
+
 #[derive(Deserialize, PartialEq, Default, Copy, Clone)]
 pub enum Climate {
-	#[default]
-	Unapplicable,
-	Cold,
+    #[default]
+    Unapplicable,
+    Cold,
     Temperate,
     Warm,
 }
 
 #[derive(Deserialize)]
 pub struct Crop {
+    pub id: String,
     pub time_to_grow: usize,
-    grow_step: usize,
+    pub grow_step: usize,
     pub climate: Climate,
     pub sell_price: usize,
     pub exp: usize,
@@ -42,8 +51,9 @@ pub struct Crop {
 
 #[derive(Deserialize)]
 pub struct Tree {
+    pub id: String,
     pub time_to_grow: usize,
-    grow_step: usize,
+    pub grow_step: usize,
     pub climate: Climate,
     pub time_to_fruit: usize,
     pub sell_price: usize,
@@ -52,6 +62,7 @@ pub struct Tree {
 
 #[derive(Deserialize)]
 pub struct Beehive {
+    pub id: String,
     pub time_to_honey: usize,
     pub sell_price: usize,
     pub exp: usize,
@@ -59,11 +70,14 @@ pub struct Beehive {
 
 #[derive(Deserialize)]
 pub struct Flower {
+    pub id: String,
+    #[serde(default)]
+    pub climate: Climate,
     pub sell_price: usize,
     pub exp: usize,
 }
 
-#[derive(PartialEq, PartialOrd, Eq, Ord, Serialize, Deserialize, Copy, Clone)]
+#[derive(PartialEq, PartialOrd, Eq, Ord, Serialize, Deserialize, Copy, Clone, Debug)]
 pub enum TileType {
     Grass,
     Tree {
@@ -75,6 +89,14 @@ pub enum TileType {
         crop: usize,
         stage: usize,
     },
+    // harvested, waiting for a worker to plant the same crop again
+    Fallow {
+        crop: usize,
+    },
+    // paid for and marked by the player, waiting for a worker to plant it
+    Blueprint {
+        crop: usize,
+    },
     AnimalDrop {
         animal: usize,
     },
@@ -86,6 +108,24 @@ pub enum TileType {
     Flower {
         flower: usize,
     },
+    // where tired workers get their stamina back
+    RestHut,
+    // keeps animals in and workers out
+    Fence,
+    // keeps animals in but lets workers through
+    Gate,
+    // holds one kind of crop at a time for the animals in its pen
+    Trough {
+        crop: Option<usize>,
+        amount: usize,
+    },
+    // each one adds STORAGE_CAPACITY to how many goods the farm can keep
+    Storage,
+    // one tile of a building, its stock is kept with the tile in the top left corner
+    Building {
+        building: usize,
+        origin: (i32, i32),
+    },
 }
 
 #[derive(Deserialize)]
@@ -101,43 +141,181 @@ pub struct MapStaticData {
 pub struct MapDynamicData {
     #[serde_as(as = "Vec<(_, _)>")]
     pub tiles: HashMap<(i32, i32), TileType>,
-    #[serde_as(as = "Vec<(_, _)>")]
-    pub occupation_map: HashMap<(i32, i32), bool>,
     land_expansion_points: Vec<(i32, i32)>,
-    next_expansion_cost: usize,
+    pub next_expansion_cost: usize,
     biome_seed: u32,
+    // by the top left corner of each building
+    #[serde_as(as = "Vec<(_, _)>")]
+    #[serde(default)]
+    pub buildings: HashMap<(i32, i32), BuildingState>,
+}
+
+/// How climates are spread over the island, read from static/biomes.json
+#[derive(Deserialize)]
+struct BiomeSettings {
+    // smaller values make bigger biomes
+    noise_scale: f64,
+    cold_below: f64,
+    warm_above: f64,
+    // added to the noise for every tile to the south, so the south gets warmer and the north colder
+    #[serde(default)]
+    latitude: f64,
+}
+
+pub struct Biomes {
+    settings: BiomeSettings,
+    noise: Perlin,
+}
+
+impl Biomes {
+    fn new(seed: u32) -> Self {
+        Self {
+            settings: parse_json("static/biomes.json").expect("no biome settings"),
+            noise: Perlin::new(seed),
+        }
+    }
+
+    pub fn climate_at(&self, position: (i32, i32)) -> Climate {
+        let sample = self.noise.get([
+            position.0 as f64 * self.settings.noise_scale,
+            position.1 as f64 * self.settings.noise_scale,
+        ]) + position.1 as f64 * self.settings.latitude;
+
+        if sample < self.settings.cold_below {
+            Climate::Cold
+        } else if sample > self.settings.warm_above {
+            Climate::Warm
+        } else {
+            Climate::Temperate
+        }
+    }
+
+    /// Anything produced on a tile with the climate it likes is worth double
+    pub fn climate_multiplier(&self, position: (i32, i32), climate: Climate) -> usize {
+        if self.climate_at(position) == climate {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+/// Which tiles are in which pen and where each pen's troughs are. Worked out once for the whole island
+/// and again only after the layout changed, instead of for every animal that gets hungry
+#[derive(Default)]
+struct Pens {
+    up_to_date: bool,
+    pen_of: HashMap<(i32, i32), usize>,
+    tiles: Vec<HashSet<(i32, i32)>>,
+    troughs: Vec<Vec<(i32, i32)>>,
+    // troughs in and out of pens, the ones workers can bring crops to
+    every_trough: Vec<(i32, i32)>,
+}
+
+impl Pens {
+    fn find(tiles: &HashMap<(i32, i32), TileType>) -> Self {
+        let mut pens = Self {
+            up_to_date: true,
+            every_trough: tiles
+                .iter()
+                .filter(|(_, tile)| matches!(tile, TileType::Trough { .. }))
+                .map(|(position, _)| *position)
+                .collect(),
+            ..Default::default()
+        };
+        let mut seen = HashSet::new();
+
+        for (start, tile) in tiles {
+            if is_wall(tile) || !seen.insert(*start) {
+                continue;
+            }
+
+            // the whole area the tile is in, so every tile of it is only looked at once
+            let mut area = HashSet::from([*start]);
+            let mut open = false;
+            let mut queue = vec![*start];
+            while let Some(current) = queue.pop() {
+                for direction in [(0, 1), (1, 0), (0, -1), (-1, 0)] {
+                    let next = (current.0 + direction.0, current.1 + direction.1);
+                    match tiles.get(&next) {
+                        None => open = true,
+                        Some(tile) if is_wall(tile) => {}
+                        Some(_) => {
+                            if seen.insert(next) {
+                                area.insert(next);
+                                queue.push(next);
+                            }
+                        }
+                    }
+                }
+            }
+
+            if open || area.len() > MAX_PEN_TILES {
+                continue;
+            }
+
+            let index = pens.tiles.len();
+            pens.pen_of
+                .extend(area.iter().map(|position| (*position, index)));
+            pens.troughs.push(
+                area.iter()
+                    .copied()
+                    .filter(|position| matches!(tiles[position], TileType::Trough { .. }))
+                    .collect(),
+            );
+            pens.tiles.push(area);
+        }
+
+        pens
+    }
+}
+
+fn is_wall(tile: &TileType) -> bool {
+    matches!(tile, TileType::Fence | TileType::Gate)
 }
 
 pub struct Map {
     pub static_data: MapStaticData,
     pub dynamic_data: MapDynamicData,
-    pub noise: Perlin,
+    pub biomes: Biomes,
+    pub recipes: Recipes,
+    // whether a building is short on something that's in storage, updated every tick
+    pub supplies_wanted: bool,
+    // tiles built on, cleared or added since the last tick, for whatever keeps track of the layout
+    pub changed_tiles: Vec<(i32, i32)>,
+    // tiles that may need a different job done since the job board last looked, it takes them every tick
+    pub touched_tiles: Vec<(i32, i32)>,
+    pens: Pens,
+    // how many storage tiles there are, counted again only after the layout changed
+    storage_tiles: Cell<Option<usize>>,
 }
 
 impl Map {
-    pub fn new() -> Self {
+    pub fn new(dynamic_data: Option<MapDynamicData>) -> Self {
         let static_data: MapStaticData =
-            parse_json("static/tiles.json").expect("Can't deserialize");
-        let dynamic_data = parse_json::<MapDynamicData>("dynamic/map_save.json");
-
-        match dynamic_data {
-            Ok(dynamic_data) => {
-                let seed = dynamic_data.biome_seed;
-                return Self {
-                    static_data,
-                    dynamic_data: dynamic_data,
-                    noise: Perlin::new(seed),
-                };
-            }
-            Err(_) => {}
-        };
+            mods::load_content("tiles.json").expect("Can't deserialize");
+
+        if let Some(dynamic_data) = dynamic_data {
+            let seed = dynamic_data.biome_seed;
+            return Self {
+                static_data,
+                dynamic_data,
+                biomes: Biomes::new(seed),
+                recipes: Recipes::load(),
+                supplies_wanted: false,
+                changed_tiles: vec![],
+                touched_tiles: vec![],
+                pens: Pens::default(),
+                storage_tiles: Cell::new(None),
+            };
+        }
 
         let mut dynamic_data = MapDynamicData {
             tiles: HashMap::new(),
-            occupation_map: HashMap::new(),
             land_expansion_points: vec![],
             next_expansion_cost: 1000,
             biome_seed: rand::random::<u32>(),
+            buildings: HashMap::new(),
         };
 
         let half_width = CHUNK_WIDTH as i32 / 2;
@@ -163,52 +341,115 @@ impl Map {
         Self {
             static_data,
             dynamic_data,
-            noise: Perlin::new(seed),
+            biomes: Biomes::new(seed),
+            recipes: Recipes::load(),
+            supplies_wanted: false,
+            changed_tiles: vec![],
+            touched_tiles: vec![],
+            pens: Pens::default(),
+            storage_tiles: Cell::new(None),
         }
     }
 
-    pub fn update_tiles(&mut self, upgrade_handler: &UpgradeHandler, animals_len: usize) {
+    /// Notes a tile that was built on or cleared, changing how workers can get around and where the pens are
+    pub fn mark_changed(&mut self, position: (i32, i32)) {
+        self.changed_tiles.push(position);
+        self.touch(position);
+        self.pens.up_to_date = false;
+        self.storage_tiles.set(None);
+    }
+
+    /// Notes a tile whose job may have changed while the layout stayed the same, like a crop that ripened
+    pub fn touch(&mut self, position: (i32, i32)) {
+        self.touched_tiles.push(position);
+    }
+
+    /// Works out the pens again if the layout changed since they were last looked at
+    pub fn update_pens(&mut self) {
+        if !self.pens.up_to_date {
+            self.pens = Pens::find(&self.dynamic_data.tiles);
+        }
+    }
+
+    /// The pen a tile is in, as of the last `update_pens`
+    pub fn pen_at(&self, position: (i32, i32)) -> Option<&HashSet<(i32, i32)>> {
+        self.pens
+            .pen_of
+            .get(&position)
+            .map(|index| &self.pens.tiles[*index])
+    }
+
+    pub fn climate_at(&self, position: (i32, i32)) -> Climate {
+        self.biomes.climate_at(position)
+    }
+
+    pub fn update_tiles(&mut self, upgrade_handler: &UpgradeHandler, weather: &Weather) {
         let map_tiles = self.dynamic_data.tiles.clone();
+        // tiles that ripened or lost their harvest
+        let mut touched = vec![];
 
         for (tile_pos, tile) in self.dynamic_data.tiles.iter_mut() {
+            // one per tile, so the order the tiles come in doesn't matter
+            let mut rng = weather.rng_at(*tile_pos);
             match tile {
                 TileType::Farmland { crop, stage } => {
-                    if *stage >= self.static_data.crops_data[*crop].time_to_grow {
+                    let crop_data = &self.static_data.crops_data[*crop];
+                    // frost spoils ripe crops as well as growing ones
+                    if weather.harms(crop_data.climate, &mut rng) {
+                        *stage = 0;
+                        touched.push(*tile_pos);
+                        continue;
+                    }
+
+                    if *stage >= crop_data.time_to_grow {
                         // wait for collect
                         continue;
                     }
 
-                    *stage += 1;
+                    let speed = upgrade_handler.growth_speed(UpgradeTarget::Crop(*crop))
+                        * weather.growth_steps(crop_data.climate, &mut rng);
+                    *stage = (*stage + speed).min(crop_data.time_to_grow);
+                    if *stage >= crop_data.time_to_grow {
+                        touched.push(*tile_pos);
+                    }
                 }
                 TileType::Tree { tree, grow, stage } => {
-                    if *stage >= self.static_data.tree_data[*tree].time_to_fruit
-                        && *grow >= self.static_data.tree_data[*tree].time_to_grow
+                    let tree_data = &self.static_data.tree_data[*tree];
+                    // the tree itself weathers anything, only the fruit is lost
+                    if *stage > 0
+                        && (weather.harms(tree_data.climate, &mut rng)
+                            || weather.knocks_fruit_off(&mut rng))
                     {
+                        *stage = 0;
+                        touched.push(*tile_pos);
+                        continue;
+                    }
+
+                    if *stage >= tree_data.time_to_fruit && *grow >= tree_data.time_to_grow {
                         continue;
                     }
 
-                    if *grow >= self.static_data.tree_data[*tree].time_to_grow {
-                        *stage += 1;
+                    let speed = upgrade_handler.growth_speed(UpgradeTarget::Tree(*tree))
+                        * weather.growth_steps(tree_data.climate, &mut rng);
+                    if *grow >= tree_data.time_to_grow {
+                        *stage = (*stage + speed).min(tree_data.time_to_fruit);
+                        if *stage >= tree_data.time_to_fruit {
+                            touched.push(*tile_pos);
+                        }
                         continue;
                     }
 
-                    *grow += 1;
+                    *grow = (*grow + speed).min(tree_data.time_to_grow);
                 }
                 TileType::Beehive { stage, price, xp } => {
                     if *stage >= self.static_data.hive_data[0].time_to_honey {
                         continue;
                     }
 
-                    *stage += 1;
+                    *stage = (*stage + upgrade_handler.growth_speed(UpgradeTarget::Hive))
+                        .min(self.static_data.hive_data[0].time_to_honey);
                     if *stage >= self.static_data.hive_data[0].time_to_honey {
-                        let crops_len = self.static_data.crops_data.len();
-                        let trees_len = self.static_data.tree_data.len();
-                        let multiplier = upgrade_handler.get_multiplier_for_beehive(
-                            crops_len,
-                            trees_len,
-                            animals_len,
-                        );
-
+                        touched.push(*tile_pos);
                         *price = self.static_data.hive_data[0].sell_price;
                         *xp = self.static_data.hive_data[0].exp;
                         for i in -1..=1 {
@@ -222,14 +463,18 @@ impl Map {
                                     continue;
                                 };
 
-                                match neighbour {
-                                    TileType::Flower { flower } => {
-                                        *price += self.static_data.flower_data[*flower].sell_price
-                                            * multiplier;
-                                        *xp +=
-                                            self.static_data.flower_data[*flower].exp * multiplier;
-                                    }
-                                    _ => {}
+                                if let TileType::Flower { flower } = neighbour {
+                                    let flower_data = &self.static_data.flower_data[*flower];
+                                    let (flower_price, flower_xp) = upgrade_handler.apply(
+                                        UpgradeTarget::Hive,
+                                        flower_data.sell_price,
+                                        flower_data.exp,
+                                    );
+                                    let multiplier = self
+                                        .biomes
+                                        .climate_multiplier(neighbour_pos, flower_data.climate);
+                                    *price += flower_price * multiplier;
+                                    *xp += flower_xp * multiplier;
                                 }
                             }
                         }
@@ -238,11 +483,182 @@ impl Map {
                 _ => {}
             }
         }
+
+        self.touched_tiles.extend(touched);
+    }
+
+    /// Runs every building for a tick and returns the exp for the batches finished
+    pub fn update_buildings(&mut self, inventory: &Inventory) -> usize {
+        let mut exp = 0;
+        let supplies_were_wanted = self.supplies_wanted;
+        self.supplies_wanted = false;
+        // buildings that just got something to pick up
+        let mut stocked = vec![];
+
+        for (origin, state) in self.dynamic_data.buildings.iter_mut() {
+            let building = &self.recipes.buildings[state.building];
+            let had_output = state.output > 0;
+            exp += state.update(building, &self.recipes.goods);
+            self.supplies_wanted |= state.wanted_input(building, inventory).is_some();
+
+            if !had_output && state.output > 0 {
+                stocked.push((state.building, *origin));
+            }
+        }
+
+        for (building, origin) in stocked {
+            self.touch_building(building, origin);
+        }
+        // supplies are picked up from storage, so every barn got a job or lost one
+        if self.supplies_wanted != supplies_were_wanted {
+            let storage: Vec<(i32, i32)> = self
+                .dynamic_data
+                .tiles
+                .iter()
+                .filter(|(_, tile)| **tile == TileType::Storage)
+                .map(|(position, _)| *position)
+                .collect();
+            self.touched_tiles.extend(storage);
+        }
+
+        exp
+    }
+
+    /// Touches every tile of a building, as its job is done from any of them
+    pub fn touch_building(&mut self, building: usize, origin: (i32, i32)) {
+        for position in self.building_footprint(building, origin) {
+            self.touch(position);
+        }
+    }
+
+    /// The tiles a building would cover with its top left corner here
+    pub fn building_footprint(&self, building: usize, origin: (i32, i32)) -> Vec<(i32, i32)> {
+        let data = &self.recipes.buildings[building];
+        (0..data.width)
+            .cartesian_product(0..data.height)
+            .map(|(x, y)| (origin.0 + x, origin.1 + y))
+            .collect()
+    }
+
+    pub fn place_building(&mut self, building: usize, origin: (i32, i32)) {
+        for position in self.building_footprint(building, origin) {
+            self.dynamic_data
+                .tiles
+                .insert(position, TileType::Building { building, origin });
+            self.mark_changed(position);
+        }
+        self.dynamic_data.buildings.insert(
+            origin,
+            BuildingState::new(building, &self.recipes.buildings[building]),
+        );
+    }
+
+    /// Clears every tile of the building back to grass and hands back what it had in stock
+    pub fn remove_building(&mut self, origin: (i32, i32)) -> Option<BuildingState> {
+        let state = self.dynamic_data.buildings.remove(&origin)?;
+        for position in self.building_footprint(state.building, origin) {
+            self.dynamic_data.tiles.insert(position, TileType::Grass);
+            self.mark_changed(position);
+        }
+        Some(state)
+    }
+
+    /// Whether a worker can put a crop in the trough on this tile
+    pub fn accepts_feed(&self, position: (i32, i32), crop: usize) -> bool {
+        match self.dynamic_data.tiles.get(&position) {
+            Some(TileType::Trough {
+                crop: stored,
+                amount,
+            }) => stored.is_none_or(|stored| stored == crop) && *amount < TROUGH_CAPACITY,
+            _ => false,
+        }
+    }
+
+    /// How many goods all storage buildings hold together
+    pub fn storage_capacity(&self) -> usize {
+        let storage_tiles = self.storage_tiles.get().unwrap_or_else(|| {
+            let count = self
+                .dynamic_data
+                .tiles
+                .values()
+                .filter(|tile| **tile == TileType::Storage)
+                .count();
+            self.storage_tiles.set(Some(count));
+            count
+        });
+        storage_tiles * STORAGE_CAPACITY
+    }
+
+    /// Whether any trough has room for the crop
+    pub fn wants_feed(&mut self, crop: usize) -> bool {
+        self.update_pens();
+        self.pens
+            .every_trough
+            .iter()
+            .any(|position| self.accepts_feed(*position, crop))
+    }
+
+    /// Takes an animal's meal from a trough in its pen. Animals outside pens or in a pen without a trough
+    /// find their own food, so farms from before troughs keep producing.
+    /// The feed it likes is eaten first, anything else fills it up half as well.
+    /// Returns false if there's not enough food in its troughs
+    pub fn feed_animal(&mut self, position: (i32, i32), feed: &str, feed_per_drop: usize) -> bool {
+        self.update_pens();
+        let Some(pen) = self.pens.pen_of.get(&position) else {
+            return true;
+        };
+        let troughs = &self.pens.troughs[*pen];
+        if troughs.is_empty() {
+            return true;
+        }
+
+        let crops_data = &self.static_data.crops_data;
+
+        let needed = |crop: usize| {
+            if feed.is_empty() || crops_data[crop].id == feed {
+                feed_per_drop
+            } else {
+                feed_per_drop * 2
+            }
+        };
+
+        let trough = troughs
+            .iter()
+            .filter_map(
+                |trough_position| match self.dynamic_data.tiles.get(trough_position) {
+                    Some(TileType::Trough {
+                        crop: Some(crop),
+                        amount,
+                    }) if *amount >= needed(*crop) => Some((*trough_position, needed(*crop))),
+                    _ => None,
+                },
+            )
+            .min_by_key(|(trough_position, needed)| (*needed, *trough_position));
+
+        let Some((trough_position, needed)) = trough else {
+            return false;
+        };
+
+        if let Some(TileType::Trough { crop, amount }) =
+            self.dynamic_data.tiles.get_mut(&trough_position)
+        {
+            *amount -= needed;
+            if *amount == 0 {
+                *crop = None;
+            }
+        }
+
+        true
+    }
+
+    pub fn land_expansion_points(&self) -> &[(i32, i32)] {
+        &self.dynamic_data.land_expansion_points
     }
 
-    pub fn buy_land(&mut self, selected_tile: (i32, i32), player: &mut Player) {
+    /// Buys the expansion point on the tile if the player can pay for it, returns whether it did
+    pub fn buy_land(&mut self, selected_tile: (i32, i32), player: &mut Player) -> bool {
         if player.money < self.dynamic_data.next_expansion_cost {
-            return;
+            return false;
         }
 
         let mut index: Option<usize> = None;
@@ -261,17 +677,28 @@ impl Map {
         }
 
         if index.is_none() {
-            return;
+            return false;
         }
 
         player.money -= self.dynamic_data.next_expansion_cost;
+        player.stats.land_bought += 1;
         self.dynamic_data.next_expansion_cost =
             (self.dynamic_data.next_expansion_cost as f32 * 1.5).round() as usize;
 
-        let point = self.dynamic_data.land_expansion_points[index.unwrap()];
-        self.dynamic_data
-            .land_expansion_points
-            .remove(index.unwrap());
+        self.expand(index.unwrap());
+        true
+    }
+
+    /// Land that comes for free, next to the island, without raising the price of the next piece
+    pub fn add_free_land(&mut self) {
+        if !self.dynamic_data.land_expansion_points.is_empty() {
+            self.expand(0);
+        }
+    }
+
+    /// Turns an expansion point into a chunk of grass and puts new points around it
+    fn expand(&mut self, index: usize) {
+        let point = self.dynamic_data.land_expansion_points.remove(index);
 
         let neg_half_width = -(CHUNK_WIDTH as i32 / 2) + point.0;
         let pos_half_width = CHUNK_WIDTH as i32 / 2 + point.0;
@@ -281,6 +708,7 @@ impl Map {
         for x in neg_half_width..=pos_half_width {
             for y in neg_half_height..=pos_half_height {
                 self.dynamic_data.tiles.insert((x, y), TileType::Grass);
+                self.mark_changed((x, y));
             }
         }
 
@@ -301,285 +729,127 @@ impl Map {
             self.dynamic_data.land_expansion_points.push(position);
         }
     }
+}
 
-    pub fn draw(
-        &self,
-        rl: &mut RaylibDrawHandle,
-        textures: &HashMap<String, Texture2D>,
-        worker_handler: &mut WorkerHandler,
-        animal_handler: &mut AnimalHandler,
-        font: &Font,
-        settings: &GameSettigns,
-        locale_handler: &LocaleHandler,
-    ) {
-        let expansion_texture = textures.get("land_expansion").unwrap();
+impl Default for Map {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
 
-        for expansion_point in self.dynamic_data.land_expansion_points.iter() {
-            rl.draw_texture_ex(
-                expansion_texture,
-                Vector2::new(
-                    (expansion_point.0 * TILE_SIZE) as f32,
-                    (expansion_point.1 * TILE_SIZE) as f32,
-                ),
-                0.,
-                TILE_SCALE as f32,
-                Color::WHITE,
-            );
-            rl.draw_text_ex(
-                font,
-                &format!(
-                    "{}",
-                    shrink_number_for_display(
-                        self.dynamic_data.next_expansion_cost,
-                        locale_handler,
-                        settings
-                    )
-                ),
-                Vector2::new(
-                    (expansion_point.0 * TILE_SIZE
-                        + self
-                            .dynamic_data
-                            .next_expansion_cost
-                            .to_string()
-                            .chars()
-                            .count() as i32
-                            * 2) as f32,
-                    (expansion_point.1 * TILE_SIZE - TILE_SIZE / 3) as f32,
-                ),
-                24.,
-                0.,
-                Color::RAYWHITE,
-            );
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{content::ContentIds, localization::load_language};
+
+    // a pen of two tiles in the middle of the first chunk, (0, 0) and (1, 0)
+    fn map_with_pen() -> Map {
+        let mut map = Map::new(None);
+        for x in -1..=2 {
+            for y in -1..=1 {
+                if y != 0 || x == -1 || x == 2 {
+                    map.dynamic_data.tiles.insert((x, y), TileType::Fence);
+                    map.mark_changed((x, y));
+                }
+            }
         }
+        map
+    }
 
-        let border_texture = textures.get("borders").unwrap();
-
-        for (position, tile) in self.dynamic_data.tiles.iter().sorted() {
-            let texture_id = match tile {
-                TileType::Farmland { .. } => "dirt",
-                _ => "grass",
-            };
+    fn put_trough(map: &mut Map, crop: Option<usize>, amount: usize) {
+        map.dynamic_data
+            .tiles
+            .insert((1, 0), TileType::Trough { crop, amount });
+        map.mark_changed((1, 0));
+    }
 
-            let pixel_pos = Vector2::new(
-                (position.0 * TILE_SIZE) as f32,
-                (position.1 * TILE_SIZE) as f32,
-            );
-            let sample = self.noise.get([position.0 as f64 * 0.05, position.1 as f64 * 0.05]);
-            let offset = if sample < -0.5 {
-                0.
-            } else if sample > 0.5 {
-                2. * TILE_PIXEL_SIZE as f32
-            } else {
-                TILE_PIXEL_SIZE as f32
-            };
-            let source = Rectangle::new(0., offset, TILE_PIXEL_SIZE as f32, TILE_PIXEL_SIZE as f32);
-            rl.draw_texture_pro(
-                textures.get(texture_id).unwrap(),
-                source,
-                Rectangle::new(pixel_pos.x, pixel_pos.y, TILE_SIZE as f32, TILE_SIZE as f32),
-                Vector2::zero(),
-                0.,
-                Color::WHITE,
-            );
+    #[test]
+    fn animals_without_a_trough_find_their_own_food() {
+        let mut map = map_with_pen();
+        assert!(map.feed_animal((0, 0), "", 2));
+        // outside of any pen
+        assert!(map.feed_animal((-2, -2), "", 2));
+    }
 
-            let directions = [(0, 1), (1, 0), (0, -1), (-1, 0)];
-
-            for direction in directions {
-                let pos = (position.0 + direction.0, position.1 + direction.1);
-                if !self.dynamic_data.tiles.contains_key(&pos) {
-                    rl.draw_texture_pro(
-                        border_texture,
-                        Rectangle::new(
-                            (direction.0 * TILE_PIXEL_SIZE) as f32,
-                            (direction.1 * TILE_PIXEL_SIZE) as f32,
-                            TILE_PIXEL_SIZE as f32,
-                            TILE_PIXEL_SIZE as f32,
-                        ),
-                        Rectangle::new(
-                            (pos.0 * TILE_SIZE) as f32,
-                            (pos.1 * TILE_SIZE) as f32,
-                            TILE_SIZE as f32,
-                            TILE_SIZE as f32,
-                        ),
-                        Vector2::zero(),
-                        0.,
-                        Color::WHITE,
-                    );
-                }
+    #[test]
+    fn animals_with_a_trough_eat_from_it() {
+        let mut map = map_with_pen();
+        put_trough(&mut map, None, 0);
+        assert!(!map.feed_animal((0, 0), "", 2));
+
+        put_trough(&mut map, Some(0), 3);
+        assert!(map.feed_animal((0, 0), "", 2));
+        assert_eq!(
+            map.dynamic_data.tiles[&(1, 0)],
+            TileType::Trough {
+                crop: Some(0),
+                amount: 1
             }
-        }
+        );
+        assert!(!map.feed_animal((0, 0), "", 2));
+    }
 
-        // two loops bad, but better worker rendering
-        for (position, tile) in self.dynamic_data.tiles.iter().sorted() {
-            match tile {
-                TileType::Farmland { crop, stage, .. } => {
-                    let source = Rectangle::new(
-                        (*stage / self.static_data.crops_data[*crop].grow_step) as f32
-                            * TILE_PIXEL_SIZE as f32,
-                        0.,
-                        TILE_PIXEL_SIZE as f32,
-                        TILE_PIXEL_SIZE as f32,
-                    );
-                    let destination = Rectangle::new(
-                        (position.0 * TILE_SIZE) as f32,
-                        (position.1 * TILE_SIZE) as f32,
-                        TILE_SIZE as f32,
-                        TILE_SIZE as f32,
-                    );
-
-                    let id: &str = &format!("crop{}", crop);
-
-                    rl.draw_texture_pro(
-                        textures.get(id).unwrap_or(textures.get("error").unwrap()),
-                        source,
-                        destination,
-                        Vector2::zero(),
-                        0.,
-                        Color::WHITE,
-                    );
-                }
-                TileType::Tree { tree, grow, stage } => {
-                    let tree_data = &self.static_data.tree_data[*tree];
+    #[test]
+    fn troughs_want_crops_they_have_room_for() {
+        let mut map = map_with_pen();
+        assert!(!map.wants_feed(0));
 
-                    let offset = if *grow < tree_data.time_to_grow {
-                        (*grow / tree_data.grow_step) as f32 * TILE_PIXEL_SIZE as f32
-                    } else {
-                        if *stage >= self.static_data.tree_data[*tree].time_to_fruit {
-                            (tree_data.time_to_grow / tree_data.grow_step) as f32
-                                * TILE_PIXEL_SIZE as f32
-                        } else {
-                            ((tree_data.time_to_grow - 1) / tree_data.grow_step) as f32
-                                * TILE_PIXEL_SIZE as f32
-                        }
-                    };
-
-                    let source = Rectangle::new(
-                        offset,
-                        0.,
-                        TILE_PIXEL_SIZE as f32,
-                        TILE_PIXEL_SIZE as f32 * 2.,
-                    );
-                    let destination = Rectangle::new(
-                        (position.0 * TILE_SIZE) as f32,
-                        (position.1 * TILE_SIZE - TILE_SIZE) as f32,
-                        TILE_SIZE as f32,
-                        TILE_SIZE as f32 * 2.,
-                    );
-
-                    let id = &format!("tree{}", tree);
-
-                    rl.draw_texture_pro(
-                        textures.get(id).unwrap_or(textures.get("error").unwrap()),
-                        source,
-                        destination,
-                        Vector2::zero(),
-                        0.,
-                        Color::WHITE,
-                    );
-                }
-                TileType::AnimalDrop { animal } => {
-                    let source =
-                        Rectangle::new(0., 0., TILE_PIXEL_SIZE as f32, TILE_PIXEL_SIZE as f32);
-                    let destination = Rectangle::new(
-                        (position.0 * TILE_SIZE) as f32,
-                        (position.1 * TILE_SIZE) as f32,
-                        TILE_SIZE as f32,
-                        TILE_SIZE as f32,
-                    );
-
-                    let id: &str = &format!("animal_drop{}", *animal as usize);
-
-                    rl.draw_texture_pro(
-                        textures.get(id).unwrap_or(textures.get("error").unwrap()),
-                        source,
-                        destination,
-                        Vector2::zero(),
-                        0.,
-                        Color::WHITE,
-                    );
-                }
-                TileType::Beehive { stage, .. } => {
-                    let source = if *stage >= self.static_data.hive_data[0].time_to_honey {
-                        Rectangle::new(
-                            TILE_PIXEL_SIZE as f32,
-                            0.,
-                            TILE_PIXEL_SIZE as f32,
-                            TILE_PIXEL_SIZE as f32,
-                        )
-                    } else {
-                        Rectangle::new(0., 0., TILE_PIXEL_SIZE as f32, TILE_PIXEL_SIZE as f32)
-                    };
-
-                    let destination = Rectangle::new(
-                        (position.0 * TILE_SIZE) as f32,
-                        (position.1 * TILE_SIZE) as f32,
-                        TILE_SIZE as f32,
-                        TILE_SIZE as f32,
-                    );
-
-                    let id = &format!("beekeeping0");
-
-                    rl.draw_texture_pro(
-                        textures.get(id).unwrap_or(textures.get("error").unwrap()),
-                        source,
-                        destination,
-                        Vector2::zero(),
-                        0.,
-                        Color::WHITE,
-                    );
-                }
-                TileType::Flower { flower } => {
-                    let source =
-                        Rectangle::new(0., 0., TILE_PIXEL_SIZE as f32, TILE_PIXEL_SIZE as f32);
-                    let destination = Rectangle::new(
-                        (position.0 * TILE_SIZE) as f32,
-                        (position.1 * TILE_SIZE) as f32,
-                        TILE_SIZE as f32,
-                        TILE_SIZE as f32,
-                    );
-
-                    let id: &str = &format!("beekeeping{}", *flower as usize + 1);
-
-                    rl.draw_texture_pro(
-                        textures.get(id).unwrap_or(textures.get("error").unwrap()),
-                        source,
-                        destination,
-                        Vector2::zero(),
-                        0.,
-                        Color::WHITE,
-                    );
-                }
-                _ => {}
-            }
+        put_trough(&mut map, Some(0), 1);
+        assert!(map.wants_feed(0));
+        assert!(!map.wants_feed(1));
 
-            let worker_texture = textures.get("worker").unwrap();
-            worker_handler.workers.iter_mut().for_each(|worker| {
-                if worker.position == *position {
-                    worker.draw(rl, worker_texture);
-                }
-            });
-            animal_handler
-                .dynamic_data
-                .animals
-                .iter_mut()
-                .for_each(|animal| {
-                    if animal.position == *position {
-                        animal.draw(rl, &textures);
-                    }
-                })
+        // filling it up doesn't change the layout, but it's full all the same
+        if let Some(TileType::Trough { amount, .. }) = map.dynamic_data.tiles.get_mut(&(1, 0)) {
+            *amount = TROUGH_CAPACITY;
         }
+        assert!(!map.wants_feed(0));
     }
 
-    pub fn save(&self) {
-        let serialized = serde_json::to_string_pretty(&self.dynamic_data).expect("err");
-        std::fs::create_dir_all("dynamic").expect("Couldn't create dir");
-        std::fs::write("dynamic/map_save.json", serialized)
-            .expect("Couldn't write map data to json");
+    #[test]
+    fn pens_are_worked_out_again_after_the_layout_changes() {
+        let mut map = map_with_pen();
+        map.update_pens();
+        assert_eq!(map.pen_at((0, 0)).map(|pen| pen.len()), Some(2));
+        assert!(map.pen_at((-2, -2)).is_none());
+
+        // opening up the pen turns it into part of the open island
+        map.dynamic_data.tiles.insert((-1, 0), TileType::Grass);
+        map.mark_changed((-1, 0));
+        map.update_pens();
+        assert!(map.pen_at((0, 0)).is_none());
+
+        // a trough in what is no longer a pen doesn't make anyone hungry
+        put_trough(&mut map, None, 0);
+        assert!(map.feed_animal((0, 0), "", 2));
     }
-}
 
-impl Default for Map {
-    fn default() -> Self {
-        Self::new()
+    #[test]
+    fn the_same_weather_seed_grows_the_farm_the_same_way() {
+        let grow = || {
+            let mut map = Map::new(None);
+            for x in 0..10 {
+                let tile = if x % 2 == 0 {
+                    TileType::Farmland { crop: 0, stage: 0 }
+                } else {
+                    TileType::Tree {
+                        tree: 0,
+                        grow: 0,
+                        stage: 0,
+                    }
+                };
+                map.dynamic_data.tiles.insert((x, 0), tile);
+            }
+
+            let upgrade_handler =
+                UpgradeHandler::new(&load_language("en"), &ContentIds::load(), None);
+            let mut weather = Weather::new(3);
+            for _ in 0..500 {
+                weather.update();
+                map.update_tiles(&upgrade_handler, &weather);
+            }
+            map.dynamic_data.tiles
+        };
+
+        assert_eq!(grow(), grow());
     }
 }
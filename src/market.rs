@@ -0,0 +1,232 @@
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+use crate::{common::parse_json, sim::Produce};
+
+/// How prices move, read from static/market.json
+#[derive(Deserialize)]
+pub struct MarketSettings {
+    // the largest step the trend takes in a tick
+    drift: f32,
+    // share of the way back to the base price the trend goes every tick
+    reversion: f32,
+    min_trend: f32,
+    pub max_trend: f32,
+    // how much every piece sold floods the market
+    glut_per_piece: f32,
+    // share of the glut that wears off every tick
+    recovery: f32,
+    // nothing sells for less than this share of its base price
+    min_price: f32,
+    // ticks between two points of the price history
+    history_interval: usize,
+    pub history_length: usize,
+}
+
+impl MarketSettings {
+    fn load() -> Self {
+        parse_json("static/market.json").expect("no market settings")
+    }
+}
+
+/// Where the price of one kind of goods is at, as shares of its base price
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProductMarket {
+    // the random walk, 1 is the base price
+    trend: f32,
+    // how much was sold lately, prices drop while it's high
+    glut: f32,
+    // the price every history_interval ticks, oldest first
+    history: VecDeque<f32>,
+}
+
+impl ProductMarket {
+    fn new() -> Self {
+        Self {
+            trend: 1.,
+            glut: 0.,
+            history: VecDeque::new(),
+        }
+    }
+
+    fn price(&self, settings: &MarketSettings) -> f32 {
+        (self.trend / (1. + self.glut)).max(settings.min_price)
+    }
+}
+
+/// Prices drift on their own and drop as more of the same goods is sold, then recover over time.
+/// The drift only depends on the seed and the tick, so the same farm always sees the same market
+#[serde_as]
+#[derive(Serialize, Deserialize)]
+pub struct Market {
+    seed: u64,
+    tick: usize,
+    #[serde_as(as = "Vec<(_, _)>")]
+    products: HashMap<Produce, ProductMarket>,
+    #[serde(skip, default = "MarketSettings::load")]
+    pub settings: MarketSettings,
+}
+
+// splitmix64, plenty random for prices and the same everywhere
+fn hash(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/// A number from -1 to 1 that only depends on the seed, the goods and the tick
+fn noise(seed: u64, produce: Produce, tick: usize) -> f32 {
+    let (kind, index) = match produce {
+        Produce::Crop(crop) => (1, crop),
+        Produce::Tree(tree) => (2, tree),
+        Produce::Animal(animal) => (3, animal),
+        Produce::Honey => (4, 0),
+        Produce::Good(good) => (5, good),
+    };
+    let key = hash(seed ^ (kind << 56) ^ ((index as u64) << 32)) ^ tick as u64;
+
+    (hash(key) >> 40) as f32 / (1 << 24) as f32 * 2. - 1.
+}
+
+impl Market {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            tick: 0,
+            products: HashMap::new(),
+            settings: MarketSettings::load(),
+        }
+    }
+
+    /// Moves every price one tick along
+    pub fn update(&mut self, produce: &[Produce]) {
+        self.tick += 1;
+        let settings = &self.settings;
+        let record = self.tick.is_multiple_of(settings.history_interval.max(1));
+
+        for produce in produce {
+            let product = self
+                .products
+                .entry(*produce)
+                .or_insert_with(ProductMarket::new);
+
+            product.trend += settings.drift * noise(self.seed, *produce, self.tick)
+                + settings.reversion * (1. - product.trend);
+            product.trend = product.trend.clamp(settings.min_trend, settings.max_trend);
+            product.glut *= 1. - settings.recovery;
+
+            if record {
+                product.history.push_back(product.price(settings));
+                if product.history.len() > settings.history_length {
+                    product.history.pop_front();
+                }
+            }
+        }
+    }
+
+    /// The share of its base price the goods sell for right now
+    pub fn price(&self, produce: Produce) -> f32 {
+        self.products
+            .get(&produce)
+            .map_or(1., |product| product.price(&self.settings))
+    }
+
+    /// The price every history_interval ticks, oldest first
+    pub fn history(&self, produce: Produce) -> Option<&VecDeque<f32>> {
+        self.products.get(&produce).map(|product| &product.history)
+    }
+
+    /// Sells goods worth `money` at base prices and returns what they fetched. `unit_price` is what one
+    /// piece is worth, so the market knows how much was sold
+    pub fn sell(&mut self, produce: Produce, money: usize, unit_price: usize) -> usize {
+        let sold = (money as f32 * self.price(produce)) as usize;
+
+        // goods that haven't been on the market yet still flood it
+        let product = self
+            .products
+            .entry(produce)
+            .or_insert_with(ProductMarket::new);
+        product.glut += money as f32 / unit_price.max(1) as f32 * self.settings.glut_per_piece;
+
+        sold
+    }
+
+    /// The average share of the base prices everything sells for, for summaries
+    pub fn average_price(&self) -> f32 {
+        if self.products.is_empty() {
+            return 1.;
+        }
+
+        self.products
+            .values()
+            .map(|product| product.price(&self.settings))
+            .sum::<f32>()
+            / self.products.len() as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRODUCE: [Produce; 3] = [Produce::Crop(0), Produce::Tree(1), Produce::Honey];
+
+    fn history_after(seed: u64, ticks: usize) -> Vec<Vec<f32>> {
+        let mut market = Market::new(seed);
+        for _ in 0..ticks {
+            market.update(&PRODUCE);
+        }
+        PRODUCE
+            .iter()
+            .map(|produce| market.history(*produce).unwrap().iter().copied().collect())
+            .collect()
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_prices() {
+        let history = history_after(7, 2000);
+        assert_eq!(history[0].len(), 100);
+        assert_eq!(history, history_after(7, 2000));
+        assert_ne!(history, history_after(8, 2000));
+        // every kind of goods has its own drift
+        assert_ne!(history[0], history[1]);
+    }
+
+    #[test]
+    fn prices_drop_after_a_sale_and_recover() {
+        let mut market = Market::new(3);
+        let mut untouched = Market::new(3);
+        for market in [&mut market, &mut untouched] {
+            market.update(&PRODUCE);
+        }
+
+        let before = market.price(Produce::Honey);
+        let first = market.sell(Produce::Honey, 500, 5);
+        assert_eq!(first, (500. * before) as usize);
+        assert!(market.price(Produce::Honey) < before * 0.9);
+        assert!(market.sell(Produce::Honey, 500, 5) < first);
+
+        for _ in 0..2000 {
+            for market in [&mut market, &mut untouched] {
+                market.update(&PRODUCE);
+            }
+        }
+        let recovered = market.price(Produce::Honey);
+        assert!((recovered - untouched.price(Produce::Honey)).abs() < 0.01);
+        // other goods didn't notice
+        assert_eq!(
+            market.price(Produce::Crop(0)),
+            untouched.price(Produce::Crop(0))
+        );
+    }
+
+    #[test]
+    fn goods_new_to_the_market_drop_as_well() {
+        let mut market = Market::new(0);
+        market.sell(Produce::Good(2), 1000, 10);
+        assert!(market.price(Produce::Good(2)) < 1.);
+    }
+}
@@ -0,0 +1,229 @@
+use raylib::prelude::*;
+
+use crate::{
+    inventory::{all_produce, produce_name},
+    localization::LocaleHandler,
+    pause_menu::{Button, ButtonState},
+    sim::{Produce, Simulation},
+    utils::{get_game_height, get_game_width},
+};
+
+const ROW_HEIGHT: f32 = 44.;
+const LIST_WIDTH: f32 = 240.;
+const CHART_WIDTH: f32 = 600.;
+const CHART_HEIGHT: f32 = 320.;
+const GAPS: f32 = 10.;
+const TOP: f32 = 120.;
+
+/// Lists every kind of goods with what it sells for right now, and charts the price history of the
+/// one picked from the list
+pub struct MarketPanel {
+    pub open: bool,
+    rows: Vec<(Produce, Button)>,
+    selected: Option<Produce>,
+    // the selected goods' name and prices, oldest first, as shares of the base price
+    title: String,
+    history: Vec<f32>,
+    // how many points fit the chart and the highest price it shows
+    history_length: usize,
+    max_price: f32,
+    scroll: usize,
+    rect: Rectangle,
+}
+
+impl MarketPanel {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            rows: vec![],
+            selected: None,
+            title: String::new(),
+            history: vec![],
+            history_length: 0,
+            max_price: 1.,
+            scroll: 0,
+            rect: Rectangle::default(),
+        }
+    }
+
+    pub fn blocks_mouse(&self, mouse_position: Vector2) -> bool {
+        self.open && self.rect.check_collision_point_rec(mouse_position)
+    }
+
+    fn visible_rows(rl: &mut RaylibHandle) -> usize {
+        ((get_game_height(rl) as f32 - TOP - 40.) / ROW_HEIGHT).max(1.) as usize
+    }
+
+    fn layout(&mut self, rl: &mut RaylibHandle, sim: &Simulation, locale_handler: &LocaleHandler) {
+        let (map, animal_handler, market) = (&sim.map, &sim.animal_handler, &sim.market);
+
+        let width = LIST_WIDTH + CHART_WIDTH + GAPS * 2.;
+        let left = get_game_width(rl) as f32 / 2. - width / 2.;
+
+        let produce = all_produce(map, animal_handler);
+        let visible_rows = Self::visible_rows(rl);
+        self.scroll = self.scroll.min(produce.len().saturating_sub(visible_rows));
+
+        let shown = produce.len().min(visible_rows);
+        self.rect = Rectangle::new(
+            left - GAPS * 2.,
+            TOP - 60.,
+            width + GAPS * 4.,
+            (shown as f32 * ROW_HEIGHT).max(CHART_HEIGHT + 40.) + 80.,
+        );
+
+        let selected = *self.selected.get_or_insert(produce[0]);
+        self.title = format!(
+            "{} {:.0}%",
+            produce_name(selected, map, animal_handler, locale_handler),
+            market.price(selected) * 100.
+        );
+        self.history_length = market.settings.history_length;
+        self.history = market
+            .history(selected)
+            .map(|history| {
+                let skipped = history.len().saturating_sub(self.history_length);
+                history.iter().skip(skipped).copied().collect()
+            })
+            .unwrap_or_default();
+        self.max_price = market.settings.max_trend.max(1.);
+
+        self.rows = produce
+            .into_iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(visible_rows)
+            .map(|(index, produce)| {
+                let y = TOP + (index - self.scroll) as f32 * ROW_HEIGHT;
+                let button = Button::new(
+                    Rectangle::new(left, y, LIST_WIDTH - GAPS, ROW_HEIGHT - 8.),
+                    format!(
+                        "{} {:.0}%",
+                        produce_name(produce, map, animal_handler, locale_handler),
+                        market.price(produce) * 100.
+                    ),
+                );
+                (produce, button)
+            })
+            .collect();
+    }
+
+    pub fn update(
+        &mut self,
+        rl: &mut RaylibHandle,
+        sim: &Simulation,
+        locale_handler: &LocaleHandler,
+    ) {
+        if rl.is_key_pressed(KeyboardKey::KEY_M) {
+            self.open = !self.open;
+        }
+
+        if !self.open {
+            return;
+        }
+
+        let wheel = rl.get_mouse_wheel_move();
+        if wheel != 0. && self.blocks_mouse(rl.get_mouse_position()) {
+            self.scroll = if wheel > 0. {
+                self.scroll.saturating_sub(1)
+            } else {
+                self.scroll + 1
+            };
+        }
+
+        // prices move every tick, so the panel is rebuilt every frame
+        self.layout(rl, sim, locale_handler);
+
+        for (produce, button) in self.rows.iter_mut() {
+            button.update(rl);
+            if button.state == ButtonState::Pressed {
+                self.selected = Some(*produce);
+            }
+        }
+    }
+
+    pub fn draw(&self, rl: &mut RaylibDrawHandle, font: &Font, locale_handler: &LocaleHandler) {
+        if !self.open {
+            return;
+        }
+
+        let lang = &locale_handler.language_data;
+
+        rl.draw_rectangle_rec(self.rect, Color::BLACK.alpha(0.75));
+        rl.draw_text_ex(
+            font,
+            lang.get("market").unwrap(),
+            Vector2::new(self.rect.x + GAPS * 2., self.rect.y + GAPS),
+            32.,
+            0.,
+            Color::RAYWHITE,
+        );
+
+        for (_, button) in self.rows.iter() {
+            button.draw(rl, font);
+        }
+
+        let chart = Rectangle::new(
+            self.rect.x + GAPS * 2. + LIST_WIDTH + GAPS * 2.,
+            TOP + 40.,
+            CHART_WIDTH - GAPS * 2.,
+            CHART_HEIGHT,
+        );
+        rl.draw_text_ex(
+            font,
+            &self.title,
+            Vector2::new(chart.x, TOP),
+            24.,
+            0.,
+            Color::RAYWHITE,
+        );
+        rl.draw_rectangle_lines_ex(chart, 2., Color::GRAY);
+
+        let y_of = |price: f32| chart.y + chart.height * (1. - price / self.max_price);
+
+        // the base price, for reference
+        rl.draw_line_ex(
+            Vector2::new(chart.x, y_of(1.)),
+            Vector2::new(chart.x + chart.width, y_of(1.)),
+            1.,
+            Color::DARKGRAY,
+        );
+        rl.draw_text_ex(
+            font,
+            "100%",
+            Vector2::new(chart.x + 4., y_of(1.) - 20.),
+            16.,
+            0.,
+            Color::GRAY,
+        );
+
+        if self.history.len() < 2 {
+            rl.draw_text_ex(
+                font,
+                lang.get("no_price_history").unwrap(),
+                Vector2::new(chart.x + GAPS, chart.y + chart.height / 2.),
+                24.,
+                0.,
+                Color::LIGHTGRAY,
+            );
+            return;
+        }
+
+        // the newest price sits on the right edge
+        let step = chart.width / self.history_length.max(2) as f32;
+        let right = chart.x + chart.width;
+        let offset = self.history.len() - 1;
+        for (index, pair) in self.history.windows(2).enumerate() {
+            rl.draw_line_ex(
+                Vector2::new(right - (offset - index) as f32 * step, y_of(pair[0])),
+                Vector2::new(right - (offset - index - 1) as f32 * step, y_of(pair[1])),
+                2.,
+                if pair[1] >= 1. {
+                    Color::LIME
+                } else {
+                    Color::ORANGE
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,421 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::Value;
+
+use crate::common::parse_json;
+
+const MODS_DIR: &str = "mods";
+const MANIFEST_FILE: &str = "mod.json";
+
+// files with content lists that packs can add to or replace entries in
+const CONTENT_FILES: [&str; 6] = [
+    "tiles.json",
+    "animals.json",
+    "toolbar.json",
+    "upgrades.json",
+    "recipes.json",
+    "achievements.json",
+];
+
+#[derive(Deserialize)]
+pub struct Manifest {
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    // packs load from the lowest to the highest, so later packs win conflicts
+    #[serde(default)]
+    pub load_order: i32,
+}
+
+pub struct ContentPack {
+    pub manifest: Manifest,
+    pub dir: PathBuf,
+}
+
+/// What was found in the mods directory
+struct Scan {
+    // in load order
+    packs: Vec<ContentPack>,
+    // folders without a readable manifest, with why
+    skipped: Vec<String>,
+}
+
+impl Scan {
+    fn of(mods_dir: &Path) -> Self {
+        let mut packs = vec![];
+        let mut skipped = vec![];
+
+        let dirs = fs::read_dir(mods_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|dir| dir.is_dir());
+        for dir in dirs {
+            match parse_json(&dir.join(MANIFEST_FILE).to_string_lossy()) {
+                Ok(manifest) => packs.push(ContentPack { manifest, dir }),
+                Err(e) => skipped.push(format!("{} ({e})", dir.display())),
+            }
+        }
+
+        packs.sort_by(|a, b| {
+            a.manifest
+                .load_order
+                .cmp(&b.manifest.load_order)
+                .then_with(|| a.dir.cmp(&b.dir))
+        });
+        Self { packs, skipped }
+    }
+}
+
+/// The mods directory is only looked through once, every content file and asset kind is loaded from the
+/// same packs
+fn scan() -> &'static Scan {
+    static SCAN: OnceLock<Scan> = OnceLock::new();
+    SCAN.get_or_init(|| Scan::of(Path::new(MODS_DIR)))
+}
+
+/// Every pack in the mods directory that has a readable manifest, in load order
+pub fn content_packs() -> &'static [ContentPack] {
+    &scan().packs
+}
+
+/// The game's own directory for some kind of asset (textures, sfx, localizations) followed by the same
+/// directory of every pack that has one, in load order
+pub fn asset_dirs(kind: &str) -> Vec<PathBuf> {
+    let mut dirs = vec![Path::new("static").join(kind)];
+    dirs.extend(
+        content_packs()
+            .iter()
+            .map(|pack| pack.dir.join(kind))
+            .filter(|dir| dir.is_dir()),
+    );
+    dirs
+}
+
+/// Reads one of the static content files with every pack's version of it merged in.
+/// Lists are merged by the id of their entries: new ids are added to the end, known ones are replaced
+pub fn load_content<T: DeserializeOwned>(file: &str) -> Result<T, serde_json::Error> {
+    let (merged, _) = merge_content(file)?;
+    serde_json::from_value(merged)
+}
+
+/// Lists the loaded packs and everything they replace in the game or in each other
+pub fn report() {
+    for skipped in scan().skipped.iter() {
+        println!("skipping content pack {skipped}");
+    }
+
+    for pack in content_packs() {
+        println!(
+            "loaded content pack {} {} (load order {})",
+            pack.manifest.name, pack.manifest.version, pack.manifest.load_order
+        );
+    }
+
+    for conflict in conflicts() {
+        println!("  {conflict}");
+    }
+}
+
+fn conflicts() -> Vec<String> {
+    let mut conflicts = vec![];
+
+    for file in CONTENT_FILES {
+        if let Ok((_, file_conflicts)) = merge_content(file) {
+            conflicts.extend(file_conflicts);
+        }
+    }
+
+    let packs = content_packs();
+    for kind in ["textures", "sfx"] {
+        // file name and whoever provided it last
+        let mut providers: HashMap<String, &str> = HashMap::new();
+
+        let sources = std::iter::once(("the game", Path::new("static").join(kind))).chain(
+            packs
+                .iter()
+                .map(|pack| (pack.manifest.name.as_str(), pack.dir.join(kind))),
+        );
+
+        for (source, dir) in sources {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if let Some(previous) = providers.insert(name.clone(), source) {
+                    conflicts.push(format!("{source} replaces {kind}/{name} from {previous}"));
+                }
+            }
+        }
+    }
+
+    conflicts
+}
+
+fn merge_content(file: &str) -> Result<(Value, Vec<String>), serde_json::Error> {
+    merge_packs(Path::new("static"), content_packs(), file)
+}
+
+/// Merges the packs into the game's own version of a content file, with everything that got replaced
+fn merge_packs(
+    static_dir: &Path,
+    packs: &[ContentPack],
+    file: &str,
+) -> Result<(Value, Vec<String>), serde_json::Error> {
+    let mut merged: Value = parse_json(&static_dir.join(file).to_string_lossy())?;
+    let mut conflicts = vec![];
+
+    // (list, id) and the name of whoever defined it last
+    let mut providers: HashMap<(String, String), String> = HashMap::new();
+    if let Some(lists) = merged.as_object() {
+        for (list, entries) in lists {
+            for id in entries
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|entry| entry["id"].as_str())
+            {
+                providers.insert((list.clone(), id.to_string()), "the game".to_string());
+            }
+        }
+    }
+
+    for pack in packs {
+        let path = pack.dir.join(file);
+        if !path.exists() {
+            continue;
+        }
+
+        let pack_lists: Value = match fs::read_to_string(&path)
+            .map_err(serde_json::Error::io)
+            .and_then(|contents| serde_json::from_str(&contents))
+        {
+            Ok(value) => value,
+            Err(e) => {
+                conflicts.push(format!("{} has a broken {file} ({e})", pack.manifest.name));
+                continue;
+            }
+        };
+
+        let Value::Object(pack_lists) = pack_lists else {
+            continue;
+        };
+
+        for (list, entries) in pack_lists {
+            let Value::Array(entries) = entries else {
+                continue;
+            };
+            let Some(merged_list) = merged.get_mut(&list).and_then(Value::as_array_mut) else {
+                conflicts.push(format!(
+                    "{} adds to an unknown list {list} in {file}",
+                    pack.manifest.name
+                ));
+                continue;
+            };
+
+            for entry in entries {
+                let Some(id) = entry["id"].as_str().map(str::to_string) else {
+                    conflicts.push(format!(
+                        "{} has an entry without an id in {file}",
+                        pack.manifest.name
+                    ));
+                    continue;
+                };
+
+                match merged_list
+                    .iter()
+                    .position(|other| other["id"] == id.as_str())
+                {
+                    Some(index) => merged_list[index] = entry,
+                    None => merged_list.push(entry),
+                }
+
+                if let Some(previous) =
+                    providers.insert((list.clone(), id.clone()), pack.manifest.name.clone())
+                {
+                    conflicts.push(format!(
+                        "{} replaces {list} {id} from {previous}",
+                        pack.manifest.name
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok((merged, conflicts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory of its own under the system temp dir, emptied before the test
+    fn temp_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("uyta_mods_{test}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_pack(mods_dir: &Path, folder: &str, manifest: &str, files: &[(&str, &str)]) {
+        let dir = mods_dir.join(folder);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(MANIFEST_FILE), manifest).unwrap();
+        for (file, contents) in files {
+            fs::write(dir.join(file), contents).unwrap();
+        }
+    }
+
+    fn names(packs: &[ContentPack]) -> Vec<&str> {
+        packs
+            .iter()
+            .map(|pack| pack.manifest.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn manifests_fill_in_what_they_leave_out() {
+        let mods_dir = temp_dir("manifests");
+        write_pack(&mods_dir, "plain", r#"{"name": "Plain"}"#, &[]);
+        write_pack(&mods_dir, "broken", r#"{"version": "1.0"}"#, &[]);
+        fs::create_dir_all(mods_dir.join("no_manifest")).unwrap();
+
+        let scan = Scan::of(&mods_dir);
+
+        assert_eq!(names(&scan.packs), vec!["Plain"]);
+        assert_eq!(scan.packs[0].manifest.version, "");
+        assert_eq!(scan.packs[0].manifest.load_order, 0);
+        assert_eq!(scan.skipped.len(), 2);
+
+        fs::remove_dir_all(&mods_dir).unwrap();
+    }
+
+    #[test]
+    fn packs_load_by_load_order_then_by_folder() {
+        let mods_dir = temp_dir("load_order");
+        write_pack(&mods_dir, "a", r#"{"name": "Last", "load_order": 5}"#, &[]);
+        write_pack(
+            &mods_dir,
+            "c",
+            r#"{"name": "Second", "load_order": 1}"#,
+            &[],
+        );
+        write_pack(&mods_dir, "b", r#"{"name": "First", "load_order": 1}"#, &[]);
+        write_pack(
+            &mods_dir,
+            "d",
+            r#"{"name": "Early", "load_order": -1}"#,
+            &[],
+        );
+
+        let scan = Scan::of(&mods_dir);
+
+        assert_eq!(names(&scan.packs), vec!["Early", "First", "Second", "Last"]);
+
+        fs::remove_dir_all(&mods_dir).unwrap();
+    }
+
+    #[test]
+    fn later_packs_replace_entries_by_id() {
+        let root = temp_dir("merge");
+        let static_dir = root.join("static");
+        fs::create_dir_all(&static_dir).unwrap();
+        fs::write(
+            static_dir.join("tiles.json"),
+            r#"{"crops_data": [{"id": "carrot", "price": 1}, {"id": "cabbage", "price": 2}]}"#,
+        )
+        .unwrap();
+
+        let mods_dir = root.join("mods");
+        write_pack(
+            &mods_dir,
+            "first",
+            r#"{"name": "First", "load_order": 1}"#,
+            &[(
+                "tiles.json",
+                r#"{"crops_data": [{"id": "carrot", "price": 10}, {"id": "beet", "price": 3}]}"#,
+            )],
+        );
+        write_pack(
+            &mods_dir,
+            "second",
+            r#"{"name": "Second", "load_order": 2}"#,
+            &[(
+                "tiles.json",
+                r#"{"crops_data": [{"id": "carrot", "price": 20}]}"#,
+            )],
+        );
+
+        let scan = Scan::of(&mods_dir);
+        let (merged, conflicts) = merge_packs(&static_dir, &scan.packs, "tiles.json").unwrap();
+
+        let crops: Vec<(&str, u64)> = merged["crops_data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|crop| {
+                (
+                    crop["id"].as_str().unwrap(),
+                    crop["price"].as_u64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(crops, vec![("carrot", 20), ("cabbage", 2), ("beet", 3)]);
+        assert_eq!(
+            conflicts,
+            vec![
+                "First replaces crops_data carrot from the game",
+                "Second replaces crops_data carrot from First",
+            ]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn broken_pack_content_is_reported_and_left_out() {
+        let root = temp_dir("broken_content");
+        let static_dir = root.join("static");
+        fs::create_dir_all(&static_dir).unwrap();
+        fs::write(static_dir.join("animals.json"), r#"{"animal_data": []}"#).unwrap();
+
+        let mods_dir = root.join("mods");
+        write_pack(
+            &mods_dir,
+            "odd",
+            r#"{"name": "Odd"}"#,
+            &[(
+                "animals.json",
+                r#"{"pets": [{"id": "cat"}], "animal_data": [{"name": "goat"}]}"#,
+            )],
+        );
+        write_pack(
+            &mods_dir,
+            "torn",
+            r#"{"name": "Torn"}"#,
+            &[("animals.json", "{")],
+        );
+
+        let scan = Scan::of(&mods_dir);
+        let (merged, conflicts) = merge_packs(&static_dir, &scan.packs, "animals.json").unwrap();
+
+        assert_eq!(merged, serde_json::json!({"animal_data": []}));
+        assert_eq!(conflicts.len(), 3);
+        assert!(
+            conflicts.contains(&"Odd adds to an unknown list pets in animals.json".to_string())
+        );
+        assert!(conflicts.contains(&"Odd has an entry without an id in animals.json".to_string()));
+        assert!(conflicts[2].starts_with("Torn has a broken animals.json"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,157 @@
+use raylib::prelude::*;
+
+use crate::{
+    TILE_UPDATE_TIME,
+    common::get_unix_time,
+    localization::LocaleHandler,
+    settings::GameSettigns,
+    sim::{SimEvent, Simulation},
+    utils::{get_game_height, get_game_width, shrink_number_for_display},
+};
+
+// replaying is done tick by tick, so cap it to keep startup fast
+const MAX_OFFLINE_SECONDS: u64 = 2 * 60 * 60;
+
+pub struct OfflineProgress {
+    elapsed: u64,
+    money: usize,
+    exp: usize,
+    levels: usize,
+    wages: usize,
+    hidden: bool,
+}
+
+impl OfflineProgress {
+    /// Replays the ticks that passed since the last save, so the farm keeps working while the game is closed
+    pub fn catch_up(sim: &mut Simulation, saved_at: Option<u64>) -> Self {
+        let elapsed = saved_at.map_or(0, |saved_at| {
+            get_unix_time()
+                .saturating_sub(saved_at)
+                .min(MAX_OFFLINE_SECONDS)
+        });
+        let ticks = (elapsed as f32 / TILE_UPDATE_TIME) as usize;
+
+        let mut money = 0;
+        let mut exp = 0;
+        let mut levels = 0;
+        let mut wages = 0;
+
+        for _ in 0..ticks {
+            sim.tick();
+
+            for event in sim.drain_events() {
+                match event {
+                    SimEvent::Harvest {
+                        money: event_money,
+                        exp: event_exp,
+                        ..
+                    } => {
+                        money += event_money;
+                        exp += event_exp;
+                    }
+                    SimEvent::Sold {
+                        money: event_money, ..
+                    } => money += event_money,
+                    SimEvent::LevelUp { .. } => levels += 1,
+                    SimEvent::WagesPaid { money } => wages += money,
+                    SimEvent::AchievementUnlocked { .. } => {}
+                }
+            }
+        }
+
+        Self {
+            elapsed,
+            money,
+            exp,
+            levels,
+            wages,
+            hidden: money == 0 && exp == 0,
+        }
+    }
+
+    pub fn close(&mut self, rl: &mut RaylibHandle) {
+        if self.hidden {
+            return;
+        }
+
+        if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+            || rl.get_key_pressed().is_some()
+        {
+            self.hidden = true;
+        }
+    }
+
+    /// The summary is modal, so input should not reach the map while it is shown
+    pub fn blocks_input(&self) -> bool {
+        !self.hidden
+    }
+
+    pub fn draw(
+        &self,
+        rl: &mut RaylibDrawHandle,
+        font: &Font,
+        locale_handler: &LocaleHandler,
+        settings: &GameSettigns,
+    ) {
+        if self.hidden {
+            return;
+        }
+
+        let lang = &locale_handler.language_data;
+
+        let screen_width = get_game_width(rl);
+        let screen_height = get_game_height(rl);
+
+        let width = 480;
+        let extra_lines = (self.levels > 0) as i32 + (self.wages > 0) as i32;
+        let height = 200 + extra_lines * 30;
+        let x = screen_width / 2 - width / 2;
+        let y = screen_height / 2 - height / 2;
+
+        rl.draw_rectangle(x, y, width, height, Color::BLACK.alpha(0.8));
+
+        let hours = self.elapsed / 3600;
+        let minutes = self.elapsed % 3600 / 60;
+
+        let mut text = format!(
+            "{} ({}:{:02})\n\n+{} {}\n+{} {}",
+            lang.get("offline_title").unwrap(),
+            hours,
+            minutes,
+            shrink_number_for_display(self.money, locale_handler, settings),
+            lang.get("offline_money").unwrap(),
+            shrink_number_for_display(self.exp, locale_handler, settings),
+            lang.get("offline_exp").unwrap(),
+        );
+
+        if self.levels > 0 {
+            text += &format!("\n+{} {}", self.levels, lang.get("offline_levels").unwrap());
+        }
+
+        if self.wages > 0 {
+            text += &format!(
+                "\n-{} {}",
+                shrink_number_for_display(self.wages, locale_handler, settings),
+                lang.get("offline_wages").unwrap()
+            );
+        }
+
+        rl.draw_text_ex(
+            font,
+            &text,
+            Vector2::new((x + 20) as f32, (y + 20) as f32),
+            24.,
+            0.,
+            Color::RAYWHITE,
+        );
+
+        rl.draw_text_ex(
+            font,
+            lang.get("offline_continue").unwrap(),
+            Vector2::new((x + 20) as f32, (y + height - 40) as f32),
+            24.,
+            0.,
+            Color::ORANGE,
+        );
+    }
+}
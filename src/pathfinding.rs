@@ -0,0 +1,398 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use crate::map::{Map, TileType};
+
+type Position = (i32, i32);
+
+const DIRECTIONS: [(i32, i32); 4] = [(0, 1), (0, -1), (1, 0), (-1, 0)];
+
+// searches kept around at most, the least recently used one makes way for a new one
+const MAX_SEARCHES: usize = 256;
+
+// the cheapest a tile can be to walk across, so no path can be cheaper than this per step
+const MIN_TILE_COST: u32 = 1;
+
+/// How long it takes to walk across a tile, trees and hives are slow to get around.
+/// None for tiles that can't be walked across at all
+fn tile_cost(tile: &TileType) -> Option<u32> {
+    match tile {
+        TileType::Fence => None,
+        TileType::Tree { .. } | TileType::Beehive { .. } => Some(3),
+        _ => Some(MIN_TILE_COST),
+    }
+}
+
+/// The least a walk between two tiles can cost, it never overestimates so the path found is still the
+/// cheapest one
+fn estimate(from: Position, to: Position) -> u32 {
+    (from.0.abs_diff(to.0) + from.1.abs_diff(to.1)) * MIN_TILE_COST
+}
+
+struct Visit {
+    cost: u32,
+    from: Option<Position>,
+    // the cheapest path here is known
+    settled: bool,
+}
+
+/// A search spreading out from one tile, cheapest tiles first, or towards a goal when there is one.
+/// Either way a settled tile has its cheapest cost, so the search is kept after it finds what it was
+/// looking for and the next search from the same tile carries on from there instead of starting over
+struct Search {
+    visited: HashMap<Position, Visit>,
+    // settled tiles, in the order they were reached
+    settled: Vec<Position>,
+    // tiles found but not settled, by their cost plus the estimate to the goal
+    open: BinaryHeap<Reverse<(u32, Position)>>,
+    // what the open tiles are ordered towards, None spreads out evenly
+    goal: Option<Position>,
+    last_used: usize,
+}
+
+impl Search {
+    fn new(start: Position) -> Self {
+        let visit = Visit {
+            cost: 0,
+            from: None,
+            settled: false,
+        };
+        Self {
+            visited: HashMap::from([(start, visit)]),
+            settled: vec![],
+            open: BinaryHeap::from([Reverse((0, start))]),
+            goal: None,
+            last_used: 0,
+        }
+    }
+
+    /// Whether a change to this tile could change any path found so far. A tile next to a reached
+    /// one counts as well, as it may have just become walkable
+    fn depends_on(&self, position: Position) -> bool {
+        self.visited.contains_key(&position)
+            || DIRECTIONS.iter().any(|direction| {
+                self.visited
+                    .contains_key(&(position.0 + direction.0, position.1 + direction.1))
+            })
+    }
+
+    fn priority(&self, position: Position, cost: u32) -> u32 {
+        cost + self.goal.map_or(0, |goal| estimate(position, goal))
+    }
+
+    /// Orders the open tiles towards another goal
+    fn head_for(&mut self, goal: Option<Position>) {
+        if self.goal == goal {
+            return;
+        }
+
+        self.goal = goal;
+        self.open = self
+            .visited
+            .iter()
+            .filter(|(_, visit)| !visit.settled)
+            .map(|(position, visit)| Reverse((self.priority(*position, visit.cost), *position)))
+            .collect();
+    }
+
+    /// Settles the next open tile and finds its neighbours. None once every reachable tile is settled
+    fn settle_next(&mut self, tiles: &HashMap<Position, TileType>) -> Option<(u32, Position)> {
+        loop {
+            let Reverse((priority, position)) = self.open.pop()?;
+            let visit = &self.visited[&position];
+            // a cheaper way here was found after this one was queued
+            if visit.settled || priority > self.priority(position, visit.cost) {
+                continue;
+            }
+
+            let cost = visit.cost;
+            self.visited.get_mut(&position).unwrap().settled = true;
+            self.settled.push(position);
+
+            for direction in DIRECTIONS {
+                let next = (position.0 + direction.0, position.1 + direction.1);
+                let Some(step_cost) = tiles.get(&next).and_then(tile_cost) else {
+                    continue;
+                };
+
+                let next_cost = cost + step_cost;
+                if self
+                    .visited
+                    .get(&next)
+                    .is_some_and(|visit| visit.cost <= next_cost)
+                {
+                    continue;
+                }
+
+                let visit = Visit {
+                    cost: next_cost,
+                    from: Some(position),
+                    settled: false,
+                };
+                self.visited.insert(next, visit);
+                self.open
+                    .push(Reverse((self.priority(next, next_cost), next)));
+            }
+
+            return Some((cost, position));
+        }
+    }
+
+    fn path(
+        &mut self,
+        tiles: &HashMap<Position, TileType>,
+        goal: Position,
+    ) -> Option<Vec<Position>> {
+        if self.visited.get(&goal).is_some_and(|visit| visit.settled) {
+            return Some(self.path_to(goal));
+        }
+
+        self.head_for(Some(goal));
+        while let Some((_, position)) = self.settle_next(tiles) {
+            if position == goal {
+                return Some(self.path_to(goal));
+            }
+        }
+
+        None
+    }
+
+    fn nearest(
+        &mut self,
+        tiles: &HashMap<Position, TileType>,
+        is_target: impl Fn(Position) -> bool,
+    ) -> Option<Vec<Position>> {
+        // searches towards a goal settle tiles out of order, so a target settled before may not be
+        // the closest one
+        let mut best = self
+            .settled
+            .iter()
+            .copied()
+            .filter(|position| is_target(*position))
+            .min_by_key(|position| self.visited[position].cost);
+
+        self.head_for(None);
+        loop {
+            if let Some(best) = best
+                && self
+                    .open
+                    .peek()
+                    .is_none_or(|Reverse((cost, _))| self.visited[&best].cost <= *cost)
+            {
+                return Some(self.path_to(best));
+            }
+
+            let Some((cost, position)) = self.settle_next(tiles) else {
+                return best.map(|best| self.path_to(best));
+            };
+            if is_target(position) && best.is_none_or(|best| cost < self.visited[&best].cost) {
+                best = Some(position);
+            }
+        }
+    }
+
+    fn path_to(&self, target: Position) -> Vec<Position> {
+        let mut path = vec![target];
+        let mut current = target;
+        while let Some(parent) = self.visited[&current].from {
+            path.push(parent);
+            current = parent;
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// Finds paths over the island for all workers. Searches are kept per starting tile and only dropped
+/// when a tile they depend on is built on or cleared
+#[derive(Default)]
+pub struct Pathfinder {
+    searches_from: HashMap<Position, Search>,
+    // the size of the island the searches were made on, buying land opens up new ways
+    tile_count: usize,
+    // bumped whenever a change to the island dropped searches, so paths found before can be checked
+    generation: usize,
+    uses: usize,
+    pub searches: usize,
+    pub cache_hits: usize,
+}
+
+impl Pathfinder {
+    /// Drops the searches the tiles changed since the last tick could have made outdated
+    pub fn sync(&mut self, map: &Map) {
+        let tile_count = map.dynamic_data.tiles.len();
+        if tile_count != self.tile_count {
+            self.tile_count = tile_count;
+            self.searches_from.clear();
+            self.generation += 1;
+            return;
+        }
+
+        if map.changed_tiles.is_empty() {
+            return;
+        }
+
+        self.searches_from.retain(|_, search| {
+            !map.changed_tiles
+                .iter()
+                .any(|position| search.depends_on(*position))
+        });
+        self.generation += 1;
+    }
+
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// The cheapest path from start to goal, both included. None if the goal can't be reached
+    pub fn find_path(
+        &mut self,
+        tiles: &HashMap<Position, TileType>,
+        start: Position,
+        goal: Position,
+    ) -> Option<Vec<Position>> {
+        self.search_from(tiles, start)?.path(tiles, goal)
+    }
+
+    /// The cheapest path from start to the closest tile `is_target` accepts, closest by walking
+    /// rather than in a straight line
+    pub fn find_nearest(
+        &mut self,
+        tiles: &HashMap<Position, TileType>,
+        start: Position,
+        is_target: impl Fn(Position) -> bool,
+    ) -> Option<Vec<Position>> {
+        self.search_from(tiles, start)?.nearest(tiles, is_target)
+    }
+
+    /// The kept search from this tile, or a new one
+    fn search_from(
+        &mut self,
+        tiles: &HashMap<Position, TileType>,
+        start: Position,
+    ) -> Option<&mut Search> {
+        if !tiles.contains_key(&start) {
+            return None;
+        }
+
+        self.uses += 1;
+        if self.searches_from.contains_key(&start) {
+            self.cache_hits += 1;
+        } else {
+            self.searches += 1;
+            if self.searches_from.len() >= MAX_SEARCHES
+                && let Some(oldest) = self
+                    .searches_from
+                    .iter()
+                    .min_by_key(|(_, search)| search.last_used)
+                    .map(|(position, _)| *position)
+            {
+                self.searches_from.remove(&oldest);
+            }
+            self.searches_from.insert(start, Search::new(start));
+        }
+
+        let search = self.searches_from.get_mut(&start).unwrap();
+        search.last_used = self.uses;
+        Some(search)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set_fences(map: &mut Map, fence: bool) {
+        // a wall down the middle of the first chunk, with a gap at the bottom
+        for y in -2..=1 {
+            let tile = if fence {
+                TileType::Fence
+            } else {
+                TileType::Grass
+            };
+            map.dynamic_data.tiles.insert((0, y), tile);
+            map.mark_changed((0, y));
+        }
+    }
+
+    #[test]
+    fn paths_go_around_fences_built_after_they_were_found() {
+        let mut map = Map::new(None);
+        let mut pathfinder = Pathfinder::default();
+        pathfinder.sync(&map);
+
+        let straight = pathfinder
+            .find_path(&map.dynamic_data.tiles, (-2, 0), (2, 0))
+            .unwrap();
+        assert_eq!(straight.len(), 5);
+
+        set_fences(&mut map, true);
+        pathfinder.sync(&map);
+        map.changed_tiles.clear();
+
+        let around = pathfinder
+            .find_path(&map.dynamic_data.tiles, (-2, 0), (2, 0))
+            .unwrap();
+        assert!(
+            around
+                .iter()
+                .all(|position| position.0 != 0 || position.1 == 2)
+        );
+        assert_eq!(around.len(), 9);
+
+        set_fences(&mut map, false);
+        pathfinder.sync(&map);
+
+        let straight_again = pathfinder
+            .find_path(&map.dynamic_data.tiles, (-2, 0), (2, 0))
+            .unwrap();
+        assert_eq!(straight_again, straight);
+    }
+
+    #[test]
+    fn searches_from_the_same_tile_are_reused() {
+        let map = Map::new(None);
+        let mut pathfinder = Pathfinder::default();
+        pathfinder.sync(&map);
+
+        for goal in [(2, 2), (-2, 2), (1, -1)] {
+            pathfinder.find_path(&map.dynamic_data.tiles, (0, 0), goal);
+        }
+        assert_eq!(pathfinder.searches, 1);
+        assert_eq!(pathfinder.cache_hits, 2);
+    }
+
+    #[test]
+    fn paths_head_for_the_goal() {
+        let map = Map::new(None);
+        let mut pathfinder = Pathfinder::default();
+        pathfinder.sync(&map);
+
+        let path = pathfinder
+            .find_path(&map.dynamic_data.tiles, (-2, 0), (2, 0))
+            .unwrap();
+
+        assert_eq!(path.len(), 5);
+        // nothing off the straight line is worth looking at on open grass
+        assert_eq!(pathfinder.searches_from[&(-2, 0)].settled.len(), 5);
+    }
+
+    #[test]
+    fn searches_towards_a_goal_still_find_the_nearest_target() {
+        let map = Map::new(None);
+        let mut pathfinder = Pathfinder::default();
+        pathfinder.sync(&map);
+
+        pathfinder.find_path(&map.dynamic_data.tiles, (0, 0), (2, 2));
+        let nearest = pathfinder
+            .find_nearest(&map.dynamic_data.tiles, (0, 0), |position| {
+                position == (2, 2) || position == (-1, 0)
+            })
+            .unwrap();
+
+        assert_eq!(nearest, vec![(0, 0), (-1, 0)]);
+        assert_eq!(pathfinder.searches, 1);
+    }
+}
@@ -1,14 +1,20 @@
 use raylib::{ffi::CheckCollisionPointRec, prelude::*};
-use serde::{Deserialize, Serialize};
 
 use crate::{
+    achievements::AchievementLine,
+    common::get_unix_time,
     localization::LocaleHandler,
     map::TILE_SCALE,
-    utils::{get_game_height, get_game_width, parse_json},
+    save::Backup,
+    utils::{get_game_height, get_game_width},
 };
 
+pub const AUTOSAVE_INTERVALS: [usize; 4] = [30, 60, 120, 300];
+
+const ACHIEVEMENT_ROW_HEIGHT: f32 = 48.;
+
 pub struct Button {
-    rect: Rectangle,
+    pub rect: Rectangle,
     pub label: String,
     pub state: ButtonState,
 }
@@ -20,48 +26,80 @@ pub enum ButtonState {
     Pressed,
 }
 
+impl Button {
+    pub fn new(rect: Rectangle, label: String) -> Self {
+        Self {
+            rect,
+            label,
+            state: ButtonState::Normal,
+        }
+    }
+
+    /// Updates the state from the mouse, returns true if the button is under it
+    pub fn update(&mut self, rl: &RaylibHandle) -> bool {
+        if unsafe {
+            use raylib::ffi::{Rectangle, Vector2};
+            let rect = Rectangle {
+                x: self.rect.x,
+                y: self.rect.y,
+                width: self.rect.width,
+                height: self.rect.height,
+            };
+            let mouse_pos = Vector2 {
+                x: rl.get_mouse_position().x,
+                y: rl.get_mouse_position().y,
+            };
+            CheckCollisionPointRec(mouse_pos, rect)
+        } {
+            self.state = ButtonState::Hovered;
+            if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+                self.state = ButtonState::Pressed;
+            }
+            true
+        } else {
+            self.state = ButtonState::Normal;
+            false
+        }
+    }
+
+    pub fn draw(&self, rl: &mut RaylibDrawHandle, font: &Font) {
+        let color = match self.state {
+            ButtonState::Normal => Color::GRAY,
+            ButtonState::Hovered => Color::RAYWHITE,
+            ButtonState::Pressed => Color::GRAY,
+        };
+        rl.draw_rectangle_lines_ex(self.rect, TILE_SCALE as f32, color);
+        rl.draw_text_ex(
+            font,
+            &self.label,
+            Vector2::new(
+                self.rect.x + self.rect.width / 2. - self.label.chars().count() as f32 * 6.,
+                self.rect.y + self.rect.height / 2. - 12.,
+            ),
+            24.,
+            0.,
+            Color::RAYWHITE,
+        );
+    }
+}
+
 #[derive(Copy, Clone, PartialEq)]
 pub enum PauseMenuState {
     Main,
     Settings,
+    Backups,
+    Achievements,
 }
 
 pub struct PauseMenu {
     pub is_paused: bool,
     pub buttons: Vec<Button>,
     pub state: PauseMenuState,
-}
-
-#[derive(Deserialize, Serialize)]
-pub struct GameSettigns {
-    pub master_volume: f32,
-    pub is_fullscreen: bool,
-    pub short_numbers: bool,
-    pub language: String,
-}
-
-impl GameSettigns {
-    pub fn new() -> Self {
-        let res = parse_json("dynamic/settings.json");
-        match res {
-            Ok(settings) => return settings,
-            Err(_) => {
-                return Self {
-                    master_volume: 0.5,
-                    is_fullscreen: true,
-                    short_numbers: true,
-                    language: "ru".to_owned(),
-                };
-            }
-        }
-    }
-
-    pub fn save(&self) {
-        let serialized = serde_json::to_string_pretty(self).expect("err");
-        std::fs::create_dir_all("dynamic").expect("Couldn't create dir");
-        std::fs::write("dynamic/settings.json", serialized)
-            .expect("Couldn't write settings data to json");
-    }
+    pub backups: Vec<Backup>,
+    // filled in by whoever opens the achievements, like the backups
+    pub achievements: Vec<AchievementLine>,
+    pub stats: Vec<String>,
+    achievements_scroll: usize,
 }
 
 impl PauseMenu {
@@ -70,6 +108,10 @@ impl PauseMenu {
             is_paused: false,
             buttons: vec![],
             state: PauseMenuState::Main,
+            backups: vec![],
+            achievements: vec![],
+            stats: vec![],
+            achievements_scroll: 0,
         };
 
         menu.switch_state(rl, PauseMenuState::Main, locale_handler);
@@ -104,13 +146,41 @@ impl PauseMenu {
                         .to_string(),
                     state: ButtonState::Normal,
                 };
-                let quit = Button {
+                let backups = Button {
                     rect: Rectangle::new(
                         screen_width / 2. - menu_width / 4.,
                         screen_height / 2. - menu_height / 2. + 120.,
                         menu_width / 2.,
                         50.,
                     ),
+                    label: locale_handler
+                        .language_data
+                        .get("backups")
+                        .unwrap()
+                        .to_string(),
+                    state: ButtonState::Normal,
+                };
+                let achievements = Button {
+                    rect: Rectangle::new(
+                        screen_width / 2. - menu_width / 4.,
+                        screen_height / 2. - menu_height / 2. + 180.,
+                        menu_width / 2.,
+                        50.,
+                    ),
+                    label: locale_handler
+                        .language_data
+                        .get("achievements")
+                        .unwrap()
+                        .to_string(),
+                    state: ButtonState::Normal,
+                };
+                let quit = Button {
+                    rect: Rectangle::new(
+                        screen_width / 2. - menu_width / 4.,
+                        screen_height / 2. - menu_height / 2. + 240.,
+                        menu_width / 2.,
+                        50.,
+                    ),
                     label: locale_handler
                         .language_data
                         .get("quit")
@@ -119,7 +189,7 @@ impl PauseMenu {
                     state: ButtonState::Normal,
                 };
 
-                self.buttons = vec![settings, quit];
+                self.buttons = vec![settings, backups, achievements, quit];
             }
             PauseMenuState::Settings => {
                 let sfx_sub = Button {
@@ -193,13 +263,27 @@ impl PauseMenu {
                     label: fullscreen_label,
                     state: ButtonState::Normal,
                 };
-                let save = Button {
+                let autosave = Button {
                     rect: Rectangle::new(
                         screen_width / 2. - menu_width / 4.,
                         screen_height / 2. - menu_height / 2. + 300.,
                         menu_width / 2.,
                         50.,
                     ),
+                    label: locale_handler
+                        .language_data
+                        .get("autosave")
+                        .unwrap()
+                        .to_string(),
+                    state: ButtonState::Normal,
+                };
+                let save = Button {
+                    rect: Rectangle::new(
+                        screen_width / 2. - menu_width / 4.,
+                        screen_height / 2. - menu_height / 2. + 360.,
+                        menu_width / 2.,
+                        50.,
+                    ),
                     label: locale_handler
                         .language_data
                         .get("save_settings")
@@ -214,9 +298,61 @@ impl PauseMenu {
                     number_display,
                     cicle_language,
                     fullscreen_toggle,
+                    autosave,
                     save,
                 ];
             }
+            PauseMenuState::Backups => {
+                self.buttons.clear();
+
+                let lang = &locale_handler.language_data;
+                let now = get_unix_time();
+
+                for (index, backup) in self.backups.iter().enumerate() {
+                    let minutes = now.saturating_sub(backup.saved_at) / 60;
+                    self.buttons.push(Button {
+                        rect: Rectangle::new(
+                            screen_width / 2. - menu_width / 4.,
+                            screen_height / 2. - menu_height / 2. + 60. * (index + 1) as f32,
+                            menu_width / 2.,
+                            50.,
+                        ),
+                        label: format!("{} {}", minutes, lang.get("minutes_ago").unwrap()),
+                        state: ButtonState::Normal,
+                    });
+                }
+
+                // the last button always goes back, leaving room for the "no backups" text
+                self.buttons.push(Button {
+                    rect: Rectangle::new(
+                        screen_width / 2. - menu_width / 4.,
+                        screen_height / 2. - menu_height / 2.
+                            + 60. * (self.backups.len().max(1) + 1) as f32,
+                        menu_width / 2.,
+                        50.,
+                    ),
+                    label: lang.get("back").unwrap().to_string(),
+                    state: ButtonState::Normal,
+                });
+            }
+            PauseMenuState::Achievements => {
+                let lang = &locale_handler.language_data;
+                let left = screen_width / 2. - menu_width / 2. + 20.;
+                let bottom = screen_height / 2. + menu_height / 2.;
+
+                let export = Button {
+                    rect: Rectangle::new(left, bottom - 120., menu_width / 3., 50.),
+                    label: lang.get("export_summary").unwrap().to_string(),
+                    state: ButtonState::Normal,
+                };
+                let back = Button {
+                    rect: Rectangle::new(left, bottom - 60., menu_width / 3., 50.),
+                    label: lang.get("back").unwrap().to_string(),
+                    state: ButtonState::Normal,
+                };
+
+                self.buttons = vec![export, back];
+            }
         }
 
         self.state = state;
@@ -242,31 +378,21 @@ impl PauseMenu {
             self.switch_state(rl, self.state, locale_handler);
         }
 
+        if self.state == PauseMenuState::Achievements {
+            let wheel = rl.get_mouse_wheel_move();
+            if wheel > 0. {
+                self.achievements_scroll = self.achievements_scroll.saturating_sub(1);
+            } else if wheel < 0. {
+                self.achievements_scroll =
+                    (self.achievements_scroll + 1).min(self.achievements.len().saturating_sub(1));
+            }
+        }
+
         let mut blocks_mouse = false;
 
-        for i in 0..self.buttons.len() {
-            let button = &mut self.buttons[i];
-            if unsafe {
-                use raylib::ffi::{Rectangle, Vector2};
-                let rect = Rectangle {
-                    x: button.rect.x,
-                    y: button.rect.y,
-                    width: button.rect.width,
-                    height: button.rect.height,
-                };
-                let mouse_pos = Vector2 {
-                    x: rl.get_mouse_position().x,
-                    y: rl.get_mouse_position().y,
-                };
-                CheckCollisionPointRec(mouse_pos, rect)
-            } {
-                button.state = ButtonState::Hovered;
-                if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
-                    button.state = ButtonState::Pressed;
-                }
+        for button in self.buttons.iter_mut() {
+            if button.update(rl) {
                 blocks_mouse = true;
-            } else {
-                button.state = ButtonState::Normal;
             }
         }
 
@@ -310,26 +436,27 @@ impl PauseMenu {
         );
 
         for button in self.buttons.iter() {
-            let color = match button.state {
-                ButtonState::Normal => Color::GRAY,
-                ButtonState::Hovered => Color::RAYWHITE,
-                ButtonState::Pressed => Color::GRAY,
-            };
-            rl.draw_rectangle_lines_ex(button.rect, TILE_SCALE as f32, color);
+            button.draw(rl, font);
+        }
+
+        if self.state == PauseMenuState::Backups && self.backups.is_empty() {
             rl.draw_text_ex(
                 font,
-                &button.label,
+                locale_handler.language_data.get("no_backups").unwrap(),
                 Vector2::new(
-                    button.rect.x + button.rect.width / 2.
-                        - button.label.chars().count() as f32 * 6.,
-                    button.rect.y + button.rect.height / 2. - 12.,
+                    screen_width as f32 / 2. - menu_width as f32 / 4.,
+                    screen_height as f32 / 2. - menu_height as f32 / 2. + 60.,
                 ),
                 24.,
                 0.,
-                Color::RAYWHITE,
+                Color::GRAY,
             );
         }
 
+        if self.state == PauseMenuState::Achievements {
+            self.draw_achievements(rl, font, locale_handler);
+        }
+
         if self.state == PauseMenuState::Settings {
             rl.draw_text_ex(
                 font,
@@ -348,4 +475,77 @@ impl PauseMenu {
             );
         }
     }
+
+    /// The farm's stats on the left, above the buttons, and every achievement with its progress on the right
+    fn draw_achievements(
+        &self,
+        rl: &mut RaylibDrawHandle,
+        font: &Font,
+        locale_handler: &LocaleHandler,
+    ) {
+        let screen_width = get_game_width(rl) as f32;
+        let screen_height = get_game_height(rl) as f32;
+        let menu_width = screen_width * 0.5;
+        let menu_height = screen_height * 0.75;
+        let left = screen_width / 2. - menu_width / 2. + 20.;
+        let top = screen_height / 2. - menu_height / 2. + 60.;
+        let bottom = screen_height / 2. + menu_height / 2. - 140.;
+
+        for (index, line) in self.stats.iter().enumerate() {
+            let y = top + index as f32 * 28.;
+            if y + 28. > bottom {
+                break;
+            }
+            rl.draw_text_ex(font, line, Vector2::new(left, y), 24., 0., Color::RAYWHITE);
+        }
+
+        let column = left + menu_width * 0.4;
+        let unlocked = self
+            .achievements
+            .iter()
+            .filter(|achievement| achievement.unlocked)
+            .count();
+        rl.draw_text_ex(
+            font,
+            &format!(
+                "{} {unlocked}/{}",
+                locale_handler.language_data.get("achievements").unwrap(),
+                self.achievements.len()
+            ),
+            Vector2::new(column, top),
+            24.,
+            0.,
+            Color::GOLD,
+        );
+
+        let visible = ((menu_height - 140.) / ACHIEVEMENT_ROW_HEIGHT).max(1.) as usize;
+        for (index, achievement) in self
+            .achievements
+            .iter()
+            .skip(self.achievements_scroll)
+            .take(visible)
+            .enumerate()
+        {
+            let y = top + 36. + index as f32 * ACHIEVEMENT_ROW_HEIGHT;
+            let (current, target) = achievement.progress;
+
+            let (label, color) = if achievement.unlocked {
+                (achievement.name.clone(), Color::GOLD)
+            } else {
+                (
+                    format!("{} {}/{target}", achievement.name, current.min(target)),
+                    Color::GRAY,
+                )
+            };
+            rl.draw_text_ex(font, &label, Vector2::new(column, y), 24., 0., color);
+            rl.draw_text_ex(
+                font,
+                &achievement.description,
+                Vector2::new(column, y + 22.),
+                18.,
+                0.,
+                Color::LIGHTGRAY,
+            );
+        }
+    }
 }
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::parse_json;
+
+#[derive(Deserialize, Copy, Clone)]
+pub enum PerkEffect {
+    // in percent, per level
+    MoneyBonus(usize),
+    ExpBonus(usize),
+    // given at the start of every island, per level
+    StartingWorkers(usize),
+    StartingLand(usize),
+}
+
+#[derive(Deserialize)]
+pub struct Perk {
+    pub id: String,
+    pub effect: PerkEffect,
+    // in prestige points, the price of every level grows by cost_growth
+    cost: usize,
+    cost_growth: f32,
+    pub max_level: usize,
+}
+
+/// What a reset is worth and what the points buy, read from static/prestige.json
+#[derive(Deserialize)]
+pub struct PrestigeSettings {
+    // a reset gives (alltime_max_money / money_per_point) ^ point_exponent points
+    pub money_per_point: usize,
+    point_exponent: f32,
+    pub perks: Vec<Perk>,
+}
+
+impl PrestigeSettings {
+    fn load() -> Self {
+        parse_json("static/prestige.json").expect("no prestige settings")
+    }
+}
+
+/// Everything that outlives resetting the island: the points left to spend and the perks bought with
+/// them. Perks are saved by id, so they stay put when perks are added or reordered
+#[derive(Serialize, Deserialize)]
+pub struct Prestige {
+    pub points: usize,
+    pub resets: usize,
+    perk_levels: HashMap<String, usize>,
+    #[serde(skip, default = "PrestigeSettings::load")]
+    pub settings: PrestigeSettings,
+}
+
+impl Prestige {
+    pub fn new() -> Self {
+        Self {
+            points: 0,
+            resets: 0,
+            perk_levels: HashMap::new(),
+            settings: PrestigeSettings::load(),
+        }
+    }
+
+    /// Points a reset gives for a farm that made it to this much money at once
+    pub fn points_for(&self, alltime_max_money: usize) -> usize {
+        (alltime_max_money as f32 / self.settings.money_per_point.max(1) as f32)
+            .powf(self.settings.point_exponent) as usize
+    }
+
+    pub fn level(&self, index: usize) -> usize {
+        self.perk_levels
+            .get(&self.settings.perks[index].id)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// None once the perk is maxed out
+    pub fn price(&self, index: usize) -> Option<usize> {
+        let perk = &self.settings.perks[index];
+        let level = self.level(index);
+        if level >= perk.max_level {
+            return None;
+        }
+
+        Some((perk.cost as f32 * perk.cost_growth.powi(level as i32)).round() as usize)
+    }
+
+    pub fn buy(&mut self, index: usize) {
+        let Some(price) = self.price(index) else {
+            return;
+        };
+        if self.points < price {
+            return;
+        }
+
+        self.points -= price;
+        *self
+            .perk_levels
+            .entry(self.settings.perks[index].id.clone())
+            .or_insert(0) += 1;
+    }
+
+    /// Adds up every bought level of the perks with the same kind of effect
+    fn total(&self, amount_of: impl Fn(PerkEffect) -> Option<usize>) -> usize {
+        (0..self.settings.perks.len())
+            .filter_map(|index| {
+                amount_of(self.settings.perks[index].effect)
+                    .map(|amount| amount * self.level(index))
+            })
+            .sum()
+    }
+
+    /// Percent on top of the money and exp everything gives
+    pub fn bonus(&self) -> (usize, usize) {
+        (
+            self.total(|effect| match effect {
+                PerkEffect::MoneyBonus(percent) => Some(percent),
+                _ => None,
+            }),
+            self.total(|effect| match effect {
+                PerkEffect::ExpBonus(percent) => Some(percent),
+                _ => None,
+            }),
+        )
+    }
+
+    pub fn starting_workers(&self) -> usize {
+        self.total(|effect| match effect {
+            PerkEffect::StartingWorkers(amount) => Some(amount),
+            _ => None,
+        })
+    }
+
+    /// Chunks of land added to a new island
+    pub fn starting_land(&self) -> usize {
+        self.total(|effect| match effect {
+            PerkEffect::StartingLand(amount) => Some(amount),
+            _ => None,
+        })
+    }
+}
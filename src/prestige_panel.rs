@@ -0,0 +1,224 @@
+use raylib::prelude::*;
+
+use crate::{
+    localization::LocaleHandler,
+    pause_menu::{Button, ButtonState},
+    settings::GameSettigns,
+    sim::Simulation,
+    utils::{get_game_height, get_game_width, shrink_number_for_display},
+};
+
+const WIDTH: f32 = 720.;
+const ROW_HEIGHT: f32 = 64.;
+const BUY_WIDTH: f32 = 220.;
+const GAPS: f32 = 10.;
+const TOP: f32 = 120.;
+
+struct PerkRow {
+    name: String,
+    description: String,
+    buy: Button,
+}
+
+/// Resetting the island for prestige points, and the perks they buy. A reset takes two clicks, as it
+/// throws away the whole farm
+pub struct PrestigePanel {
+    pub open: bool,
+    // points and resets so far
+    summary: String,
+    reset: Button,
+    confirming: bool,
+    rows: Vec<PerkRow>,
+    rect: Rectangle,
+}
+
+impl PrestigePanel {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            summary: String::new(),
+            reset: Button::new(Rectangle::default(), String::new()),
+            confirming: false,
+            rows: vec![],
+            rect: Rectangle::default(),
+        }
+    }
+
+    pub fn blocks_mouse(&self, mouse_position: Vector2) -> bool {
+        self.open && self.rect.check_collision_point_rec(mouse_position)
+    }
+
+    fn layout(
+        &mut self,
+        rl: &mut RaylibHandle,
+        sim: &Simulation,
+        locale_handler: &LocaleHandler,
+        settings: &GameSettigns,
+    ) {
+        let lang = &locale_handler.language_data;
+        let prestige = &sim.prestige;
+
+        let left = get_game_width(rl) as f32 / 2. - WIDTH / 2.;
+        let perks = prestige.settings.perks.len();
+        let visible_rows =
+            ((get_game_height(rl) as f32 - TOP - 140.) / ROW_HEIGHT).max(1.) as usize;
+
+        self.rect = Rectangle::new(
+            left - GAPS * 2.,
+            TOP - 60.,
+            WIDTH + GAPS * 4.,
+            perks.min(visible_rows) as f32 * ROW_HEIGHT + 180.,
+        );
+
+        self.summary = format!(
+            "{}: {}   {}: {}",
+            lang.get("prestige_points").unwrap(),
+            prestige.points,
+            lang.get("prestige_resets").unwrap(),
+            prestige.resets
+        );
+
+        let points = prestige.points_for(sim.player.alltime_max_money);
+        let reset_label = if points == 0 {
+            format!(
+                "{} {}",
+                lang.get("prestige_not_ready").unwrap(),
+                shrink_number_for_display(
+                    prestige.settings.money_per_point,
+                    locale_handler,
+                    settings
+                )
+            )
+        } else if self.confirming {
+            lang.get("prestige_confirm").unwrap().to_string()
+        } else {
+            format!("{} +{points}", lang.get("prestige_reset").unwrap())
+        };
+        self.reset = Button::new(
+            Rectangle::new(left, TOP, WIDTH, ROW_HEIGHT - 14.),
+            reset_label,
+        );
+
+        self.rows = (0..perks)
+            .take(visible_rows)
+            .map(|index| {
+                let perk = &prestige.settings.perks[index];
+                let level = prestige.level(index);
+                let y = TOP + (index + 1) as f32 * ROW_HEIGHT + 20.;
+
+                let buy = Button::new(
+                    Rectangle::new(left + WIDTH - BUY_WIDTH, y, BUY_WIDTH, ROW_HEIGHT - 14.),
+                    match prestige.price(index) {
+                        Some(price) => format!("{} {price}", lang.get("buy_for").unwrap()),
+                        None => lang.get("maxed_out").unwrap().to_string(),
+                    },
+                );
+
+                let key = format!("perk_{}", perk.id);
+                let description = format!("perk_description_{}", perk.id);
+                PerkRow {
+                    name: format!(
+                        "{} {level}/{}",
+                        lang.get(&key).cloned().unwrap_or(key),
+                        perk.max_level
+                    ),
+                    description: lang.get(&description).cloned().unwrap_or(description),
+                    buy,
+                }
+            })
+            .collect();
+    }
+
+    /// Returns true if the island was reset, so the front end can reset what the simulation doesn't own
+    pub fn update(
+        &mut self,
+        rl: &mut RaylibHandle,
+        sim: &mut Simulation,
+        locale_handler: &LocaleHandler,
+        settings: &GameSettigns,
+    ) -> bool {
+        if rl.is_key_pressed(KeyboardKey::KEY_P) {
+            self.open = !self.open;
+            self.confirming = false;
+        }
+
+        if !self.open {
+            return false;
+        }
+
+        self.layout(rl, sim, locale_handler, settings);
+
+        let mut reset = false;
+        self.reset.update(rl);
+        if self.reset.state == ButtonState::Pressed
+            && sim.prestige.points_for(sim.player.alltime_max_money) > 0
+        {
+            if self.confirming {
+                sim.reset_island();
+                self.confirming = false;
+                reset = true;
+            } else {
+                self.confirming = true;
+            }
+        } else if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+            // clicking anywhere else calls the reset off
+            self.confirming = false;
+        }
+
+        for (index, row) in self.rows.iter_mut().enumerate() {
+            row.buy.update(rl);
+            if row.buy.state == ButtonState::Pressed {
+                sim.buy_perk(index);
+            }
+        }
+
+        reset
+    }
+
+    pub fn draw(&self, rl: &mut RaylibDrawHandle, font: &Font, locale_handler: &LocaleHandler) {
+        if !self.open {
+            return;
+        }
+
+        rl.draw_rectangle_rec(self.rect, Color::BLACK.alpha(0.75));
+        rl.draw_text_ex(
+            font,
+            locale_handler.language_data.get("prestige").unwrap(),
+            Vector2::new(self.rect.x + GAPS * 2., self.rect.y + GAPS),
+            32.,
+            0.,
+            Color::RAYWHITE,
+        );
+        rl.draw_text_ex(
+            font,
+            &self.summary,
+            Vector2::new(self.rect.x + GAPS * 2. + 240., self.rect.y + GAPS + 6.),
+            24.,
+            0.,
+            Color::GOLD,
+        );
+
+        self.reset.draw(rl, font);
+
+        for row in self.rows.iter() {
+            let x = row.buy.rect.x - WIDTH + BUY_WIDTH;
+            rl.draw_text_ex(
+                font,
+                &row.name,
+                Vector2::new(x, row.buy.rect.y),
+                24.,
+                0.,
+                Color::RAYWHITE,
+            );
+            rl.draw_text_ex(
+                font,
+                &row.description,
+                Vector2::new(x, row.buy.rect.y + 26.),
+                18.,
+                0.,
+                Color::LIGHTGRAY,
+            );
+            row.buy.draw(rl, font);
+        }
+    }
+}
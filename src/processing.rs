@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{content::ContentIds, inventory::Inventory, mods, sim::Produce};
+
+// batches worth of each input a building keeps on hand
+const INPUT_BATCHES: usize = 2;
+// batches a building makes before it waits for someone to take them away
+const OUTPUT_BATCHES: usize = 5;
+
+/// Goods that only come out of buildings
+#[derive(Deserialize)]
+pub struct Good {
+    pub id: String,
+    pub sell_price: usize,
+    // given out for every batch made
+    pub exp: usize,
+}
+
+/// Raw produce as written in static content, e.g. {"Crop": "potato"} or "Honey"
+#[derive(Deserialize)]
+pub enum ProduceId {
+    Crop(String),
+    Tree(String),
+    Animal(String),
+    Honey,
+    Good(String),
+}
+
+impl ProduceId {
+    pub fn resolve(&self, content_ids: &ContentIds, goods: &[Good]) -> Option<Produce> {
+        let index_of = |ids: &[String], id: &str| ContentIds::index_of(ids, id);
+
+        match self {
+            Self::Crop(id) => index_of(&content_ids.crops, id).map(Produce::Crop),
+            Self::Tree(id) => index_of(&content_ids.trees, id).map(Produce::Tree),
+            Self::Animal(id) => index_of(&content_ids.animals, id).map(Produce::Animal),
+            Self::Honey => Some(Produce::Honey),
+            Self::Good(id) => goods
+                .iter()
+                .position(|good| good.id == *id)
+                .map(Produce::Good),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RecipeInput {
+    produce: ProduceId,
+    amount: usize,
+}
+
+#[derive(Deserialize)]
+struct BuildingEntry {
+    id: String,
+    width: i32,
+    height: i32,
+    inputs: Vec<RecipeInput>,
+    output: String,
+    #[serde(default = "default_output_amount")]
+    output_amount: usize,
+    // ticks one batch takes
+    time_to_make: usize,
+}
+
+fn default_output_amount() -> usize {
+    1
+}
+
+#[derive(Deserialize)]
+struct RecipesEntry {
+    goods: Vec<Good>,
+    buildings: Vec<BuildingEntry>,
+}
+
+pub struct Building {
+    pub id: String,
+    pub width: i32,
+    pub height: i32,
+    pub inputs: Vec<(Produce, usize)>,
+    // index into the goods
+    pub output: usize,
+    pub output_amount: usize,
+    pub time_to_make: usize,
+}
+
+/// Buildings and the goods they make, read from static/recipes.json
+pub struct Recipes {
+    pub goods: Vec<Good>,
+    pub buildings: Vec<Building>,
+}
+
+impl Recipes {
+    pub fn load() -> Self {
+        let entry: RecipesEntry = mods::load_content("recipes.json").expect("no recipes");
+        let content_ids = ContentIds::load();
+
+        let buildings = entry
+            .buildings
+            .into_iter()
+            .map(|building| Building {
+                inputs: building
+                    .inputs
+                    .iter()
+                    .map(|input| {
+                        let produce = input
+                            .produce
+                            .resolve(&content_ids, &entry.goods)
+                            .unwrap_or_else(|| {
+                                panic!("{} needs produce that doesn't exist", building.id)
+                            });
+                        (produce, input.amount)
+                    })
+                    .collect(),
+                output: entry
+                    .goods
+                    .iter()
+                    .position(|good| good.id == building.output)
+                    .unwrap_or_else(|| panic!("{} makes a good that doesn't exist", building.id)),
+                id: building.id,
+                width: building.width,
+                height: building.height,
+                output_amount: building.output_amount,
+                time_to_make: building.time_to_make,
+            })
+            .collect();
+
+        Self {
+            goods: entry.goods,
+            buildings,
+        }
+    }
+}
+
+/// What a placed building has in stock, kept on the tile in its top left corner
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BuildingState {
+    pub building: usize,
+    // of each input, in the order of the recipe
+    pub stock: Vec<usize>,
+    pub progress: usize,
+    // made and waiting to be taken to storage
+    pub output: usize,
+}
+
+impl BuildingState {
+    pub fn new(building: usize, data: &Building) -> Self {
+        Self {
+            building,
+            stock: vec![0; data.inputs.len()],
+            progress: 0,
+            output: 0,
+        }
+    }
+
+    /// Works on the next batch if everything is in stock. Returns the exp for a finished batch
+    pub fn update(&mut self, data: &Building, goods: &[Good]) -> usize {
+        let stocked = data
+            .inputs
+            .iter()
+            .zip(self.stock.iter())
+            .all(|((_, amount), stock)| stock >= amount);
+        if !stocked || self.output >= data.output_amount * OUTPUT_BATCHES {
+            return 0;
+        }
+
+        self.progress += 1;
+        if self.progress < data.time_to_make {
+            return 0;
+        }
+
+        self.progress = 0;
+        for ((_, amount), stock) in data.inputs.iter().zip(self.stock.iter_mut()) {
+            *stock -= amount;
+        }
+        self.output += data.output_amount;
+        goods[data.output].exp
+    }
+
+    /// The first input that's running low and can be had from storage, with how much would top it up
+    pub fn wanted_input(&self, data: &Building, inventory: &Inventory) -> Option<(Produce, usize)> {
+        data.inputs
+            .iter()
+            .zip(self.stock.iter())
+            .map(|((produce, amount), stock)| {
+                (*produce, (amount * INPUT_BATCHES).saturating_sub(*stock))
+            })
+            .find(|(produce, missing)| *missing > 0 && inventory.amount(*produce) > 0)
+    }
+
+    /// Puts a delivery in stock and returns whatever the recipe has no use for
+    pub fn supply(&mut self, data: &Building, produce: Produce, amount: usize) -> usize {
+        match data.inputs.iter().position(|(input, _)| *input == produce) {
+            Some(index) => {
+                self.stock[index] += amount;
+                0
+            }
+            None => amount,
+        }
+    }
+}
@@ -1,17 +1,31 @@
-use raylib::prelude::*;
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use raylib::{ffi::CheckCollisionPointRec, prelude::*};
 
 use crate::{
-    animal::AnimalHandler,
+    UI_BUTTON_SIZE, UI_GAPS,
+    achievement_popup::AchievementPopup,
+    animal::{Animal, AnimalData, AnimalHandler},
     camera_controller::CameraController,
+    contracts_panel::ContractsPanel,
     localization::LocaleHandler,
-    map::{Map, TILE_SCALE, TILE_SIZE},
-    pause_menu::{GameSettigns, PauseMenu},
+    map::{Climate, Map, TILE_PIXEL_SIZE, TILE_SCALE, TILE_SIZE, TROUGH_CAPACITY, TileType},
+    market_panel::MarketPanel,
+    offline_progress::OfflineProgress,
+    pause_menu::PauseMenu,
     player::Player,
-    shop_ui::Canvas,
+    prestige_panel::PrestigePanel,
+    roster::RosterPanel,
+    settings::GameSettigns,
+    storage_panel::StoragePanel,
     texture_handler::TextureHandler,
+    toolbar::Canvas,
     tutorial::Tutorial,
     upgrades::UpgradeHandler,
-    worker::WorkerHandler,
+    utils::{get_game_height, get_game_width, shrink_number_for_display},
+    weather::{Particles, Weather},
+    worker::{Worker, WorkerHandler, WorkerSettings},
 };
 
 pub fn draw_bg(rl: &mut RaylibDrawHandle, bg_shader: &mut Shader, bg_texture: &Texture2D) {
@@ -25,6 +39,7 @@ pub fn draw_bg(rl: &mut RaylibDrawHandle, bg_shader: &mut Shader, bg_texture: &T
 pub fn draw_for_camera(
     rl: &mut RaylibDrawHandle,
     map: &Map,
+    canvas: &Canvas,
     camera_controller: &CameraController,
     texture_handler: &TextureHandler,
     worker_handler: &mut WorkerHandler,
@@ -36,8 +51,9 @@ pub fn draw_for_camera(
 ) {
     let mut d2 = rl.begin_mode2D(camera_controller.camera);
 
-    map.draw(
+    draw_map(
         &mut d2,
+        map,
         &texture_handler.textures,
         worker_handler,
         animal_handler,
@@ -46,6 +62,14 @@ pub fn draw_for_camera(
         locale_handler,
     );
 
+    if canvas.climate_overlay {
+        draw_climate_overlay(&mut d2, map, canvas);
+    }
+
+    if canvas.job_board_overlay {
+        draw_job_board_overlay(&mut d2, worker_handler, font);
+    }
+
     if !map.dynamic_data.tiles.contains_key(&selected_tile) {
         return;
     }
@@ -63,6 +87,735 @@ pub fn draw_for_camera(
     );
 }
 
+/// The ground first, then whatever stands on each tile along with the workers and animals on it
+fn draw_map(
+    rl: &mut RaylibDrawHandle,
+    map: &Map,
+    textures: &HashMap<String, Texture2D>,
+    worker_handler: &mut WorkerHandler,
+    animal_handler: &mut AnimalHandler,
+    font: &Font,
+    settings: &GameSettigns,
+    locale_handler: &LocaleHandler,
+) {
+    let expansion_texture = textures.get("land_expansion").unwrap();
+
+    for expansion_point in map.land_expansion_points().iter() {
+        rl.draw_texture_ex(
+            expansion_texture,
+            Vector2::new(
+                (expansion_point.0 * TILE_SIZE) as f32,
+                (expansion_point.1 * TILE_SIZE) as f32,
+            ),
+            0.,
+            TILE_SCALE as f32,
+            Color::WHITE,
+        );
+        rl.draw_text_ex(
+            font,
+            &format!(
+                "{}",
+                shrink_number_for_display(
+                    map.dynamic_data.next_expansion_cost,
+                    locale_handler,
+                    settings
+                )
+            ),
+            Vector2::new(
+                (expansion_point.0 * TILE_SIZE
+                    + map
+                        .dynamic_data
+                        .next_expansion_cost
+                        .to_string()
+                        .chars()
+                        .count() as i32
+                        * 2) as f32,
+                (expansion_point.1 * TILE_SIZE - TILE_SIZE / 3) as f32,
+            ),
+            24.,
+            0.,
+            Color::RAYWHITE,
+        );
+    }
+
+    let border_texture = textures.get("borders").unwrap();
+
+    for (position, tile) in map.dynamic_data.tiles.iter().sorted() {
+        let texture_id = match tile {
+            TileType::Farmland { .. } | TileType::Fallow { .. } => "dirt",
+            _ => "grass",
+        };
+
+        let pixel_pos = Vector2::new(
+            (position.0 * TILE_SIZE) as f32,
+            (position.1 * TILE_SIZE) as f32,
+        );
+        let offset = match map.climate_at(*position) {
+            Climate::Cold => 0.,
+            Climate::Warm => 2. * TILE_PIXEL_SIZE as f32,
+            _ => TILE_PIXEL_SIZE as f32,
+        };
+        let source = Rectangle::new(0., offset, TILE_PIXEL_SIZE as f32, TILE_PIXEL_SIZE as f32);
+        rl.draw_texture_pro(
+            textures.get(texture_id).unwrap(),
+            source,
+            Rectangle::new(pixel_pos.x, pixel_pos.y, TILE_SIZE as f32, TILE_SIZE as f32),
+            Vector2::zero(),
+            0.,
+            Color::WHITE,
+        );
+
+        let directions = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+
+        for direction in directions {
+            let pos = (position.0 + direction.0, position.1 + direction.1);
+            if !map.dynamic_data.tiles.contains_key(&pos) {
+                rl.draw_texture_pro(
+                    border_texture,
+                    Rectangle::new(
+                        (direction.0 * TILE_PIXEL_SIZE) as f32,
+                        (direction.1 * TILE_PIXEL_SIZE) as f32,
+                        TILE_PIXEL_SIZE as f32,
+                        TILE_PIXEL_SIZE as f32,
+                    ),
+                    Rectangle::new(
+                        (pos.0 * TILE_SIZE) as f32,
+                        (pos.1 * TILE_SIZE) as f32,
+                        TILE_SIZE as f32,
+                        TILE_SIZE as f32,
+                    ),
+                    Vector2::zero(),
+                    0.,
+                    Color::WHITE,
+                );
+            }
+        }
+    }
+
+    // two loops bad, but better worker rendering
+    for (position, tile) in map.dynamic_data.tiles.iter().sorted() {
+        match tile {
+            TileType::Farmland { crop, stage, .. } => {
+                let source = Rectangle::new(
+                    (*stage / map.static_data.crops_data[*crop].grow_step) as f32
+                        * TILE_PIXEL_SIZE as f32,
+                    0.,
+                    TILE_PIXEL_SIZE as f32,
+                    TILE_PIXEL_SIZE as f32,
+                );
+                let destination = Rectangle::new(
+                    (position.0 * TILE_SIZE) as f32,
+                    (position.1 * TILE_SIZE) as f32,
+                    TILE_SIZE as f32,
+                    TILE_SIZE as f32,
+                );
+
+                let id: &str = &format!("crop_{}", map.static_data.crops_data[*crop].id);
+
+                rl.draw_texture_pro(
+                    textures.get(id).unwrap_or(textures.get("error").unwrap()),
+                    source,
+                    destination,
+                    Vector2::zero(),
+                    0.,
+                    Color::WHITE,
+                );
+            }
+            TileType::Blueprint { crop } => {
+                // a see-through grown crop, so planned fields read differently from planted ones
+                let crop_data = &map.static_data.crops_data[*crop];
+                let source = Rectangle::new(
+                    (crop_data.time_to_grow / crop_data.grow_step) as f32 * TILE_PIXEL_SIZE as f32,
+                    0.,
+                    TILE_PIXEL_SIZE as f32,
+                    TILE_PIXEL_SIZE as f32,
+                );
+                let destination = Rectangle::new(
+                    (position.0 * TILE_SIZE) as f32,
+                    (position.1 * TILE_SIZE) as f32,
+                    TILE_SIZE as f32,
+                    TILE_SIZE as f32,
+                );
+
+                let id: &str = &format!("crop_{}", crop_data.id);
+
+                rl.draw_texture_pro(
+                    textures.get(id).unwrap_or(textures.get("error").unwrap()),
+                    source,
+                    destination,
+                    Vector2::zero(),
+                    0.,
+                    Color::WHITE.alpha(0.4),
+                );
+            }
+            TileType::Tree { tree, grow, stage } => {
+                let tree_data = &map.static_data.tree_data[*tree];
+
+                let offset = if *grow < tree_data.time_to_grow {
+                    (*grow / tree_data.grow_step) as f32 * TILE_PIXEL_SIZE as f32
+                } else {
+                    if *stage >= map.static_data.tree_data[*tree].time_to_fruit {
+                        (tree_data.time_to_grow / tree_data.grow_step) as f32
+                            * TILE_PIXEL_SIZE as f32
+                    } else {
+                        ((tree_data.time_to_grow - 1) / tree_data.grow_step) as f32
+                            * TILE_PIXEL_SIZE as f32
+                    }
+                };
+
+                let source = Rectangle::new(
+                    offset,
+                    0.,
+                    TILE_PIXEL_SIZE as f32,
+                    TILE_PIXEL_SIZE as f32 * 2.,
+                );
+                let destination = Rectangle::new(
+                    (position.0 * TILE_SIZE) as f32,
+                    (position.1 * TILE_SIZE - TILE_SIZE) as f32,
+                    TILE_SIZE as f32,
+                    TILE_SIZE as f32 * 2.,
+                );
+
+                let id = &format!("tree_{}", tree_data.id);
+
+                rl.draw_texture_pro(
+                    textures.get(id).unwrap_or(textures.get("error").unwrap()),
+                    source,
+                    destination,
+                    Vector2::zero(),
+                    0.,
+                    Color::WHITE,
+                );
+            }
+            TileType::AnimalDrop { animal } => {
+                let source = Rectangle::new(0., 0., TILE_PIXEL_SIZE as f32, TILE_PIXEL_SIZE as f32);
+                let destination = Rectangle::new(
+                    (position.0 * TILE_SIZE) as f32,
+                    (position.1 * TILE_SIZE) as f32,
+                    TILE_SIZE as f32,
+                    TILE_SIZE as f32,
+                );
+
+                let id: &str = &format!(
+                    "animal_drop_{}",
+                    animal_handler.static_data.animal_data[*animal].id
+                );
+
+                rl.draw_texture_pro(
+                    textures.get(id).unwrap_or(textures.get("error").unwrap()),
+                    source,
+                    destination,
+                    Vector2::zero(),
+                    0.,
+                    Color::WHITE,
+                );
+            }
+            TileType::Beehive { stage, .. } => {
+                let source = if *stage >= map.static_data.hive_data[0].time_to_honey {
+                    Rectangle::new(
+                        TILE_PIXEL_SIZE as f32,
+                        0.,
+                        TILE_PIXEL_SIZE as f32,
+                        TILE_PIXEL_SIZE as f32,
+                    )
+                } else {
+                    Rectangle::new(0., 0., TILE_PIXEL_SIZE as f32, TILE_PIXEL_SIZE as f32)
+                };
+
+                let destination = Rectangle::new(
+                    (position.0 * TILE_SIZE) as f32,
+                    (position.1 * TILE_SIZE) as f32,
+                    TILE_SIZE as f32,
+                    TILE_SIZE as f32,
+                );
+
+                let id = &format!("beekeeping_{}", map.static_data.hive_data[0].id);
+
+                rl.draw_texture_pro(
+                    textures.get(id).unwrap_or(textures.get("error").unwrap()),
+                    source,
+                    destination,
+                    Vector2::zero(),
+                    0.,
+                    Color::WHITE,
+                );
+            }
+            TileType::Flower { flower } => {
+                let source = Rectangle::new(0., 0., TILE_PIXEL_SIZE as f32, TILE_PIXEL_SIZE as f32);
+                let destination = Rectangle::new(
+                    (position.0 * TILE_SIZE) as f32,
+                    (position.1 * TILE_SIZE) as f32,
+                    TILE_SIZE as f32,
+                    TILE_SIZE as f32,
+                );
+
+                let id: &str = &format!("beekeeping_{}", map.static_data.flower_data[*flower].id);
+
+                rl.draw_texture_pro(
+                    textures.get(id).unwrap_or(textures.get("error").unwrap()),
+                    source,
+                    destination,
+                    Vector2::zero(),
+                    0.,
+                    Color::WHITE,
+                );
+            }
+            TileType::Trough { crop, amount } => {
+                let pixel_pos = Vector2::new(
+                    (position.0 * TILE_SIZE) as f32,
+                    (position.1 * TILE_SIZE) as f32,
+                );
+                rl.draw_texture_ex(
+                    textures
+                        .get("misc_trough")
+                        .unwrap_or(textures.get("error").unwrap()),
+                    pixel_pos,
+                    0.,
+                    TILE_SCALE as f32,
+                    Color::WHITE,
+                );
+
+                // a small ripe crop shows what's in it, the bar how much
+                if let Some(crop) = crop {
+                    let crop_data = &map.static_data.crops_data[*crop];
+                    let id: &str = &format!("crop_{}", crop_data.id);
+                    rl.draw_texture_pro(
+                        textures.get(id).unwrap_or(textures.get("error").unwrap()),
+                        Rectangle::new(
+                            (crop_data.time_to_grow / crop_data.grow_step) as f32
+                                * TILE_PIXEL_SIZE as f32,
+                            0.,
+                            TILE_PIXEL_SIZE as f32,
+                            TILE_PIXEL_SIZE as f32,
+                        ),
+                        Rectangle::new(
+                            pixel_pos.x + TILE_SIZE as f32 / 4.,
+                            pixel_pos.y,
+                            TILE_SIZE as f32 / 2.,
+                            TILE_SIZE as f32 / 2.,
+                        ),
+                        Vector2::zero(),
+                        0.,
+                        Color::WHITE,
+                    );
+                }
+                rl.draw_rectangle_rec(
+                    Rectangle::new(
+                        pixel_pos.x,
+                        pixel_pos.y + TILE_SIZE as f32 - 4.,
+                        TILE_SIZE as f32 * *amount as f32 / TROUGH_CAPACITY as f32,
+                        4.,
+                    ),
+                    Color::LIME,
+                );
+            }
+            TileType::RestHut | TileType::Fence | TileType::Gate | TileType::Storage => {
+                let id = match tile {
+                    TileType::RestHut => "misc_rest_hut",
+                    TileType::Fence => "misc_fence",
+                    TileType::Gate => "misc_gate",
+                    _ => "misc_storage",
+                };
+                rl.draw_texture_ex(
+                    textures.get(id).unwrap_or(textures.get("error").unwrap()),
+                    Vector2::new(
+                        (position.0 * TILE_SIZE) as f32,
+                        (position.1 * TILE_SIZE) as f32,
+                    ),
+                    0.,
+                    TILE_SCALE as f32,
+                    Color::WHITE,
+                );
+            }
+            // the whole building is drawn from its top left tile
+            TileType::Building { building, origin } if origin == position => {
+                let data = &map.recipes.buildings[*building];
+                let state = &map.dynamic_data.buildings[origin];
+                let pixel_pos = Vector2::new(
+                    (position.0 * TILE_SIZE) as f32,
+                    (position.1 * TILE_SIZE) as f32,
+                );
+                let id: &str = &format!("building_{}", data.id);
+                rl.draw_texture_ex(
+                    textures.get(id).unwrap_or(textures.get("error").unwrap()),
+                    pixel_pos,
+                    0.,
+                    TILE_SCALE as f32,
+                    Color::WHITE,
+                );
+
+                let width = (data.width * TILE_SIZE) as f32;
+                let height = (data.height * TILE_SIZE) as f32;
+                rl.draw_rectangle_rec(
+                    Rectangle::new(
+                        pixel_pos.x,
+                        pixel_pos.y + height - 4.,
+                        width * state.progress as f32 / data.time_to_make.max(1) as f32,
+                        4.,
+                    ),
+                    Color::LIME,
+                );
+                if state.output > 0 {
+                    rl.draw_text_ex(
+                        font,
+                        &state.output.to_string(),
+                        Vector2::new(pixel_pos.x + 4., pixel_pos.y),
+                        24.,
+                        0.,
+                        Color::RAYWHITE,
+                    );
+                }
+            }
+            _ => {}
+        }
+
+        let worker_texture = textures.get("worker").unwrap();
+        worker_handler.workers.iter_mut().for_each(|worker| {
+            if worker.position == *position {
+                draw_worker(rl, worker, worker_texture, &worker_handler.settings);
+            }
+        });
+        let animal_data = &animal_handler.static_data.animal_data;
+        animal_handler
+            .dynamic_data
+            .animals
+            .iter_mut()
+            .for_each(|animal| {
+                if animal.position == *position {
+                    draw_animal(rl, animal, textures, animal_data);
+                }
+            })
+    }
+}
+
+fn draw_worker(
+    rl: &mut RaylibDrawHandle,
+    worker: &mut Worker,
+    texture: &Texture2D,
+    settings: &WorkerSettings,
+) {
+    let pixel_position = Vector2::new(
+        (worker.position.0 * TILE_SIZE) as f32,
+        (worker.position.1 * TILE_SIZE) as f32,
+    );
+
+    worker.display_position.0 = lerp(
+        worker.display_position.0,
+        pixel_position.x,
+        10. * rl.get_frame_time(),
+    );
+    worker.display_position.1 = lerp(
+        worker.display_position.1,
+        pixel_position.y,
+        10. * rl.get_frame_time(),
+    );
+
+    let pixel_position = Vector2::new(worker.display_position.0, worker.display_position.1);
+
+    if let Some(next_position) = worker.next_step() {
+        worker.direction = (
+            next_position.0 - worker.position.0,
+            next_position.1 - worker.position.1,
+        );
+    }
+
+    let texture_index = match worker.direction {
+        (0, 1) => 0,
+        (0, -1) => 1,
+        (1, 0) => 2,
+        (-1, 0) => 3,
+        _ => 4,
+    };
+
+    let source = Rectangle {
+        x: (texture_index * TILE_PIXEL_SIZE) as f32,
+        y: 0.,
+        width: TILE_PIXEL_SIZE as f32,
+        height: TILE_PIXEL_SIZE as f32,
+    };
+
+    let destination = Rectangle {
+        x: pixel_position.x,
+        y: pixel_position.y,
+        width: TILE_SIZE as f32,
+        height: TILE_SIZE as f32,
+    };
+
+    rl.draw_texture_pro(
+        texture,
+        source,
+        destination,
+        Vector2::zero(),
+        0.,
+        Color::WHITE,
+    );
+
+    // stamina bar, only once some of it is used up
+    let stamina = worker.stamina(settings);
+    if stamina < settings.max_stamina {
+        let width = TILE_SIZE as f32 * stamina as f32 / settings.max_stamina.max(1) as f32;
+        let color = if stamina < settings.tired_below {
+            Color::ORANGE
+        } else {
+            Color::LIME
+        };
+        rl.draw_rectangle_rec(
+            Rectangle::new(pixel_position.x, pixel_position.y - 8., width, 4.),
+            color,
+        );
+    }
+}
+
+fn draw_animal(
+    rl: &mut RaylibDrawHandle,
+    animal: &mut Animal,
+    textures: &HashMap<String, Texture2D>,
+    animal_data: &[AnimalData],
+) {
+    let pixel_position = Vector2::new(
+        (animal.position.0 * TILE_SIZE) as f32,
+        (animal.position.1 * TILE_SIZE) as f32,
+    );
+
+    animal.display_position.0 = lerp(
+        animal.display_position.0,
+        pixel_position.x,
+        10. * rl.get_frame_time(),
+    );
+    animal.display_position.1 = lerp(
+        animal.display_position.1,
+        pixel_position.y,
+        10. * rl.get_frame_time(),
+    );
+
+    let pixel_position = Vector2::new(animal.display_position.0, animal.display_position.1);
+
+    let texture_index = match animal.direction {
+        (0, 1) => 0,
+        (0, -1) => 1,
+        (1, 0) => 2,
+        (-1, 0) => 3,
+        _ => 4,
+    };
+
+    let source = Rectangle {
+        x: (texture_index * TILE_PIXEL_SIZE) as f32,
+        y: 0.,
+        width: TILE_PIXEL_SIZE as f32,
+        height: TILE_PIXEL_SIZE as f32,
+    };
+
+    let destination = Rectangle {
+        x: pixel_position.x,
+        y: pixel_position.y,
+        width: TILE_SIZE as f32,
+        height: TILE_SIZE as f32,
+    };
+
+    let animal_data = &animal_data[animal.animal_type()];
+    let texture = match &animal_data.juvenile_texture {
+        Some(texture) if !animal.adult() => texture.clone(),
+        _ => format!("animal_{}", animal_data.id),
+    };
+
+    rl.draw_texture_pro(
+        textures
+            .get(&texture)
+            .unwrap_or(textures.get("error").unwrap()),
+        source,
+        destination,
+        Vector2::zero(),
+        0.,
+        Color::WHITE,
+    );
+}
+
+fn climate_color(climate: Climate) -> Color {
+    match climate {
+        Climate::Cold => Color::SKYBLUE,
+        Climate::Warm => Color::ORANGE,
+        _ => Color::LIME,
+    }
+}
+
+/// Tints the screen for the weather, lets rain or snow fall over it and says what season it is under
+/// the money
+fn draw_weather(
+    rl: &mut RaylibDrawHandle,
+    weather: &Weather,
+    font: &Font,
+    locale_handler: &LocaleHandler,
+) {
+    let lang = &locale_handler.language_data;
+    let kind = weather.current();
+    let (width, height) = (get_game_width(rl), get_game_height(rl));
+
+    // half of what the sea gets, so the farm stays readable
+    let [r, g, b, a] = kind.tint;
+    rl.draw_rectangle(0, 0, width, height, Color::new(r, g, b, a / 2));
+
+    if let Some(particles) = kind.particles {
+        let time = rl.get_time() as f32;
+        let (amount, fall, drift, sway, size, color) = match particles {
+            Particles::Rain => (
+                200,
+                900.,
+                150.,
+                0.,
+                Vector2::new(2., 14.),
+                Color::SKYBLUE.alpha(0.6),
+            ),
+            Particles::Snow => (
+                150,
+                80.,
+                20.,
+                12.,
+                Vector2::new(4., 4.),
+                Color::WHITE.alpha(0.8),
+            ),
+        };
+
+        for i in 0..amount {
+            // scattered by a couple of primes, so no particle has to be kept track of
+            let x = (i * 7919 % 1000) as f32 / 1000. * width as f32
+                + time * drift
+                + (time + i as f32).sin() * sway;
+            let y = (i * 104729 % 1000) as f32 / 1000. * height as f32 + time * fall;
+            rl.draw_rectangle_v(
+                Vector2::new(x.rem_euclid(width as f32), y.rem_euclid(height as f32)),
+                size,
+                color,
+            );
+        }
+    }
+
+    let season_key = format!("season_{}", weather.season().id);
+    let weather_key = format!("weather_{}", kind.id);
+    let text = format!(
+        "{}, {}",
+        lang.get(&season_key).cloned().unwrap_or(season_key),
+        lang.get(&weather_key).cloned().unwrap_or(weather_key)
+    );
+    rl.draw_rectangle(
+        10,
+        44,
+        text.chars().count() as i32 * 12 + 8,
+        28,
+        Color::BLACK.alpha(0.5),
+    );
+    rl.draw_text_ex(font, &text, Vector2::new(14., 48.), 24., 0., Color::WHITE);
+}
+
+/// Outlines every reserved tile with the id of the worker holding it and the ticks it has left
+fn draw_job_board_overlay(rl: &mut RaylibDrawHandle, worker_handler: &WorkerHandler, font: &Font) {
+    let half_tile = TILE_SIZE as f32 / 2.;
+    let tile_center = |position: (i32, i32)| {
+        Vector2::new(
+            (position.0 * TILE_SIZE) as f32 + half_tile,
+            (position.1 * TILE_SIZE) as f32 + half_tile,
+        )
+    };
+
+    for worker in worker_handler.workers.iter() {
+        if let Some(target) = worker.target() {
+            rl.draw_line_ex(
+                tile_center(worker.position),
+                tile_center(target),
+                TILE_SCALE as f32 / 2.,
+                Color::YELLOW.alpha(0.6),
+            );
+        }
+    }
+
+    let tick = worker_handler.job_board.tick();
+    for (position, reservation) in worker_handler.job_board.reservations() {
+        rl.draw_rectangle_lines_ex(
+            Rectangle::new(
+                (position.0 * TILE_SIZE) as f32,
+                (position.1 * TILE_SIZE) as f32,
+                TILE_SIZE as f32,
+                TILE_SIZE as f32,
+            ),
+            TILE_SCALE as f32 / 2.,
+            Color::YELLOW,
+        );
+        rl.draw_text_ex(
+            font,
+            &format!(
+                "{} ({})",
+                reservation.worker,
+                reservation.deadline.saturating_sub(tick)
+            ),
+            Vector2::new(
+                (position.0 * TILE_SIZE) as f32 + 2.,
+                (position.1 * TILE_SIZE) as f32 + 2.,
+            ),
+            16.,
+            0.,
+            Color::YELLOW,
+        );
+    }
+}
+
+/// Tints every tile by its climate and outlines the ones the selected shop item likes
+fn draw_climate_overlay(rl: &mut RaylibDrawHandle, map: &Map, canvas: &Canvas) {
+    let selected_climate = canvas.selected_climate();
+
+    for position in map.dynamic_data.tiles.keys() {
+        let climate = map.climate_at(*position);
+        let rect = Rectangle::new(
+            (position.0 * TILE_SIZE) as f32,
+            (position.1 * TILE_SIZE) as f32,
+            TILE_SIZE as f32,
+            TILE_SIZE as f32,
+        );
+
+        rl.draw_rectangle_rec(rect, climate_color(climate).alpha(0.35));
+
+        if selected_climate != Climate::Unapplicable && climate == selected_climate {
+            rl.draw_rectangle_lines_ex(rect, TILE_SCALE as f32 / 2., Color::GOLD);
+        }
+    }
+}
+
+fn draw_climate_legend(rl: &mut RaylibDrawHandle, font: &Font, locale_handler: &LocaleHandler) {
+    let lang = &locale_handler.language_data;
+    let top = get_game_height(rl) as f32 - 150.;
+
+    rl.draw_rectangle_rec(
+        Rectangle::new(10., top, 240., 140.),
+        Color::BLACK.alpha(0.5),
+    );
+    rl.draw_text_ex(
+        font,
+        lang.get("climate_map").unwrap(),
+        Vector2::new(20., top + 10.),
+        24.,
+        0.,
+        Color::RAYWHITE,
+    );
+
+    for (i, (climate, key)) in [
+        (Climate::Cold, "cold"),
+        (Climate::Temperate, "temperate"),
+        (Climate::Warm, "warm"),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let y = top + 45. + i as f32 * 30.;
+        rl.draw_rectangle_rec(Rectangle::new(20., y, 20., 20.), climate_color(climate));
+        rl.draw_text_ex(
+            font,
+            lang.get(key).unwrap(),
+            Vector2::new(50., y - 2.),
+            24.,
+            0.,
+            Color::RAYWHITE,
+        );
+    }
+}
+
 pub fn draw_fg(
     rl: &mut RaylibDrawHandle,
     canvas: &mut Canvas,
@@ -72,16 +825,38 @@ pub fn draw_fg(
     texture_handler: &TextureHandler,
     player: &mut Player,
     pause_menu: &PauseMenu,
+    roster: &RosterPanel,
+    storage_panel: &StoragePanel,
+    market_panel: &MarketPanel,
+    contracts_panel: &ContractsPanel,
+    prestige_panel: &PrestigePanel,
+    achievement_popup: &AchievementPopup,
     tutorial: &Tutorial,
+    offline_progress: &OfflineProgress,
+    weather: &Weather,
     font: &Font,
     locale_handler: &LocaleHandler,
     master_volume: f32,
     selected_tile: (i32, i32),
     settings: &GameSettigns,
 ) {
-    draw_placing_tooltip(rl, font, map, canvas, upgrade_handler, selected_tile);
+    draw_weather(rl, weather, font, locale_handler);
+
+    draw_placing_tooltip(
+        rl,
+        font,
+        map,
+        canvas,
+        upgrade_handler,
+        selected_tile,
+        locale_handler,
+    );
+
+    draw_player_stats(rl, player, font, locale_handler, settings);
 
-    player.draw_stats(rl, font, locale_handler, settings);
+    if canvas.climate_overlay {
+        draw_climate_legend(rl, font, locale_handler);
+    }
 
     canvas.draw(rl, map, animal_handler, texture_handler, player, font);
     canvas.update(
@@ -95,8 +870,9 @@ pub fn draw_fg(
         settings,
     );
 
-    upgrade_handler.draw(
+    draw_upgrades(
         rl,
+        upgrade_handler,
         texture_handler.textures.get("upgrades").unwrap(),
         font,
         player,
@@ -104,11 +880,214 @@ pub fn draw_fg(
         settings,
     );
 
+    roster.draw(rl, font, locale_handler);
+    storage_panel.draw(rl, font, locale_handler);
+    market_panel.draw(rl, font, locale_handler);
+    contracts_panel.draw(rl, font, locale_handler);
+    prestige_panel.draw(rl, font, locale_handler);
+
     tutorial.draw(rl, font);
 
+    achievement_popup.draw(rl, font, locale_handler);
+
+    offline_progress.draw(rl, font, locale_handler, settings);
+
     pause_menu.draw(rl, font, master_volume, locale_handler);
 }
 
+/// Money, and the xp bar with the level on it
+fn draw_player_stats(
+    rl: &mut RaylibDrawHandle,
+    player: &Player,
+    font: &Font,
+    locale_handler: &LocaleHandler,
+    settings: &GameSettigns,
+) {
+    rl.draw_rectangle(10, 10, 130, 28, Color::BLACK.alpha(0.5));
+
+    rl.draw_text_ex(
+        font,
+        &shrink_number_for_display(player.money, locale_handler, settings),
+        Vector2::new(14., 14.),
+        24.,
+        0.,
+        Color::WHITE,
+    );
+
+    let screen_width = get_game_width(rl);
+
+    let exp_bar_fill = player.exp as f32 / player.exp_to_lvl_up as f32;
+    rl.draw_rectangle(
+        screen_width / 4,
+        10,
+        screen_width / 2,
+        24,
+        Color::BLACK.alpha(0.5),
+    );
+    rl.draw_rectangle(
+        screen_width / 4,
+        10,
+        (exp_bar_fill * (screen_width / 2) as f32) as i32,
+        24,
+        Color::DARKORANGE,
+    );
+    rl.draw_text_ex(
+        font,
+        &format!(
+            "{} {}",
+            locale_handler.language_data.get("level").unwrap(),
+            player.level
+        ),
+        Vector2::new(screen_width as f32 / 4. + 10., 10.),
+        24.,
+        0.,
+        Color::WHITE,
+    );
+
+    if check_collision_point_poly(
+        rl.get_mouse_position(),
+        &[
+            Vector2::new(screen_width as f32 / 4., 10.),
+            Vector2::new(3. * screen_width as f32 / 4., 10.),
+            Vector2::new(3. * screen_width as f32 / 4., 34.),
+            Vector2::new(screen_width as f32 / 4., 34.),
+        ],
+    ) {
+        let text = format!(
+            "{}/{}",
+            shrink_number_for_display(player.exp, locale_handler, settings),
+            shrink_number_for_display(player.exp_to_lvl_up, locale_handler, settings)
+        );
+        rl.draw_text_ex(
+            font,
+            &text,
+            rl.get_mouse_position() + Vector2::new(12., -12.),
+            24.,
+            0.,
+            Color::WHITE,
+        );
+    }
+}
+
+/// The upgrades the player can get, bought by clicking them
+fn draw_upgrades(
+    rl: &mut RaylibDrawHandle,
+    upgrade_handler: &mut UpgradeHandler,
+    texture: &Texture2D,
+    font: &Font,
+    player: &mut Player,
+    locale_handler: &LocaleHandler,
+    settings: &GameSettigns,
+) {
+    let mut offset = 0;
+    for i in 0..upgrade_handler.static_data.upgrade_data.len() {
+        if !upgrade_handler.is_available(&upgrade_handler.static_data.upgrade_data[i], player) {
+            offset += 1;
+            continue;
+        }
+
+        let i = i as i32;
+        let scale = UI_BUTTON_SIZE + UI_GAPS / 2.;
+        let button_rect = Rectangle::new(
+            ((i - offset) % 3) as f32 * scale + get_game_width(rl) as f32 - 3. * scale,
+            ((i - offset) / 3) as f32 * scale + UI_GAPS / 2.,
+            UI_BUTTON_SIZE,
+            UI_BUTTON_SIZE,
+        );
+
+        let icon = upgrade_handler.static_data.upgrade_data[i as usize].icon as i32;
+        let source = Rectangle::new(
+            (icon % 3 * TILE_PIXEL_SIZE) as f32,
+            (icon / 3 * TILE_PIXEL_SIZE) as f32,
+            TILE_PIXEL_SIZE as f32,
+            TILE_PIXEL_SIZE as f32,
+        );
+
+        rl.draw_rectangle_rec(button_rect, Color::BLACK.alpha(0.5));
+        rl.draw_texture_pro(
+            texture,
+            source,
+            button_rect,
+            Vector2::zero(),
+            0.,
+            Color::WHITE,
+        );
+    }
+
+    offset = 0;
+    upgrade_handler.ui_blocks_mouse = false;
+    for i in 0..upgrade_handler.static_data.upgrade_data.len() {
+        if !upgrade_handler.is_available(&upgrade_handler.static_data.upgrade_data[i], player) {
+            offset += 1;
+            continue;
+        }
+
+        let data = &upgrade_handler.static_data.upgrade_data[i];
+
+        let i = i as i32;
+        let scale = UI_BUTTON_SIZE + UI_GAPS / 2.;
+        let button_rect = Rectangle::new(
+            ((i - offset) % 3) as f32 * scale + get_game_width(rl) as f32 - 3. * scale,
+            ((i - offset) / 3) as f32 * scale + UI_GAPS / 2.,
+            UI_BUTTON_SIZE,
+            UI_BUTTON_SIZE,
+        );
+
+        if !unsafe { CheckCollisionPointRec(rl.get_mouse_position().into(), button_rect.into()) } {
+            continue;
+        }
+
+        upgrade_handler.ui_blocks_mouse = true;
+
+        let x = rl.get_mouse_position().x
+            - data.description.chars().count() as f32 / 2. * UI_BUTTON_SIZE / 4.;
+        let y = rl.get_mouse_position().y;
+
+        let tooltip_rect = Rectangle::new(
+            x,
+            y,
+            data.description.chars().count() as f32 / 2. * UI_BUTTON_SIZE / 4.,
+            UI_BUTTON_SIZE * 2.,
+        );
+
+        rl.draw_rectangle_rec(tooltip_rect, Color::BLACK.alpha(0.75));
+        rl.draw_text_ex(
+            font,
+            &data.label,
+            Vector2::new(x + 5., y),
+            UI_BUTTON_SIZE / 2.,
+            0.,
+            Color::RAYWHITE,
+        );
+        rl.draw_text_ex(
+            font,
+            &data.description,
+            Vector2::new(x + 5., y + UI_BUTTON_SIZE / 2.),
+            UI_BUTTON_SIZE / 3.,
+            0.,
+            Color::DARKGRAY,
+        );
+        rl.draw_text_ex(
+            font,
+            &shrink_number_for_display(data.cost, locale_handler, settings),
+            Vector2::new(x + 5., y + UI_BUTTON_SIZE + UI_BUTTON_SIZE / 3.),
+            UI_BUTTON_SIZE / 2.,
+            0.,
+            Color::RAYWHITE,
+        );
+
+        if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+            if player.money >= data.cost {
+                player.money -= data.cost;
+                upgrade_handler
+                    .dynamic_data
+                    .purchased_upgrades
+                    .push(data.id.clone());
+            }
+        }
+    }
+}
+
 fn draw_placing_tooltip(
     rl: &mut RaylibDrawHandle,
     font: &Font,
@@ -116,6 +1095,7 @@ fn draw_placing_tooltip(
     canvas: &Canvas,
     upgrade_handler: &UpgradeHandler,
     selected_tile: (i32, i32),
+    locale_handler: &LocaleHandler,
 ) {
     if map.dynamic_data.tiles.contains_key(&selected_tile)
         && !canvas.blocks_mouse(rl.get_mouse_position())
@@ -124,35 +1104,49 @@ fn draw_placing_tooltip(
         let sel = canvas.selected;
         let toolbar_static = &canvas.toolbar_data.static_data;
         let (label, price) = match canvas.mode {
-            crate::shop_ui::MenuMode::Crops => (
+            crate::toolbar::MenuMode::Crops => (
                 toolbar_static.crops[sel].tooltip.clone(),
-                canvas.toolbar_data.get_price_for_crop(sel),
+                canvas.toolbar_data.get_price_for_crop(sel, upgrade_handler),
             ),
-            crate::shop_ui::MenuMode::Trees => (
+            crate::toolbar::MenuMode::Trees => (
                 toolbar_static.trees[sel].tooltip.clone(),
-                canvas.toolbar_data.get_price_for_tree(sel),
+                canvas.toolbar_data.get_price_for_tree(sel, upgrade_handler),
             ),
-            crate::shop_ui::MenuMode::Animals => (
+            crate::toolbar::MenuMode::Animals => (
                 toolbar_static.animals[sel].tooltip.clone(),
-                canvas.toolbar_data.get_price_for_animal(sel),
+                canvas
+                    .toolbar_data
+                    .get_price_for_animal(sel, upgrade_handler),
             ),
-            crate::shop_ui::MenuMode::Beekeeping => (
+            crate::toolbar::MenuMode::Beekeeping => (
                 toolbar_static.beekeeping[sel].tooltip.clone(),
-                canvas.toolbar_data.get_price_for_beekeeping(sel),
+                canvas
+                    .toolbar_data
+                    .get_price_for_beekeeping(sel, upgrade_handler),
             ),
-            crate::shop_ui::MenuMode::Misc => (
+            crate::toolbar::MenuMode::Misc => (
                 toolbar_static.misc[sel].tooltip.clone(),
-                canvas.toolbar_data.get_price_for_misc(sel),
+                canvas.toolbar_data.get_price_for_misc(sel, upgrade_handler),
+            ),
+            crate::toolbar::MenuMode::Buildings => (
+                toolbar_static.buildings[sel].tooltip.clone(),
+                canvas.toolbar_data.get_price_for_building(sel),
             ),
         };
 
-        let text = if price > 0 {
+        let mut text = if price > 0 {
             label.to_owned() + "\n" + &price.to_string()
         } else {
             label.to_string()
         };
 
-        let position = rl.get_mouse_position() + Vector2::new(0., -48.);
+        if canvas.mode == crate::toolbar::MenuMode::Animals && map.pen_at(selected_tile).is_none() {
+            text += "\n";
+            text += locale_handler.language_data.get("needs_pen").unwrap();
+        }
+
+        let height = text.lines().count() as f32 * 24.;
+        let position = rl.get_mouse_position() + Vector2::new(0., -height);
 
         rl.draw_rectangle_v(
             position,
@@ -163,7 +1157,7 @@ fn draw_placing_tooltip(
                     .chars()
                     .count() as f32
                     * 12.,
-                48.,
+                height,
             ),
             Color::BLACK.alpha(0.75),
         );
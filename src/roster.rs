@@ -0,0 +1,357 @@
+use raylib::prelude::*;
+
+use crate::{
+    localization::LocaleHandler,
+    pause_menu::{Button, ButtonState},
+    utils::{get_game_height, get_game_width},
+    worker::{JobType, Skill, Worker, WorkerHandler, WorkerRole},
+};
+
+const ROW_HEIGHT: f32 = 84.;
+const NAME_WIDTH: f32 = 150.;
+const ROLE_WIDTH: f32 = 150.;
+const JOB_WIDTH: f32 = 120.;
+const GAPS: f32 = 10.;
+const TOP: f32 = 120.;
+const MAX_NAME_LENGTH: usize = 16;
+
+struct RosterRow {
+    worker: usize,
+    name: Button,
+    // stamina, what the worker is up to and skill levels
+    stats: String,
+    role: Button,
+    priorities: Vec<Button>,
+}
+
+/// Lists every worker with their stats, role and job priorities. Clicking a name renames the worker,
+/// clicking the rest cycles through the options
+pub struct RosterPanel {
+    pub open: bool,
+    rows: Vec<RosterRow>,
+    // wages per minute and whether they're overdue
+    wages: (usize, bool),
+    // id of the worker being renamed and the name typed so far
+    renaming: Option<(usize, String)>,
+    scroll: usize,
+    rect: Rectangle,
+}
+
+pub fn role_key(role: WorkerRole) -> &'static str {
+    match role {
+        WorkerRole::Farmhand => "role_farmhand",
+        WorkerRole::Harvester => "role_harvester",
+        WorkerRole::Planter => "role_planter",
+        WorkerRole::Hauler => "role_hauler",
+        WorkerRole::Beekeeper => "role_beekeeper",
+    }
+}
+
+pub fn job_key(job: JobType) -> &'static str {
+    match job {
+        JobType::Harvest => "job_harvest",
+        JobType::Replant => "job_replant",
+        JobType::PlantFromPlan => "job_plant",
+        JobType::CollectDrops => "job_collect_drops",
+        JobType::TendHives => "job_tend_hives",
+        JobType::FeedAnimals => "job_feed",
+        JobType::RunBuildings => "job_buildings",
+    }
+}
+
+pub fn skill_key(skill: Skill) -> &'static str {
+    match skill {
+        Skill::Crops => "skill_crops",
+        Skill::Trees => "skill_trees",
+        Skill::Animals => "skill_animals",
+        Skill::Bees => "skill_bees",
+    }
+}
+
+/// The name the player gave the worker, or a numbered default
+pub fn worker_name(worker: &Worker, locale_handler: &LocaleHandler) -> String {
+    if worker.name.is_empty() {
+        format!(
+            "{} {}",
+            locale_handler.language_data.get("worker").unwrap(),
+            worker.id + 1
+        )
+    } else {
+        worker.name.clone()
+    }
+}
+
+impl RosterPanel {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            rows: vec![],
+            wages: (0, false),
+            renaming: None,
+            scroll: 0,
+            rect: Rectangle::default(),
+        }
+    }
+
+    pub fn blocks_mouse(&self, mouse_position: Vector2) -> bool {
+        self.open && self.rect.check_collision_point_rec(mouse_position)
+    }
+
+    /// Typing a name shouldn't move the camera or toggle anything
+    pub fn is_renaming(&self) -> bool {
+        self.renaming.is_some()
+    }
+
+    fn visible_rows(rl: &mut RaylibHandle) -> usize {
+        ((get_game_height(rl) as f32 - TOP - 40.) / ROW_HEIGHT).max(1.) as usize
+    }
+
+    fn layout(
+        &mut self,
+        rl: &mut RaylibHandle,
+        worker_handler: &WorkerHandler,
+        locale_handler: &LocaleHandler,
+    ) {
+        let lang = &locale_handler.language_data;
+        let settings = &worker_handler.settings;
+
+        let row_width = NAME_WIDTH + ROLE_WIDTH + JobType::ALL.len() as f32 * (JOB_WIDTH + GAPS);
+        let left = get_game_width(rl) as f32 / 2. - row_width / 2.;
+
+        let visible_rows = Self::visible_rows(rl);
+        self.scroll = self
+            .scroll
+            .min(worker_handler.workers.len().saturating_sub(visible_rows));
+
+        let shown = worker_handler.workers.len().min(visible_rows);
+        self.rect = Rectangle::new(
+            left - GAPS * 2.,
+            TOP - 60.,
+            row_width + GAPS * 4.,
+            shown as f32 * ROW_HEIGHT + 80.,
+        );
+
+        self.wages = (worker_handler.wages_per_minute(), worker_handler.unpaid());
+
+        self.rows = worker_handler
+            .workers
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(visible_rows)
+            .map(|(index, worker)| {
+                let y = TOP + (index - self.scroll) as f32 * ROW_HEIGHT;
+
+                let name = match &self.renaming {
+                    Some((id, name)) if *id == worker.id => format!("{name}_"),
+                    _ => worker_name(worker, locale_handler),
+                };
+                let name = Button::new(Rectangle::new(left, y, NAME_WIDTH - GAPS, 50.), name);
+
+                let role = Button::new(
+                    Rectangle::new(left + NAME_WIDTH, y, ROLE_WIDTH - GAPS, 50.),
+                    lang.get(role_key(worker.role)).unwrap().to_string(),
+                );
+                let priorities = JobType::ALL
+                    .iter()
+                    .enumerate()
+                    .map(|(i, job)| {
+                        let x = left + NAME_WIDTH + ROLE_WIDTH + i as f32 * (JOB_WIDTH + GAPS);
+                        Button::new(
+                            Rectangle::new(x, y, JOB_WIDTH, 50.),
+                            format!(
+                                "{} {}",
+                                lang.get(job_key(*job)).unwrap(),
+                                worker.priority(*job)
+                            ),
+                        )
+                    })
+                    .collect();
+
+                let doing = if worker.resting {
+                    lang.get("resting").unwrap()
+                } else if worker.delivering.is_some() {
+                    lang.get("delivering").unwrap()
+                } else {
+                    match worker.job {
+                        Some(job) => lang.get(job_key(job)).unwrap(),
+                        None => lang.get("idle").unwrap(),
+                    }
+                };
+                let mut stats = format!(
+                    "{} {}/{}   {}",
+                    lang.get("stamina").unwrap(),
+                    worker.stamina(settings),
+                    settings.max_stamina,
+                    doing,
+                );
+                for skill in Skill::ALL {
+                    stats += &format!(
+                        "   {} {}",
+                        lang.get(skill_key(skill)).unwrap(),
+                        worker.skill_level(skill, settings)
+                    );
+                }
+
+                RosterRow {
+                    worker: worker.id,
+                    name,
+                    stats,
+                    role,
+                    priorities,
+                }
+            })
+            .collect();
+    }
+
+    pub fn update(
+        &mut self,
+        rl: &mut RaylibHandle,
+        worker_handler: &mut WorkerHandler,
+        locale_handler: &LocaleHandler,
+    ) {
+        if self.renaming.is_some() {
+            self.update_rename(rl, worker_handler);
+        } else if rl.is_key_pressed(KeyboardKey::KEY_R) {
+            self.open = !self.open;
+        }
+
+        if !self.open {
+            return;
+        }
+
+        let wheel = rl.get_mouse_wheel_move();
+        if wheel != 0. && self.blocks_mouse(rl.get_mouse_position()) {
+            self.scroll = if wheel > 0. {
+                self.scroll.saturating_sub(1)
+            } else {
+                self.scroll + 1
+            };
+        }
+
+        // workers come and go, so the rows are rebuilt every frame
+        self.layout(rl, worker_handler, locale_handler);
+
+        for (row_index, row) in self.rows.iter_mut().enumerate() {
+            let worker = &mut worker_handler.workers[self.scroll + row_index];
+
+            row.name.update(rl);
+            if row.name.state == ButtonState::Pressed && self.renaming.is_none() {
+                self.renaming = Some((worker.id, worker.name.clone()));
+            }
+
+            row.role.update(rl);
+            if row.role.state == ButtonState::Pressed {
+                worker.set_role(worker.role.next());
+            }
+
+            for (job, button) in JobType::ALL.iter().zip(row.priorities.iter_mut()) {
+                button.update(rl);
+                if button.state == ButtonState::Pressed {
+                    worker.cycle_priority(*job);
+                }
+            }
+        }
+    }
+
+    /// Enter or a click anywhere else keeps the new name, an empty name goes back to the default one
+    fn update_rename(&mut self, rl: &mut RaylibHandle, worker_handler: &mut WorkerHandler) {
+        let Some((id, name)) = self.renaming.as_mut() else {
+            return;
+        };
+
+        while let Some(c) = rl.get_char_pressed() {
+            if name.chars().count() < MAX_NAME_LENGTH {
+                name.push(c);
+            }
+        }
+
+        if rl.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+            name.pop();
+        }
+
+        let clicked_away = rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+            && !self.rows.iter().any(|row| {
+                row.worker == *id
+                    && row
+                        .name
+                        .rect
+                        .check_collision_point_rec(rl.get_mouse_position())
+            });
+
+        if rl.is_key_pressed(KeyboardKey::KEY_ENTER) || clicked_away {
+            if let Some(worker) = worker_handler
+                .workers
+                .iter_mut()
+                .find(|worker| worker.id == *id)
+            {
+                worker.name = name.trim().to_string();
+            }
+            self.renaming = None;
+        }
+    }
+
+    pub fn draw(&self, rl: &mut RaylibDrawHandle, font: &Font, locale_handler: &LocaleHandler) {
+        if !self.open {
+            return;
+        }
+
+        let lang = &locale_handler.language_data;
+
+        rl.draw_rectangle_rec(self.rect, Color::BLACK.alpha(0.75));
+        rl.draw_text_ex(
+            font,
+            lang.get("workers").unwrap(),
+            Vector2::new(self.rect.x + GAPS * 2., self.rect.y + GAPS),
+            32.,
+            0.,
+            Color::RAYWHITE,
+        );
+
+        let (wages, unpaid) = self.wages;
+        if wages > 0 {
+            let text = if unpaid {
+                lang.get("unpaid").unwrap().to_string()
+            } else {
+                format!(
+                    "{}: {}{}",
+                    lang.get("wages").unwrap(),
+                    wages,
+                    lang.get("per_minute").unwrap()
+                )
+            };
+            rl.draw_text_ex(
+                font,
+                &text,
+                Vector2::new(
+                    self.rect.x + NAME_WIDTH + ROLE_WIDTH,
+                    self.rect.y + GAPS + 6.,
+                ),
+                24.,
+                0.,
+                if unpaid {
+                    Color::ORANGE
+                } else {
+                    Color::RAYWHITE
+                },
+            );
+        }
+
+        for row in self.rows.iter() {
+            row.name.draw(rl, font);
+            rl.draw_text_ex(
+                font,
+                &row.stats,
+                Vector2::new(row.name.rect.x, row.name.rect.y + 54.),
+                20.,
+                0.,
+                Color::LIGHTGRAY,
+            );
+
+            row.role.draw(rl, font);
+            for button in row.priorities.iter() {
+                button.draw(rl, font);
+            }
+        }
+    }
+}
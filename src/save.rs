@@ -0,0 +1,1162 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    panic,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map as JsonMap, Value};
+
+use crate::{
+    achievements::AchievementHandler, animal::AnimalDynamic, common::get_unix_time,
+    content::ContentIds, contracts::ContractBoard, map::MapDynamicData, market::Market,
+    player::Player, prestige::Prestige, sim::Simulation, toolbar::ToolbarDynamic,
+    upgrades::UpgradeDynamic, weather::Weather, worker::WorkerHandler,
+};
+
+// every slot gets a directory in here, named after the slot
+const SLOTS_DIR: &str = "dynamic/slots";
+// where saves lived before there were slots
+const UNSLOTTED_DIR: &str = "dynamic";
+const DEFAULT_SLOT_NAME: &str = "Farm";
+// longer names are cut, the slot picker stops taking letters here
+pub const MAX_SLOT_NAME_LENGTH: usize = 24;
+
+const SAVE_FILE: &str = "save.json";
+const META_FILE: &str = "meta.json";
+const BACKUP_DIR: &str = "backups";
+const SUMMARY_DIR: &str = "summaries";
+const MAX_BACKUPS: usize = 5;
+// seconds between two backup snapshots, autosaves in between only overwrite the main save
+const BACKUP_INTERVAL: u64 = 10 * 60;
+
+// the six files every version before the single save container wrote
+const LEGACY_FILES: [(&str, &str); 6] = [
+    ("map", "map_save.json"),
+    ("player", "player_save.json"),
+    ("workers", "workers_save.json"),
+    ("animals", "animals_save.json"),
+    ("upgrades", "upgrades_save.json"),
+    ("toolbar", "toolbar_save.json"),
+];
+
+type Migration = fn(&mut Value);
+
+/// What content is referred to by in a save value. Saves on disk use ids, the game itself uses positions
+/// in the static data
+#[derive(Copy, Clone)]
+enum ContentRefs {
+    Ids,
+    Indices,
+}
+
+impl ContentRefs {
+    fn convert(self, value: &Value, ids: &[String]) -> Option<Value> {
+        match self {
+            Self::Ids => ids
+                .get(value.as_u64()? as usize)
+                .map(|id| Value::from(id.as_str())),
+            Self::Indices => ContentIds::index_of(ids, value.as_str()?).map(Value::from),
+        }
+    }
+
+    fn convert_key(self, key: &str, ids: &[String]) -> Option<String> {
+        match self {
+            Self::Ids => ids.get(key.parse::<usize>().ok()?).cloned(),
+            Self::Indices => ContentIds::index_of(ids, key).map(|index| index.to_string()),
+        }
+    }
+}
+
+// MIGRATIONS[n] upgrades a save of version n to version n + 1.
+// whenever the layout of anything in the save changes, append a step here instead of editing old ones
+const MIGRATIONS: [Migration; 3] = [migrate_v0_to_v1, migrate_v1_to_v2, migrate_v2_to_v3];
+
+pub const SAVE_VERSION: u32 = MIGRATIONS.len() as u32;
+
+#[derive(Deserialize)]
+pub struct SaveFile {
+    pub saved_at: u64,
+    // in seconds
+    #[serde(default)]
+    pub play_time: u64,
+    pub map: MapDynamicData,
+    pub player: Player,
+    pub workers: Option<WorkerHandler>,
+    pub animals: Option<AnimalDynamic>,
+    pub upgrades: Option<UpgradeDynamic>,
+    pub toolbar: Option<ToolbarDynamic>,
+    #[serde(default)]
+    pub market: Option<Market>,
+    #[serde(default)]
+    pub contracts: Option<ContractBoard>,
+    #[serde(default)]
+    pub achievements: Option<AchievementHandler>,
+    #[serde(default)]
+    pub prestige: Option<Prestige>,
+    #[serde(default)]
+    pub weather: Option<Weather>,
+}
+
+#[derive(Serialize)]
+struct SaveFileRef<'a> {
+    version: u32,
+    saved_at: u64,
+    play_time: u64,
+    map: &'a MapDynamicData,
+    player: &'a Player,
+    workers: &'a WorkerHandler,
+    animals: &'a AnimalDynamic,
+    upgrades: &'a UpgradeDynamic,
+    toolbar: &'a ToolbarDynamic,
+    market: &'a Market,
+    contracts: &'a ContractBoard,
+    achievements: &'a AchievementHandler,
+    prestige: &'a Prestige,
+    weather: &'a Weather,
+}
+
+/// Summary of a slot, written next to the save so the slot picker doesn't have to load whole farms
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct SlotMeta {
+    pub level: usize,
+    pub money: usize,
+    // in seconds
+    pub play_time: u64,
+    pub last_played: u64,
+}
+
+pub struct SaveSlot {
+    pub name: String,
+    pub meta: Option<SlotMeta>,
+    // the directory holding every slot, SLOTS_DIR outside of tests
+    root: PathBuf,
+}
+
+impl SaveSlot {
+    fn dir(&self) -> PathBuf {
+        self.root.join(&self.name)
+    }
+
+    fn save_path(&self) -> PathBuf {
+        self.dir().join(SAVE_FILE)
+    }
+}
+
+pub struct Backup {
+    path: PathBuf,
+    pub saved_at: u64,
+}
+
+/// A save written by a newer version of the game. It's left as it is, so that version can still open it
+pub struct NewerSave {
+    pub version: usize,
+}
+
+enum MigrateError {
+    Newer(NewerSave),
+    Invalid(String),
+}
+
+/// Loads the slot's save container, migrating it (or the old six-file layout) to the current version.
+/// A save that can't be read is moved aside instead of being overwritten by a fresh farm, a save from a
+/// newer version of the game is refused and the slot shouldn't be played
+pub fn load(slot: &SaveSlot, content_ids: &ContentIds) -> Result<Option<SaveFile>, NewerSave> {
+    let save_path = slot.save_path();
+
+    let value = match fs::read_to_string(&save_path) {
+        Ok(contents) => match serde_json::from_str::<Value>(&contents) {
+            Ok(value) => value,
+            Err(e) => {
+                println!("save file is corrupted ({e}), starting a new farm");
+                set_aside(&save_path);
+                return Ok(None);
+            }
+        },
+        Err(_) => match read_legacy_layout(&slot.dir()) {
+            Some(value) => value,
+            None => return Ok(None),
+        },
+    };
+
+    match migrate(value, content_ids) {
+        Ok(save) => Ok(Some(save)),
+        Err(MigrateError::Newer(newer)) => {
+            println!(
+                "save was made by a newer version of the game (save version {}), not loading it",
+                newer.version
+            );
+            Err(newer)
+        }
+        Err(MigrateError::Invalid(e)) => {
+            println!("couldn't read save ({e}), starting a new farm");
+            set_aside(&save_path);
+            Ok(None)
+        }
+    }
+}
+
+pub fn load_backup(backup: &Backup, content_ids: &ContentIds) -> Option<SaveFile> {
+    let contents = fs::read_to_string(&backup.path).ok()?;
+    let value = serde_json::from_str(&contents).ok()?;
+
+    match migrate(value, content_ids) {
+        Ok(save) => Some(save),
+        Err(MigrateError::Newer(newer)) => {
+            println!(
+                "backup {} was made by a newer version of the game (save version {})",
+                backup.path.display(),
+                newer.version
+            );
+            None
+        }
+        Err(MigrateError::Invalid(e)) => {
+            println!("couldn't read backup {} ({e})", backup.path.display());
+            None
+        }
+    }
+}
+
+/// Backup snapshots of the slot, newest first
+pub fn list_backups(slot: &SaveSlot) -> Vec<Backup> {
+    let Ok(entries) = fs::read_dir(slot.dir().join(BACKUP_DIR)) else {
+        return vec![];
+    };
+
+    let mut backups: Vec<Backup> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let saved_at = path
+                .file_stem()?
+                .to_str()?
+                .strip_prefix("save_")?
+                .parse()
+                .ok()?;
+            Some(Backup { path, saved_at })
+        })
+        .collect();
+
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.saved_at));
+    backups
+}
+
+fn migrate(mut value: Value, content_ids: &ContentIds) -> Result<SaveFile, MigrateError> {
+    if !value.is_object() {
+        return Err(MigrateError::Invalid("not a save container".to_string()));
+    }
+
+    let version = value.get("version").and_then(Value::as_u64).unwrap_or(0) as usize;
+    if version > MIGRATIONS.len() {
+        return Err(MigrateError::Newer(NewerSave { version }));
+    }
+
+    for migration in MIGRATIONS[version..].iter() {
+        migration(&mut value);
+    }
+    value["version"] = SAVE_VERSION.into();
+
+    convert_content_refs(&mut value, content_ids, ContentRefs::Indices);
+
+    serde_json::from_value(value).map_err(|e| MigrateError::Invalid(e.to_string()))
+}
+
+/// The whole game serialized and ready to be written to its slot
+pub struct Snapshot {
+    slot: SaveSlot,
+    serialized: String,
+    meta: SlotMeta,
+}
+
+// the farm as of the last tick, written by the panic hook
+static EMERGENCY_SNAPSHOT: Mutex<Option<Snapshot>> = Mutex::new(None);
+
+/// Writes the whole game into one file. The data goes to a temporary file first and is renamed over the
+/// old save, so a crash mid-write leaves the previous save intact. What was written is also kept for the
+/// panic hook, so it never writes an older farm over this one
+pub fn write(
+    slot: &SaveSlot,
+    sim: &Simulation,
+    toolbar: &ToolbarDynamic,
+    play_time: u64,
+    content_ids: &ContentIds,
+) -> io::Result<()> {
+    let snapshot = snapshot(slot, sim, toolbar, play_time, content_ids)?;
+    write_snapshot(&snapshot)?;
+    keep(snapshot);
+    Ok(())
+}
+
+/// Keeps a snapshot of the game around for the panic hook, replacing the previous one. It costs as much
+/// as a save, so it's only worth taking when the player changed something since the last one
+pub fn keep_emergency_snapshot(
+    slot: &SaveSlot,
+    sim: &Simulation,
+    toolbar: &ToolbarDynamic,
+    play_time: u64,
+    content_ids: &ContentIds,
+) {
+    match snapshot(slot, sim, toolbar, play_time, content_ids) {
+        Ok(snapshot) => keep(snapshot),
+        Err(e) => println!("couldn't snapshot the game ({e})"),
+    }
+}
+
+fn keep(snapshot: Snapshot) {
+    if let Ok(mut kept) = EMERGENCY_SNAPSHOT.lock() {
+        *kept = Some(snapshot);
+    }
+}
+
+/// Drops the kept snapshot, so a panic after the final save doesn't write an older farm over it
+pub fn forget_emergency_snapshot() {
+    if let Ok(mut kept) = EMERGENCY_SNAPSHOT.lock() {
+        *kept = None;
+    }
+}
+
+/// Makes every panic write the last kept snapshot before the usual panic message. A hook runs wherever
+/// the panic happens and even when panics abort, unlike catching the unwind
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        // try_lock, the panic may have happened while the snapshot was being replaced
+        if let Ok(mut kept) = EMERGENCY_SNAPSHOT.try_lock()
+            && let Some(snapshot) = kept.take()
+        {
+            match write_snapshot(&snapshot) {
+                Ok(()) => println!("the game crashed, the farm was saved"),
+                Err(e) => println!("the game crashed and couldn't be saved ({e})"),
+            }
+        }
+        default_hook(info);
+    }));
+}
+
+fn snapshot(
+    slot: &SaveSlot,
+    sim: &Simulation,
+    toolbar: &ToolbarDynamic,
+    play_time: u64,
+    content_ids: &ContentIds,
+) -> io::Result<Snapshot> {
+    let saved_at = get_unix_time();
+    let save = SaveFileRef {
+        version: SAVE_VERSION,
+        saved_at,
+        play_time,
+        map: &sim.map.dynamic_data,
+        player: &sim.player,
+        workers: &sim.worker_handler,
+        animals: &sim.animal_handler.dynamic_data,
+        upgrades: &sim.upgrade_handler.dynamic_data,
+        toolbar,
+        market: &sim.market,
+        contracts: &sim.contracts,
+        achievements: &sim.achievements,
+        prestige: &sim.prestige,
+        weather: &sim.weather,
+    };
+
+    let mut value = serde_json::to_value(&save)?;
+    convert_content_refs(&mut value, content_ids, ContentRefs::Ids);
+    let serialized = serde_json::to_string_pretty(&value)?;
+
+    let meta = SlotMeta {
+        level: sim.player.level,
+        money: sim.player.money,
+        play_time,
+        last_played: saved_at,
+    };
+
+    Ok(Snapshot {
+        slot: SaveSlot {
+            name: slot.name.clone(),
+            meta: None,
+            root: slot.root.clone(),
+        },
+        serialized,
+        meta,
+    })
+}
+
+fn write_snapshot(snapshot: &Snapshot) -> io::Result<()> {
+    let slot = &snapshot.slot;
+
+    fs::create_dir_all(slot.dir())?;
+    if let Err(e) = rotate_backups(slot) {
+        println!("couldn't back up the previous save ({e})");
+    }
+    write_atomic(&slot.save_path(), &snapshot.serialized)?;
+    write_atomic(
+        &slot.dir().join(META_FILE),
+        &serde_json::to_string_pretty(&snapshot.meta)?,
+    )
+}
+
+/// Writes a session summary into the slot's summaries, named after the time, and returns where it went
+pub fn write_summary(slot: &SaveSlot, summary: &str) -> io::Result<PathBuf> {
+    let summary_dir = slot.dir().join(SUMMARY_DIR);
+    fs::create_dir_all(&summary_dir)?;
+
+    let path = summary_dir.join(format!("session_{}.txt", get_unix_time()));
+    fs::write(&path, summary)?;
+    Ok(path)
+}
+
+/// Copies the save about to be overwritten into the backups, at most once per BACKUP_INTERVAL,
+/// and drops the oldest snapshots past MAX_BACKUPS
+fn rotate_backups(slot: &SaveSlot) -> io::Result<()> {
+    let save_path = slot.save_path();
+    if !save_path.exists() {
+        return Ok(());
+    }
+
+    let now = get_unix_time();
+    let backups = list_backups(slot);
+
+    if backups
+        .first()
+        .is_some_and(|newest| now.saturating_sub(newest.saved_at) < BACKUP_INTERVAL)
+    {
+        return Ok(());
+    }
+
+    let backup_dir = slot.dir().join(BACKUP_DIR);
+    fs::create_dir_all(&backup_dir)?;
+    fs::copy(&save_path, backup_dir.join(format!("save_{now}.json")))?;
+
+    // the new snapshot takes one of the slots
+    for backup in backups.iter().skip(MAX_BACKUPS - 1) {
+        fs::remove_file(&backup.path)?;
+    }
+
+    Ok(())
+}
+
+fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    let temp_path = path.with_extension("json.tmp");
+
+    let mut file = File::create(&temp_path)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+
+    fs::rename(&temp_path, path)
+}
+
+fn set_aside(path: &Path) {
+    let _ = fs::rename(path, path.with_extension("json.corrupted"));
+}
+
+/// All slots, most recently played first
+pub fn list_slots() -> Vec<SaveSlot> {
+    let Ok(entries) = fs::read_dir(SLOTS_DIR) else {
+        return vec![];
+    };
+
+    let mut slots: Vec<SaveSlot> = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            if !entry.file_type().ok()?.is_dir() {
+                return None;
+            }
+
+            let name = entry.file_name().into_string().ok()?;
+            let meta = fs::read_to_string(entry.path().join(META_FILE))
+                .ok()
+                .and_then(|contents| serde_json::from_str(&contents).ok());
+
+            Some(SaveSlot {
+                name,
+                meta,
+                root: PathBuf::from(SLOTS_DIR),
+            })
+        })
+        .collect();
+
+    slots.sort_by(|a, b| {
+        let a_played = a.meta.as_ref().map_or(0, |meta| meta.last_played);
+        let b_played = b.meta.as_ref().map_or(0, |meta| meta.last_played);
+        b_played.cmp(&a_played).then_with(|| a.name.cmp(&b.name))
+    });
+    slots
+}
+
+/// Creates an empty slot, the farm itself is generated when the slot is first played
+pub fn create_slot(name: &str) -> io::Result<SaveSlot> {
+    let root = PathBuf::from(SLOTS_DIR);
+    let slot = SaveSlot {
+        name: unique_slot_name(&root, name),
+        meta: None,
+        root,
+    };
+    fs::create_dir_all(slot.dir())?;
+    Ok(slot)
+}
+
+pub fn copy_slot(slot: &SaveSlot) -> io::Result<()> {
+    let copy = SaveSlot {
+        name: unique_slot_name(&slot.root, &slot.name),
+        meta: None,
+        root: slot.root.clone(),
+    };
+    copy_dir(&slot.dir(), &copy.dir())
+}
+
+pub fn rename_slot(slot: &SaveSlot, new_name: &str) -> io::Result<()> {
+    let new_name = sanitize_slot_name(new_name);
+    if new_name == slot.name {
+        return Ok(());
+    }
+
+    fs::rename(
+        slot.dir(),
+        slot.root.join(unique_slot_name(&slot.root, &new_name)),
+    )
+}
+
+pub fn delete_slot(slot: &SaveSlot) -> io::Result<()> {
+    fs::remove_dir_all(slot.dir())
+}
+
+/// Moves a save made before slots existed into a slot of its own, so it shows up in the picker
+pub fn adopt_unslotted_save() -> io::Result<()> {
+    let unslotted = Path::new(UNSLOTTED_DIR);
+    let mut files: Vec<&str> = LEGACY_FILES.iter().map(|(_, file)| *file).collect();
+    files.push(SAVE_FILE);
+
+    if Path::new(SLOTS_DIR).exists() || !files.iter().any(|file| unslotted.join(file).exists()) {
+        return Ok(());
+    }
+
+    let slot = create_slot(DEFAULT_SLOT_NAME)?;
+    files.push(BACKUP_DIR);
+    for file in files {
+        let path = unslotted.join(file);
+        if path.exists() {
+            fs::rename(&path, slot.dir().join(file))?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn sanitize_slot_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .filter(|c| c.is_alphanumeric() || [' ', '-', '_'].contains(c))
+        .take(MAX_SLOT_NAME_LENGTH)
+        .collect();
+    let name = name.trim();
+
+    if name.is_empty() {
+        DEFAULT_SLOT_NAME.to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+fn unique_slot_name(root: &Path, name: &str) -> String {
+    let name = sanitize_slot_name(name);
+    if !root.join(&name).exists() {
+        return name;
+    }
+
+    let mut index = 2;
+    while root.join(format!("{name} {index}")).exists() {
+        index += 1;
+    }
+    format!("{name} {index}")
+}
+
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &to.join(entry.file_name()))?;
+        } else {
+            fs::copy(entry.path(), to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Gathers the separate save files into one version 0 object, so it can go through the migration chain
+fn read_legacy_layout(dir: &Path) -> Option<Value> {
+    let mut save = JsonMap::new();
+    save.insert("version".to_string(), 0.into());
+
+    for (key, file) in LEGACY_FILES {
+        let Ok(contents) = fs::read_to_string(dir.join(file)) else {
+            continue;
+        };
+        let Ok(value) = serde_json::from_str::<Value>(&contents) else {
+            println!("skipping unreadable {file}");
+            continue;
+        };
+        save.insert(key.to_string(), value);
+    }
+
+    if !save.contains_key("map") || !save.contains_key("player") {
+        return None;
+    }
+
+    Some(Value::Object(save))
+}
+
+fn migrate_v0_to_v1(save: &mut Value) {
+    // the save time used to live in the player data
+    let saved_at = save["player"]
+        .as_object_mut()
+        .and_then(|player| player.remove("last_online"))
+        .and_then(|last_online| last_online.as_u64())
+        .unwrap_or_else(get_unix_time);
+
+    save["saved_at"] = saved_at.into();
+}
+
+// purchased upgrades used to be stored as positions in the old upgrade list, in this order
+#[rustfmt::skip]
+const LEGACY_UPGRADE_IDS: [&str; 48] = [
+    "carrot_bronze", "carrot_silver", "carrot_gold",
+    "cabbage_bronze", "cabbage_silver", "cabbage_gold",
+    "pumpkin_bronze", "pumpkin_silver", "pumpkin_gold",
+    "potato_bronze", "potato_silver", "potato_gold",
+    "strawberry_bronze", "strawberry_silver", "strawberry_gold",
+    "apple_bronze", "apple_silver", "apple_gold",
+    "orange_bronze", "orange_silver", "orange_gold",
+    "cherry_bronze", "cherry_silver", "cherry_gold",
+    "pomegranate_bronze", "pomegranate_silver", "pomegranate_gold",
+    "coconut_bronze", "coconut_silver", "coconut_gold",
+    "chicken_bronze", "chicken_silver", "chicken_gold",
+    "pig_bronze", "pig_silver", "pig_gold",
+    "sheep_bronze", "sheep_silver", "sheep_gold",
+    "cow_bronze", "cow_silver", "cow_gold",
+    "rabbit_bronze", "rabbit_silver", "rabbit_gold",
+    "beehive_bronze", "beehive_silver", "beehive_gold",
+];
+
+fn migrate_v1_to_v2(save: &mut Value) {
+    let Some(purchased) = save
+        .get_mut("upgrades")
+        .and_then(|upgrades| upgrades.get_mut("purchased_upgrades"))
+        .and_then(|purchased| purchased.as_array_mut())
+    else {
+        return;
+    };
+
+    *purchased = purchased
+        .iter()
+        .filter_map(|index| LEGACY_UPGRADE_IDS.get(index.as_u64()? as usize))
+        .map(|id| Value::from(*id))
+        .collect();
+}
+
+/// Rewrites every reference to content in the save. Content that doesn't exist anymore is dropped,
+/// tiles holding it turn back into grass
+fn convert_content_refs(save: &mut Value, content_ids: &ContentIds, to: ContentRefs) {
+    let mut dropped = 0;
+
+    // tiles are stored as [position, tile] pairs
+    if let Some(tiles) = save.pointer_mut("/map/tiles").and_then(Value::as_array_mut) {
+        for tile in tiles.iter_mut().filter_map(|pair| pair.get_mut(1)) {
+            let converted = {
+                let Some((variant, fields)) =
+                    tile.as_object_mut().and_then(|tile| tile.iter_mut().next())
+                else {
+                    continue;
+                };
+                let (field, ids) = match variant.as_str() {
+                    "Farmland" | "Fallow" | "Blueprint" | "Trough" => ("crop", &content_ids.crops),
+                    "Tree" => ("tree", &content_ids.trees),
+                    "AnimalDrop" => ("animal", &content_ids.animals),
+                    "Flower" => ("flower", &content_ids.flowers),
+                    "Building" => ("building", &content_ids.buildings),
+                    _ => continue,
+                };
+
+                // an empty trough holds nothing to convert
+                if fields.get(field).is_some_and(Value::is_null) {
+                    continue;
+                }
+
+                match fields.get(field).and_then(|value| to.convert(value, ids)) {
+                    Some(value) => {
+                        fields[field] = value;
+                        true
+                    }
+                    None => false,
+                }
+            };
+
+            if !converted {
+                *tile = Value::from("Grass");
+                dropped += 1;
+            }
+        }
+    }
+
+    // and what's in stock in them, stored as [position, state] pairs
+    if let Some(buildings) = save
+        .pointer_mut("/map/buildings")
+        .and_then(Value::as_array_mut)
+    {
+        buildings.retain_mut(|pair| {
+            let Some(state) = pair.get_mut(1) else {
+                return false;
+            };
+            match state
+                .get("building")
+                .and_then(|value| to.convert(value, &content_ids.buildings))
+            {
+                Some(value) => {
+                    state["building"] = value;
+                    true
+                }
+                None => false,
+            }
+        });
+    }
+
+    if let Some(animals) = save
+        .pointer_mut("/animals/animals")
+        .and_then(Value::as_array_mut)
+    {
+        animals.retain_mut(|animal| {
+            match animal
+                .get("animal_type")
+                .and_then(|value| to.convert(value, &content_ids.animals))
+            {
+                Some(value) => {
+                    animal["animal_type"] = value;
+                    true
+                }
+                None => {
+                    dropped += 1;
+                    false
+                }
+            }
+        });
+    }
+
+    // crops on their way to a trough are lost if the crop is gone
+    if let Some(workers) = save
+        .pointer_mut("/workers/workers")
+        .and_then(Value::as_array_mut)
+    {
+        for carrying in workers
+            .iter_mut()
+            .filter_map(|worker| worker.get_mut("carrying"))
+            .filter(|carrying| !carrying.is_null())
+        {
+            *carrying = to
+                .convert(carrying, &content_ids.crops)
+                .unwrap_or(Value::Null);
+        }
+
+        // and so are goods on their way to storage
+        for delivering in workers
+            .iter_mut()
+            .filter_map(|worker| worker.get_mut("delivering"))
+            .filter(|delivering| !delivering.is_null())
+        {
+            match delivering
+                .get(0)
+                .and_then(|produce| convert_produce(produce, content_ids, to))
+            {
+                Some(produce) => delivering[0] = produce,
+                None => *delivering = Value::Null,
+            }
+        }
+
+        // or to a building
+        for supplying in workers
+            .iter_mut()
+            .filter_map(|worker| worker.get_mut("supplying"))
+            .filter(|supplying| !supplying.is_null())
+        {
+            match supplying
+                .get(0)
+                .and_then(|produce| convert_produce(produce, content_ids, to))
+            {
+                Some(produce) => supplying[0] = produce,
+                None => *supplying = Value::Null,
+            }
+        }
+    }
+
+    // stored goods are [produce, amount] pairs
+    if let Some(inventory) = save
+        .pointer_mut("/player/inventory")
+        .and_then(Value::as_object_mut)
+    {
+        if let Some(items) = inventory.get_mut("items").and_then(Value::as_array_mut) {
+            items.retain_mut(|item| {
+                match item
+                    .get(0)
+                    .and_then(|produce| convert_produce(produce, content_ids, to))
+                {
+                    Some(produce) => {
+                        item[0] = produce;
+                        true
+                    }
+                    None => false,
+                }
+            });
+        }
+
+        if let Some(kept) = inventory.get_mut("kept").and_then(Value::as_array_mut) {
+            *kept = kept
+                .iter()
+                .filter_map(|produce| convert_produce(produce, content_ids, to))
+                .collect();
+        }
+    }
+
+    // prices are kept as [produce, prices] pairs, goods that are gone have no market anymore
+    // [produce, value] pairs
+    for pointer in ["/market/products", "/player/stats/harvests"] {
+        let Some(pairs) = save.pointer_mut(pointer).and_then(Value::as_array_mut) else {
+            continue;
+        };
+        pairs.retain_mut(|pair| {
+            match pair
+                .get(0)
+                .and_then(|produce| convert_produce(produce, content_ids, to))
+            {
+                Some(produce) => {
+                    pair[0] = produce;
+                    true
+                }
+                None => false,
+            }
+        });
+    }
+
+    // orders for goods that are gone can't be finished anymore
+    if let Some(contracts) = save
+        .pointer_mut("/contracts/contracts")
+        .and_then(Value::as_array_mut)
+    {
+        contracts.retain_mut(|contract| {
+            let Some(requirements) = contract
+                .get_mut("requirements")
+                .and_then(Value::as_array_mut)
+            else {
+                return false;
+            };
+            requirements.iter_mut().all(|requirement| {
+                match requirement
+                    .get(0)
+                    .and_then(|produce| convert_produce(produce, content_ids, to))
+                {
+                    Some(produce) => {
+                        requirement[0] = produce;
+                        true
+                    }
+                    None => false,
+                }
+            })
+        });
+    }
+
+    if let Some(toolbar) = save.get_mut("toolbar").and_then(Value::as_object_mut) {
+        for (key, ids) in [
+            ("crop_amount", &content_ids.crops),
+            ("tree_amount", &content_ids.trees),
+            ("animal_amount", &content_ids.animals),
+            ("beekeeping_amount", &content_ids.beekeeping),
+            ("misc_amount", &content_ids.misc),
+            ("building_amount", &content_ids.buildings),
+        ] {
+            let Some(amounts) = toolbar.get_mut(key).and_then(Value::as_object_mut) else {
+                continue;
+            };
+
+            *amounts = std::mem::take(amounts)
+                .into_iter()
+                .filter_map(|(key, amount)| Some((to.convert_key(&key, ids)?, amount)))
+                .collect();
+        }
+    }
+
+    if dropped > 0 {
+        println!(
+            "{dropped} tiles and animals in the save refer to content that doesn't exist anymore"
+        );
+    }
+}
+
+/// Produce is stored as {"Crop": crop} and the like, honey has nothing in it to convert
+fn convert_produce(produce: &Value, content_ids: &ContentIds, to: ContentRefs) -> Option<Value> {
+    let Some((variant, value)) = produce
+        .as_object()
+        .and_then(|produce| produce.iter().next())
+    else {
+        return Some(produce.clone());
+    };
+
+    let ids = match variant.as_str() {
+        "Crop" => &content_ids.crops,
+        "Tree" => &content_ids.trees,
+        "Animal" => &content_ids.animals,
+        "Good" => &content_ids.goods,
+        _ => return None,
+    };
+
+    let mut converted = JsonMap::new();
+    converted.insert(variant.clone(), to.convert(value, ids)?);
+    Some(Value::Object(converted))
+}
+
+// content was stored as positions in the static data, in this order
+fn legacy_content_ids() -> ContentIds {
+    let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect();
+
+    ContentIds {
+        crops: ids(&["carrot", "cabbage", "pumpkin", "potato", "strawberry"]),
+        trees: ids(&["apple", "orange", "cherry", "pomegranate", "coconut"]),
+        animals: ids(&["chicken", "pig", "sheep", "cow", "rabbit"]),
+        hives: ids(&["beehive"]),
+        flowers: ids(&["dandelion", "daisy", "tulip", "rose", "forget_me_not"]),
+        beekeeping: ids(&[
+            "beehive",
+            "dandelion",
+            "daisy",
+            "tulip",
+            "rose",
+            "forget_me_not",
+        ]),
+        misc: ids(&["worker", "remove_plants"]),
+        buildings: vec![],
+        goods: vec![],
+    }
+}
+
+fn migrate_v2_to_v3(save: &mut Value) {
+    convert_content_refs(save, &legacy_content_ids(), ContentRefs::Ids);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{localization::load_language, toolbar::Canvas};
+
+    /// A slot in its own directory under the system temp dir, emptied before the test
+    fn temp_slot(test: &str) -> SaveSlot {
+        let root = std::env::temp_dir().join(format!("uyta_{test}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let slot = SaveSlot {
+            name: DEFAULT_SLOT_NAME.to_string(),
+            meta: None,
+            root,
+        };
+        fs::create_dir_all(slot.dir()).unwrap();
+        slot
+    }
+
+    fn index_of(ids: &[String], id: &str) -> usize {
+        ContentIds::index_of(ids, id).unwrap()
+    }
+
+    #[test]
+    fn legacy_layout_loads_through_every_migration() {
+        let slot = temp_slot("legacy_layout");
+        let files = [
+            (
+                "map_save.json",
+                r#"{
+                    "tiles": [[[0, 0], {"Farmland": {"crop": 1, "stage": 2}}],
+                              [[1, 0], {"Tree": {"tree": 2, "grow": 0, "stage": 1}}],
+                              [[2, 0], "Grass"]],
+                    "occupation_map": [],
+                    "land_expansion_points": [],
+                    "next_expansion_cost": 500,
+                    "biome_seed": 7
+                }"#,
+            ),
+            (
+                "player_save.json",
+                r#"{"money": 1234, "alltime_max_money": 2000, "level": 3, "exp": 5,
+                    "exp_to_lvl_up": 40, "last_online": 1700000000}"#,
+            ),
+            ("workers_save.json", r#"{"workers": []}"#),
+            (
+                "animals_save.json",
+                r#"{"animals": [{"animal_type": 3, "drop_bar": 0, "position": [1, 1],
+                                  "display_position": [0.0, 0.0]}]}"#,
+            ),
+            ("upgrades_save.json", r#"{"purchased_upgrades": [0, 4]}"#),
+            (
+                "toolbar_save.json",
+                r#"{"crop_amount": {"1": 2}, "tree_amount": {}, "animal_amount": {"3": 1},
+                    "beekeeping_amount": {}, "misc_amount": {"0": 1}}"#,
+            ),
+        ];
+        for (file, contents) in files {
+            fs::write(slot.dir().join(file), contents).unwrap();
+        }
+
+        let ids = ContentIds::load();
+        let save = load(&slot, &ids).ok().flatten().unwrap();
+
+        assert_eq!(save.saved_at, 1_700_000_000);
+        assert_eq!(save.player.money, 1234);
+        assert_eq!(save.map.next_expansion_cost, 500);
+        assert_eq!(
+            save.map.tiles[&(0, 0)],
+            crate::map::TileType::Farmland {
+                crop: index_of(&ids.crops, "cabbage"),
+                stage: 2
+            }
+        );
+        assert_eq!(
+            save.map.tiles[&(1, 0)],
+            crate::map::TileType::Tree {
+                tree: index_of(&ids.trees, "cherry"),
+                grow: 0,
+                stage: 1
+            }
+        );
+        assert_eq!(
+            save.animals.unwrap().animals[0].animal_type(),
+            index_of(&ids.animals, "cow")
+        );
+        assert_eq!(
+            save.upgrades.unwrap().purchased_upgrades,
+            ["carrot_bronze", "cabbage_silver"]
+        );
+        let toolbar = save.toolbar.unwrap();
+        assert_eq!(toolbar.crop_amount[&index_of(&ids.crops, "cabbage")], 2);
+        assert_eq!(toolbar.animal_amount[&index_of(&ids.animals, "cow")], 1);
+
+        fs::remove_dir_all(&slot.root).unwrap();
+    }
+
+    #[test]
+    fn saved_indices_become_ids() {
+        let mut save = serde_json::json!({
+            "version": 2,
+            "map": {
+                "tiles": [[[0, 0], {"Farmland": {"crop": 4, "stage": 0}}],
+                          [[1, 0], {"Flower": {"flower": 1}}],
+                          [[2, 0], {"Trough": {"crop": null, "amount": 0}}],
+                          [[3, 0], {"Farmland": {"crop": 99, "stage": 0}}]]
+            },
+            "animals": {"animals": [{"animal_type": 0}, {"animal_type": 99}]},
+            "player": {"inventory": {"items": [[{"Tree": 0}, 3], ["Honey", 1]], "kept": [{"Animal": 1}]}},
+            "toolbar": {"crop_amount": {"0": 1, "99": 1}, "misc_amount": {"1": 2}}
+        });
+
+        migrate_v2_to_v3(&mut save);
+
+        let tiles = &save["map"]["tiles"];
+        assert_eq!(tiles[0][1]["Farmland"]["crop"], "strawberry");
+        assert_eq!(tiles[1][1]["Flower"]["flower"], "daisy");
+        assert!(tiles[2][1]["Trough"]["crop"].is_null());
+        // content that never existed turns back into grass
+        assert_eq!(tiles[3][1], "Grass");
+
+        assert_eq!(
+            save["animals"]["animals"],
+            serde_json::json!([{"animal_type": "chicken"}])
+        );
+        assert_eq!(
+            save["player"]["inventory"]["items"],
+            serde_json::json!([[{"Tree": "apple"}, 3], ["Honey", 1]])
+        );
+        assert_eq!(
+            save["player"]["inventory"]["kept"],
+            serde_json::json!([{"Animal": "pig"}])
+        );
+        assert_eq!(
+            save["toolbar"]["crop_amount"],
+            serde_json::json!({"carrot": 1})
+        );
+        assert_eq!(
+            save["toolbar"]["misc_amount"],
+            serde_json::json!({"remove_plants": 2})
+        );
+    }
+
+    #[test]
+    fn upgrade_indices_become_upgrade_ids() {
+        let mut save = serde_json::json!({
+            "upgrades": {"purchased_upgrades": [0, 4, 47, 100]}
+        });
+
+        migrate_v1_to_v2(&mut save);
+
+        assert_eq!(
+            save["upgrades"]["purchased_upgrades"],
+            serde_json::json!(["carrot_bronze", "cabbage_silver", "beehive_gold"])
+        );
+    }
+
+    #[test]
+    fn newer_saves_are_refused_and_left_alone() {
+        let slot = temp_slot("newer_save");
+        let contents = format!(r#"{{"version": {}, "saved_at": 0}}"#, SAVE_VERSION + 1);
+        fs::write(slot.save_path(), &contents).unwrap();
+
+        let result = load(&slot, &ContentIds::load());
+
+        assert!(result.is_err_and(|newer| newer.version == SAVE_VERSION as usize + 1));
+        assert_eq!(fs::read_to_string(slot.save_path()).unwrap(), contents);
+        assert!(!slot.save_path().with_extension("json.corrupted").exists());
+
+        fs::remove_dir_all(&slot.root).unwrap();
+    }
+
+    #[test]
+    fn backups_are_rotated_up_to_the_limit() {
+        let slot = temp_slot("backup_rotation");
+        fs::write(slot.save_path(), "{}").unwrap();
+
+        // all of them old enough for a new snapshot to be taken
+        let backup_dir = slot.dir().join(BACKUP_DIR);
+        fs::create_dir_all(&backup_dir).unwrap();
+        let oldest = get_unix_time() - BACKUP_INTERVAL * (MAX_BACKUPS as u64 + 1);
+        for i in 0..MAX_BACKUPS as u64 {
+            let saved_at = oldest + i * BACKUP_INTERVAL;
+            fs::write(backup_dir.join(format!("save_{saved_at}.json")), "{}").unwrap();
+        }
+
+        rotate_backups(&slot).unwrap();
+
+        let backups = list_backups(&slot);
+        assert_eq!(backups.len(), MAX_BACKUPS);
+        assert!(backups.iter().all(|backup| backup.saved_at != oldest));
+        assert!(get_unix_time() - backups[0].saved_at < BACKUP_INTERVAL);
+
+        fs::remove_dir_all(&slot.root).unwrap();
+    }
+
+    #[test]
+    fn backups_restore_an_earlier_farm() {
+        let slot = temp_slot("backup_restore");
+        let language_data = load_language("en");
+        let content_ids = ContentIds::load();
+        let toolbar = Canvas::new(&language_data, None).toolbar_data.dynamic_data;
+        let mut sim = Simulation::new(&language_data, &ContentIds::load(), None);
+
+        // the first save has nothing to back up, the second backs up the first
+        // and the third comes too soon after it for another backup
+        for money in [111, 222, 333] {
+            sim.player.money = money;
+            write(&slot, &sim, &toolbar, 0, &content_ids).unwrap();
+        }
+
+        let backups = list_backups(&slot);
+        assert_eq!(backups.len(), 1);
+
+        let restored = load_backup(&backups[0], &content_ids).unwrap();
+        assert_eq!(restored.player.money, 111);
+        let latest = load(&slot, &content_ids).ok().flatten().unwrap();
+        assert_eq!(latest.player.money, 333);
+
+        fs::remove_dir_all(&slot.root).unwrap();
+    }
+}
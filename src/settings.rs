@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+use crate::common::parse_json;
+
+#[derive(Deserialize, Serialize)]
+pub struct GameSettigns {
+    pub master_volume: f32,
+    pub is_fullscreen: bool,
+    pub short_numbers: bool,
+    pub language: String,
+    // in seconds
+    #[serde(default = "default_autosave_interval")]
+    pub autosave_interval: usize,
+}
+
+fn default_autosave_interval() -> usize {
+    60
+}
+
+impl GameSettigns {
+    pub fn new() -> Self {
+        let res = parse_json("dynamic/settings.json");
+        match res {
+            Ok(settings) => settings,
+            Err(_) => Self {
+                master_volume: 0.5,
+                is_fullscreen: true,
+                short_numbers: true,
+                language: "ru".to_owned(),
+                autosave_interval: default_autosave_interval(),
+            },
+        }
+    }
+
+    pub fn save(&self) {
+        let serialized = serde_json::to_string_pretty(self).expect("err");
+        std::fs::create_dir_all("dynamic").expect("Couldn't create dir");
+        std::fs::write("dynamic/settings.json", serialized)
+            .expect("Couldn't write settings data to json");
+    }
+}
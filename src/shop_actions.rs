@@ -0,0 +1,804 @@
+use crate::{
+    animal::{Animal, AnimalHandler},
+    map::{Map, TileType},
+    player::Player,
+    sim::Produce,
+    toolbar::{Canvas, MenuMode},
+    tutorial::Tutorial,
+    upgrades::UpgradeHandler,
+    worker::{Worker, WorkerHandler},
+};
+
+// share of the last purchase price given back when selling a worker or an animal
+const SELL_REFUND: f32 = 0.5;
+
+/// What the shop tools do to the farm. It's kept apart from the player, as it works off the toolbar.
+/// Each tool says whether it changed anything, so the farm is only snapshotted when there's news
+impl Player {
+    /// Plants the selected crop, or with `plan` only marks grass with a blueprint for workers to plant
+    pub fn plant_crops(
+        &mut self,
+        canvas: &mut Canvas,
+        map: &mut Map,
+        selected_tile: &(i32, i32),
+        tutorial: &mut Tutorial,
+        upgrade_handler: &UpgradeHandler,
+        plan: bool,
+    ) -> bool {
+        // blueprints and fresh fields change what workers have to do there
+        map.touch(*selected_tile);
+        let Some(tile) = map.dynamic_data.tiles.get_mut(selected_tile) else {
+            return false;
+        };
+
+        // planning is done by dragging over the field, so it shouldn't replace anything on the way
+        if plan && *tile != TileType::Grass {
+            return false;
+        }
+
+        tutorial.complete_step(2);
+
+        match tile {
+            TileType::Grass => {
+                let price = canvas
+                    .toolbar_data
+                    .get_price_for_crop(canvas.selected, upgrade_handler);
+                if self.money >= price {
+                    self.money -= price;
+                    let amount = canvas
+                        .toolbar_data
+                        .dynamic_data
+                        .crop_amount
+                        .get_mut(&canvas.selected)
+                        .unwrap();
+                    *amount += 1;
+                    *tile = if plan {
+                        TileType::Blueprint {
+                            crop: canvas.selected,
+                        }
+                    } else {
+                        TileType::Farmland {
+                            crop: canvas.selected,
+                            stage: 0,
+                        }
+                    };
+                    return true;
+                }
+            }
+            TileType::Fallow { crop } | TileType::Blueprint { crop } => {
+                if canvas.mode != MenuMode::Crops {
+                    return false;
+                }
+
+                // the seeds are already paid for, so planting the same crop by hand is free
+                if *crop == canvas.selected {
+                    *tile = TileType::Farmland {
+                        crop: *crop,
+                        stage: 0,
+                    };
+                    return true;
+                }
+
+                let price = canvas
+                    .toolbar_data
+                    .get_price_for_crop(canvas.selected, upgrade_handler);
+                if self.money >= price {
+                    let replaced_amount = canvas
+                        .toolbar_data
+                        .dynamic_data
+                        .crop_amount
+                        .get_mut(crop)
+                        .unwrap();
+                    *replaced_amount -= 1;
+
+                    let amount = canvas
+                        .toolbar_data
+                        .dynamic_data
+                        .crop_amount
+                        .get_mut(&canvas.selected)
+                        .unwrap();
+                    *amount += 1;
+
+                    self.money -= price;
+                    *tile = TileType::Farmland {
+                        crop: canvas.selected,
+                        stage: 0,
+                    };
+                    return true;
+                }
+            }
+            TileType::Farmland { crop, stage } => {
+                if canvas.mode != MenuMode::Crops {
+                    return false;
+                }
+
+                if *crop != canvas.selected {
+                    let price = canvas
+                        .toolbar_data
+                        .get_price_for_crop(canvas.selected, upgrade_handler);
+                    if self.money >= price {
+                        let replaced_amount = canvas
+                            .toolbar_data
+                            .dynamic_data
+                            .crop_amount
+                            .get_mut(crop)
+                            .unwrap();
+                        *replaced_amount -= 1;
+
+                        let amount = canvas
+                            .toolbar_data
+                            .dynamic_data
+                            .crop_amount
+                            .get_mut(&canvas.selected)
+                            .unwrap();
+                        *amount += 1;
+
+                        self.money -= price;
+                        *crop = canvas.selected;
+                        *stage = 0;
+                        return true;
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        false
+    }
+
+    pub fn plant_trees(
+        &mut self,
+        canvas: &mut Canvas,
+        map: &mut Map,
+        selected_tile: &(i32, i32),
+        upgrade_handler: &UpgradeHandler,
+    ) -> bool {
+        let Some(tile) = map.dynamic_data.tiles.get_mut(selected_tile) else {
+            return false;
+        };
+
+        if *tile == TileType::Grass {
+            let price = canvas
+                .toolbar_data
+                .get_price_for_tree(canvas.selected, upgrade_handler);
+            if self.money >= price {
+                self.money -= price;
+                let amount = canvas
+                    .toolbar_data
+                    .dynamic_data
+                    .tree_amount
+                    .get_mut(&canvas.selected)
+                    .unwrap();
+                *amount += 1;
+                *tile = TileType::Tree {
+                    tree: canvas.selected,
+                    grow: 0,
+                    stage: 0,
+                };
+                map.mark_changed(*selected_tile);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    pub fn spawn_animals(
+        &mut self,
+        canvas: &mut Canvas,
+        map: &mut Map,
+        selected_tile: &(i32, i32),
+        animal_handler: &mut AnimalHandler,
+        upgrade_handler: &UpgradeHandler,
+    ) -> bool {
+        map.update_pens();
+        let Some(tile) = map.dynamic_data.tiles.get(selected_tile) else {
+            return false;
+        };
+
+        match tile {
+            // animals only go in pens, so they and their drops stay off the fields
+            TileType::Grass if map.pen_at(*selected_tile).is_some() => {
+                let price = canvas
+                    .toolbar_data
+                    .get_price_for_animal(canvas.selected, upgrade_handler);
+                if self.money >= price {
+                    self.money -= price;
+                    let amount = canvas
+                        .toolbar_data
+                        .dynamic_data
+                        .animal_amount
+                        .get_mut(&canvas.selected)
+                        .unwrap();
+                    *amount += 1;
+                    animal_handler.add_animal(Animal::new(
+                        canvas.selected,
+                        selected_tile.0,
+                        selected_tile.1,
+                    ));
+                    return true;
+                }
+            }
+            _ => {}
+        }
+
+        false
+    }
+
+    pub fn perform_beekeeping(
+        &mut self,
+        canvas: &mut Canvas,
+        selected_tile: &(i32, i32),
+        map: &mut Map,
+        upgrade_handler: &UpgradeHandler,
+    ) -> bool {
+        let Some(tile) = map.dynamic_data.tiles.get_mut(selected_tile) else {
+            return false;
+        };
+
+        match tile {
+            TileType::Grass => {
+                let price = canvas
+                    .toolbar_data
+                    .get_price_for_beekeeping(canvas.selected, upgrade_handler);
+
+                if self.money >= price {
+                    self.money -= price;
+                    let amount = canvas
+                        .toolbar_data
+                        .dynamic_data
+                        .beekeeping_amount
+                        .get_mut(&canvas.selected)
+                        .unwrap();
+                    *amount += 1;
+                    if canvas.selected == 0 {
+                        *tile = TileType::Beehive {
+                            stage: 0,
+                            price: 0,
+                            xp: 0,
+                        };
+                        map.mark_changed(*selected_tile);
+                    } else {
+                        *tile = TileType::Flower {
+                            flower: canvas.selected - 1,
+                        };
+                    }
+                    return true;
+                }
+            }
+            TileType::Flower { flower }
+                if canvas.selected != 0 && *flower != canvas.selected - 1 =>
+            {
+                let price = canvas
+                    .toolbar_data
+                    .get_price_for_beekeeping(canvas.selected, upgrade_handler);
+                if self.money >= price {
+                    let replaced_amount = canvas
+                        .toolbar_data
+                        .dynamic_data
+                        .beekeeping_amount
+                        .get_mut(&(*flower + 1))
+                        .unwrap();
+                    *replaced_amount -= 1;
+
+                    let amount = canvas
+                        .toolbar_data
+                        .dynamic_data
+                        .beekeeping_amount
+                        .get_mut(&canvas.selected)
+                        .unwrap();
+                    *amount += 1;
+
+                    self.money -= price;
+                    *flower = canvas.selected - 1;
+                    return true;
+                }
+            }
+            _ => {}
+        };
+
+        false
+    }
+
+    /// Uses the selected misc tool on the tile. Tools are told apart by their id rather than their
+    /// place in the toolbar, so the toolbar can be reordered and added to
+    pub fn perform_misc(
+        &mut self,
+        canvas: &mut Canvas,
+        worker_handler: &mut WorkerHandler,
+        animal_handler: &mut AnimalHandler,
+        selected_tile: &(i32, i32),
+        map: &mut Map,
+        upgrade_handler: &UpgradeHandler,
+    ) -> bool {
+        let Some(tool) = canvas.selected_misc().map(str::to_string) else {
+            return false;
+        };
+
+        let tile = match tool.as_str() {
+            "sell" => {
+                return self.sell(
+                    canvas,
+                    worker_handler,
+                    animal_handler,
+                    selected_tile,
+                    upgrade_handler,
+                );
+            }
+            "remove_plants" => return self.clear_tile(canvas, selected_tile, map),
+            "worker" => None,
+            "rest_hut" => Some(TileType::RestHut),
+            "storage" => Some(TileType::Storage),
+            "fence" => Some(TileType::Fence),
+            "gate" => Some(TileType::Gate),
+            "trough" => Some(TileType::Trough {
+                crop: None,
+                amount: 0,
+            }),
+            _ => return false,
+        };
+
+        let price = canvas
+            .toolbar_data
+            .get_price_for_misc(canvas.selected, upgrade_handler);
+        if self.money < price {
+            return false;
+        }
+
+        match tile {
+            None => {
+                if !map.dynamic_data.tiles.contains_key(selected_tile) {
+                    return false;
+                }
+
+                let mut worker = Worker::new(selected_tile.0, selected_tile.1);
+                worker.hired = true;
+                worker_handler.add_worker(worker);
+                self.stats.workers_hired += 1;
+            }
+            Some(tile) => {
+                // everything else goes on empty grass, with no animal in the way
+                if map.dynamic_data.tiles.get(selected_tile) != Some(&TileType::Grass)
+                    || animal_handler
+                        .dynamic_data
+                        .animals
+                        .iter()
+                        .any(|animal| animal.position == *selected_tile)
+                {
+                    return false;
+                }
+
+                // a fence where a worker stands or is headed would wall it in
+                if tile == TileType::Fence
+                    && (worker_handler
+                        .workers
+                        .iter()
+                        .any(|worker| worker.position == *selected_tile)
+                        || worker_handler.job_board.holder(*selected_tile).is_some())
+                {
+                    return false;
+                }
+
+                map.dynamic_data.tiles.insert(*selected_tile, tile);
+                map.mark_changed(*selected_tile);
+            }
+        }
+
+        self.money -= price;
+        *canvas
+            .toolbar_data
+            .dynamic_data
+            .misc_amount
+            .get_mut(&canvas.selected)
+            .unwrap() += 1;
+        true
+    }
+
+    /// Clears whatever the player put on the tile back to grass, buildings come down whole.
+    /// Feed left in a trough goes to storage if there's room
+    fn clear_tile(
+        &mut self,
+        canvas: &mut Canvas,
+        selected_tile: &(i32, i32),
+        map: &mut Map,
+    ) -> bool {
+        let Some(tile) = map.dynamic_data.tiles.get(selected_tile).copied() else {
+            return false;
+        };
+
+        if let TileType::Building { origin, .. } = tile {
+            return self.remove_building(canvas, origin, map);
+        }
+
+        let toolbar_data = &mut canvas.toolbar_data;
+        let amounts = &mut toolbar_data.dynamic_data;
+        let replaced_amount = match tile {
+            TileType::Tree { tree, .. } => amounts.tree_amount.get_mut(&tree),
+            TileType::Farmland { crop, .. }
+            | TileType::Fallow { crop }
+            | TileType::Blueprint { crop } => amounts.crop_amount.get_mut(&crop),
+            TileType::Beehive { .. } => amounts.beekeeping_amount.get_mut(&0),
+            TileType::Flower { flower } => amounts.beekeeping_amount.get_mut(&(flower + 1)),
+            _ => {
+                let tool = match tile {
+                    TileType::RestHut => "rest_hut",
+                    TileType::Fence => "fence",
+                    TileType::Gate => "gate",
+                    TileType::Trough { .. } => "trough",
+                    TileType::Storage => "storage",
+                    _ => return false,
+                };
+                toolbar_data
+                    .static_data
+                    .misc_index(tool)
+                    .and_then(|index| amounts.misc_amount.get_mut(&index))
+            }
+        };
+        if let Some(replaced_amount) = replaced_amount {
+            *replaced_amount = replaced_amount.saturating_sub(1);
+        }
+
+        map.dynamic_data
+            .tiles
+            .insert(*selected_tile, TileType::Grass);
+        map.mark_changed(*selected_tile);
+
+        if let TileType::Trough {
+            crop: Some(crop),
+            amount,
+        } = tile
+        {
+            self.inventory.add(Produce::Crop(crop), amount, map);
+        }
+        true
+    }
+
+    /// Puts the selected building down with its top left corner on the tile. Every tile it covers has
+    /// to be empty grass with no animal on it
+    pub fn place_building(
+        &mut self,
+        canvas: &mut Canvas,
+        animal_handler: &AnimalHandler,
+        selected_tile: &(i32, i32),
+        map: &mut Map,
+    ) -> bool {
+        let price = canvas.toolbar_data.get_price_for_building(canvas.selected);
+        let footprint = map.building_footprint(canvas.selected, *selected_tile);
+        let clear = footprint.iter().all(|position| {
+            map.dynamic_data.tiles.get(position) == Some(&TileType::Grass)
+                && !animal_handler
+                    .dynamic_data
+                    .animals
+                    .iter()
+                    .any(|animal| animal.position == *position)
+        });
+
+        if self.money < price || !clear {
+            return false;
+        }
+
+        map.place_building(canvas.selected, *selected_tile);
+        self.money -= price;
+        *canvas
+            .toolbar_data
+            .dynamic_data
+            .building_amount
+            .get_mut(&canvas.selected)
+            .unwrap() += 1;
+        true
+    }
+
+    /// Takes the whole building down, whatever it had in stock goes to storage if there's room
+    fn remove_building(&mut self, canvas: &mut Canvas, origin: (i32, i32), map: &mut Map) -> bool {
+        let Some(state) = map.remove_building(origin) else {
+            return false;
+        };
+
+        let building = &map.recipes.buildings[state.building];
+        for ((produce, _), stock) in building.inputs.iter().zip(state.stock.iter()) {
+            self.inventory.add(*produce, *stock, map);
+        }
+        self.inventory
+            .add(Produce::Good(building.output), state.output, map);
+
+        let amount = canvas
+            .toolbar_data
+            .dynamic_data
+            .building_amount
+            .get_mut(&state.building)
+            .unwrap();
+        *amount = amount.saturating_sub(1);
+        true
+    }
+
+    /// Sells the worker or, if there's none, the animal on the tile, for part of what the last one cost.
+    /// Workers that came with the farm or for free and animals born in a pen weren't bought, so they go
+    /// without a refund
+    fn sell(
+        &mut self,
+        canvas: &mut Canvas,
+        worker_handler: &mut WorkerHandler,
+        animal_handler: &mut AnimalHandler,
+        selected_tile: &(i32, i32),
+        upgrade_handler: &UpgradeHandler,
+    ) -> bool {
+        let toolbar_data = &mut canvas.toolbar_data;
+
+        let price = if let Some(worker) =
+            worker_handler.dismiss_worker_at(*selected_tile, &mut self.inventory)
+        {
+            if !worker.hired {
+                return true;
+            }
+            let Some(index) = toolbar_data.static_data.misc_index("worker") else {
+                return true;
+            };
+            let amount = toolbar_data
+                .dynamic_data
+                .misc_amount
+                .get_mut(&index)
+                .unwrap();
+            if *amount == 0 {
+                return true;
+            }
+            *amount -= 1;
+            toolbar_data.get_price_for_misc(index, upgrade_handler)
+        } else if let Some(animal) = animal_handler.remove_animal_at(*selected_tile) {
+            if !animal.bought {
+                return true;
+            }
+            let animal_type = animal.animal_type();
+            let amount = toolbar_data
+                .dynamic_data
+                .animal_amount
+                .get_mut(&animal_type)
+                .unwrap();
+            if *amount == 0 {
+                return true;
+            }
+            *amount -= 1;
+            toolbar_data.get_price_for_animal(animal_type, upgrade_handler)
+        } else {
+            return false;
+        };
+
+        self.money += (price as f32 * SELL_REFUND) as usize;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        content::ContentIds, localization::load_language, sim::Simulation, worker::JobType,
+    };
+
+    fn with_misc_tool(tool: &str) -> (Simulation, Canvas) {
+        let language_data = load_language("en");
+        let mut sim = Simulation::new(&language_data, &ContentIds::load(), None);
+        sim.player.money = 1_000_000;
+
+        let mut canvas = Canvas::new(&language_data, None);
+        canvas.mode = MenuMode::Misc;
+        canvas.selected = canvas.toolbar_data.static_data.misc_index(tool).unwrap();
+        (sim, canvas)
+    }
+
+    fn use_tool(sim: &mut Simulation, canvas: &mut Canvas, position: (i32, i32)) -> bool {
+        sim.player.perform_misc(
+            canvas,
+            &mut sim.worker_handler,
+            &mut sim.animal_handler,
+            &position,
+            &mut sim.map,
+            &sim.upgrade_handler,
+        )
+    }
+
+    #[test]
+    fn fences_never_wall_in_workers() {
+        let (mut sim, mut canvas) = with_misc_tool("fence");
+        let worker = &sim.worker_handler.workers[0];
+        let (standing, id) = (worker.position, worker.id);
+        sim.worker_handler
+            .job_board
+            .reserve((1, 1), id, JobType::Harvest, 2);
+
+        for position in [standing, (1, 1), (2, 2)] {
+            use_tool(&mut sim, &mut canvas, position);
+        }
+
+        let tiles = &sim.map.dynamic_data.tiles;
+        assert_eq!(tiles[&standing], TileType::Grass);
+        assert_eq!(tiles[&(1, 1)], TileType::Grass);
+        assert_eq!(tiles[&(2, 2)], TileType::Fence);
+    }
+
+    #[test]
+    fn tools_say_whether_they_changed_the_farm() {
+        let (mut sim, mut canvas) = with_misc_tool("storage");
+        assert!(use_tool(&mut sim, &mut canvas, (2, 2)));
+        // the tile is taken now
+        assert!(!use_tool(&mut sim, &mut canvas, (2, 2)));
+
+        sim.player.money = 0;
+        assert!(!use_tool(&mut sim, &mut canvas, (1, 1)));
+        assert_eq!(sim.map.dynamic_data.tiles[&(1, 1)], TileType::Grass);
+    }
+
+    #[test]
+    fn tools_are_found_by_id_wherever_they_are() {
+        let (mut sim, mut canvas) = with_misc_tool("storage");
+        canvas.toolbar_data.static_data.misc.reverse();
+        canvas.selected = canvas
+            .toolbar_data
+            .static_data
+            .misc_index("storage")
+            .unwrap();
+
+        use_tool(&mut sim, &mut canvas, (2, 2));
+
+        assert_eq!(sim.map.dynamic_data.tiles[&(2, 2)], TileType::Storage);
+    }
+
+    #[test]
+    fn removed_troughs_give_their_feed_back() {
+        let (mut sim, mut canvas) = with_misc_tool("trough");
+        use_tool(&mut sim, &mut canvas, (2, 2));
+        sim.map.dynamic_data.tiles.insert(
+            (2, 2),
+            TileType::Trough {
+                crop: Some(0),
+                amount: 5,
+            },
+        );
+        sim.map
+            .dynamic_data
+            .tiles
+            .insert((-2, -2), TileType::Storage);
+        sim.map.mark_changed((-2, -2));
+
+        canvas.selected = canvas
+            .toolbar_data
+            .static_data
+            .misc_index("remove_plants")
+            .unwrap();
+        use_tool(&mut sim, &mut canvas, (2, 2));
+
+        assert_eq!(sim.map.dynamic_data.tiles[&(2, 2)], TileType::Grass);
+        assert_eq!(sim.player.inventory.amount(Produce::Crop(0)), 5);
+        let trough = canvas
+            .toolbar_data
+            .static_data
+            .misc_index("trough")
+            .unwrap();
+        assert_eq!(canvas.toolbar_data.dynamic_data.misc_amount[&trough], 0);
+    }
+
+    #[test]
+    fn only_hired_workers_are_sold_for_a_refund() {
+        let (mut sim, mut canvas) = with_misc_tool("worker");
+        let free = sim.worker_handler.workers[0].position;
+        use_tool(&mut sim, &mut canvas, (2, 2));
+
+        let misc = &canvas.toolbar_data.static_data;
+        let (worker, sell) = (
+            misc.misc_index("worker").unwrap(),
+            misc.misc_index("sell").unwrap(),
+        );
+        canvas.selected = sell;
+        let money = sim.player.money;
+
+        // like the one that came with the farm, or one from a contract
+        use_tool(&mut sim, &mut canvas, free);
+        assert_eq!(sim.player.money, money);
+        assert_eq!(canvas.toolbar_data.dynamic_data.misc_amount[&worker], 1);
+
+        use_tool(&mut sim, &mut canvas, (2, 2));
+        assert!(sim.player.money > money);
+        assert_eq!(canvas.toolbar_data.dynamic_data.misc_amount[&worker], 0);
+        assert!(sim.worker_handler.workers.is_empty());
+    }
+
+    #[test]
+    fn sold_workers_refund_half_and_leave_their_jobs_and_goods() {
+        let (mut sim, mut canvas) = with_misc_tool("worker");
+        let worker = canvas
+            .toolbar_data
+            .static_data
+            .misc_index("worker")
+            .unwrap();
+        let first_price = canvas
+            .toolbar_data
+            .get_price_for_misc(worker, &sim.upgrade_handler);
+        use_tool(&mut sim, &mut canvas, (2, 2));
+
+        let hired = sim.worker_handler.workers.last_mut().unwrap();
+        let id = hired.id;
+        hired.supplying = Some((Produce::Crop(0), 4, (5, 5)));
+        hired.delivering = Some((Produce::Honey, 2));
+        sim.worker_handler
+            .job_board
+            .reserve((1, 1), id, JobType::Harvest, 2);
+
+        canvas.selected = canvas.toolbar_data.static_data.misc_index("sell").unwrap();
+        let money = sim.player.money;
+        use_tool(&mut sim, &mut canvas, (2, 2));
+
+        assert_eq!(
+            sim.player.money - money,
+            (first_price as f32 * SELL_REFUND) as usize
+        );
+        assert_eq!(canvas.toolbar_data.dynamic_data.misc_amount[&worker], 0);
+        assert_eq!(sim.worker_handler.job_board.holder((1, 1)), None);
+        assert_eq!(sim.player.inventory.amount(Produce::Crop(0)), 4);
+        assert_eq!(sim.player.inventory.amount(Produce::Honey), 2);
+    }
+
+    #[test]
+    fn only_bought_animals_are_sold_for_a_refund() {
+        let (mut sim, mut canvas) = with_misc_tool("sell");
+        let first_price = canvas
+            .toolbar_data
+            .get_price_for_animal(0, &sim.upgrade_handler);
+        *canvas
+            .toolbar_data
+            .dynamic_data
+            .animal_amount
+            .get_mut(&0)
+            .unwrap() = 1;
+
+        let mut bred = Animal::new(0, 3, 3);
+        bred.bought = false;
+        sim.animal_handler.add_animal(bred);
+        sim.animal_handler.add_animal(Animal::new(0, 4, 4));
+        let money = sim.player.money;
+
+        use_tool(&mut sim, &mut canvas, (3, 3));
+        assert_eq!(sim.player.money, money);
+        assert_eq!(canvas.toolbar_data.dynamic_data.animal_amount[&0], 1);
+        assert_eq!(sim.animal_handler.dynamic_data.animals.len(), 1);
+
+        use_tool(&mut sim, &mut canvas, (4, 4));
+        assert_eq!(
+            sim.player.money - money,
+            (first_price as f32 * SELL_REFUND) as usize
+        );
+        assert_eq!(canvas.toolbar_data.dynamic_data.animal_amount[&0], 0);
+        assert!(sim.animal_handler.dynamic_data.animals.is_empty());
+    }
+
+    #[test]
+    fn animals_are_only_bought_into_pens() {
+        let (mut sim, mut canvas) = with_misc_tool("fence");
+        let buy_animal = |sim: &mut Simulation, canvas: &mut Canvas| {
+            canvas.mode = MenuMode::Animals;
+            canvas.selected = 0;
+            sim.player.spawn_animals(
+                canvas,
+                &mut sim.map,
+                &(1, 1),
+                &mut sim.animal_handler,
+                &sim.upgrade_handler,
+            );
+        };
+
+        buy_animal(&mut sim, &mut canvas);
+        assert!(sim.animal_handler.dynamic_data.animals.is_empty());
+
+        // the pen is bought into right after it's fenced off
+        canvas.mode = MenuMode::Misc;
+        canvas.selected = canvas.toolbar_data.static_data.misc_index("fence").unwrap();
+        sim.worker_handler.workers[0].position = (-2, -2);
+        for x in 0..=2 {
+            for y in 0..=2 {
+                if (x, y) != (1, 1) {
+                    use_tool(&mut sim, &mut canvas, (x, y));
+                }
+            }
+        }
+        buy_animal(&mut sim, &mut canvas);
+        assert_eq!(sim.animal_handler.dynamic_data.animals.len(), 1);
+    }
+}
@@ -1,258 +1,33 @@
-use std::collections::HashMap;
-
 use raylib::{
     ffi::{CheckCollisionPointRec, MouseButton},
     prelude::{Color, RaylibDraw, RaylibDrawHandle, Rectangle, Vector2},
     text::Font,
 };
-use serde::{Deserialize, Serialize};
 
 use crate::{
     UI_BUTTON_SIZE, UI_GAPS,
     animal::AnimalHandler,
+    inventory::produce_name,
     localization::LocaleHandler,
     map::{Climate, Map, TILE_PIXEL_SIZE},
-    pause_menu::GameSettigns,
     player::Player,
+    settings::GameSettigns,
+    sim::Produce,
     texture_handler::TextureHandler,
-    upgrades::UpgradeHandler,
-    utils::{parse_json, shrink_number_for_display},
+    toolbar::{Canvas, MenuMode},
+    upgrades::{UpgradeHandler, UpgradeTarget},
+    utils::shrink_number_for_display,
 };
 
-#[derive(Deserialize)]
-pub struct ToolbarItem {
-    pub tooltip: String,
-    unlock_level: usize,
-    pub price: usize,
-    pub climate: Climate,
-}
-
-impl ToolbarItem {
-    fn new(tooltip: String, data: ToolbarItemData) -> Self {
-        Self {
-            tooltip,
-            unlock_level: data.unlock_level,
-            price: data.price,
-            climate: data.climate,
-        }
-    }
-}
-
-#[derive(Deserialize, Clone, Copy)]
-struct ToolbarItemData {
-    unlock_level: usize,
-    price: usize,
-    climate: Climate,
-}
-
-#[derive(Deserialize)]
-pub struct ToolbarStatic {
-    pub crops: Vec<ToolbarItem>,
-    pub trees: Vec<ToolbarItem>,
-    pub animals: Vec<ToolbarItem>,
-    pub beekeeping: Vec<ToolbarItem>,
-    pub misc: Vec<ToolbarItem>,
-}
-
-impl ToolbarStatic {
-    fn new(language_data: &HashMap<String, String>) -> Self {
-        let data: HashMap<String, Vec<ToolbarItemData>> =
-            parse_json("static/toolbar.json").expect("no toolbar");
-
-        let (mut crops, mut trees, mut animals, mut beekeeping, mut misc) =
-            (vec![], vec![], vec![], vec![], vec![]);
-
-        let crops_data = data.get("crops").unwrap();
-        for (index, data) in crops_data.iter().enumerate() {
-            let tooltip = language_data
-                .get(&format!("plant{index}"))
-                .unwrap()
-                .to_string();
-            crops.push(ToolbarItem::new(tooltip, *data));
-        }
-
-        let trees_data = data.get("trees").unwrap();
-        for (index, data) in trees_data.iter().enumerate() {
-            let tooltip = language_data
-                .get(&format!("tree{index}"))
-                .unwrap()
-                .to_string();
-            trees.push(ToolbarItem::new(tooltip, *data));
-        }
-
-        let animals_data = data.get("animals").unwrap();
-        for (index, data) in animals_data.iter().enumerate() {
-            let tooltip = language_data
-                .get(&format!("animal{index}"))
-                .unwrap()
-                .to_string();
-            animals.push(ToolbarItem::new(tooltip, *data));
-        }
-
-        let beekeeping_data = data.get("beekeeping").unwrap();
-        for (index, data) in beekeeping_data.iter().enumerate() {
-            let tooltip = language_data
-                .get(&format!("beekeeping{index}"))
-                .unwrap()
-                .to_string();
-            beekeeping.push(ToolbarItem::new(tooltip, *data));
-        }
-
-        let misc_data = data.get("misc").unwrap();
-        for (index, data) in misc_data.iter().enumerate() {
-            let tooltip = language_data
-                .get(&format!("misc{index}"))
-                .unwrap()
-                .to_string();
-            misc.push(ToolbarItem::new(tooltip, *data));
-        }
-
-        Self {
-            crops,
-            trees,
-            animals,
-            beekeeping,
-            misc,
-        }
-    }
-}
-
-#[derive(Deserialize, Serialize)]
-pub struct ToolbarDynamic {
-    pub crop_amount: HashMap<usize, usize>,
-    pub tree_amount: HashMap<usize, usize>,
-    pub animal_amount: HashMap<usize, usize>,
-    pub beekeeping_amount: HashMap<usize, usize>,
-    pub misc_amount: HashMap<usize, usize>,
-}
-
-impl ToolbarDynamic {
-    fn new(static_data: &ToolbarStatic) -> Self {
-        let mut crop_amount = HashMap::new();
-        for i in 0..static_data.crops.len() {
-            crop_amount.insert(i, 0);
-        }
-        let mut tree_amount = HashMap::new();
-        for i in 0..static_data.trees.len() {
-            tree_amount.insert(i, 0);
-        }
-        let mut animal_amount = HashMap::new();
-        for i in 0..static_data.animals.len() {
-            animal_amount.insert(i, 0);
-        }
-        let mut beekeeping_amount = HashMap::new();
-        for i in 0..static_data.beekeeping.len() {
-            beekeeping_amount.insert(i, 0);
-        }
-        let mut misc_amount = HashMap::new();
-        for i in 0..static_data.misc.len() {
-            misc_amount.insert(i, 0);
-        }
-
-        Self {
-            crop_amount,
-            tree_amount,
-            animal_amount,
-            beekeeping_amount,
-            misc_amount,
-        }
-    }
-}
-
-pub struct ToolbarData {
-    pub static_data: ToolbarStatic,
-    pub dynamic_data: ToolbarDynamic,
-}
-
-impl ToolbarData {
-    fn new(language_data: &HashMap<String, String>) -> Self {
-        let static_data = ToolbarStatic::new(language_data);
-        let res = parse_json("dynamic/toolbar_save.json");
-        let dynamic_data = match res {
-            Ok(dynamic_data) => dynamic_data,
-            Err(_) => ToolbarDynamic::new(&static_data),
-        };
-
-        Self {
-            static_data,
-            dynamic_data,
-        }
-    }
-
-    pub fn get_price_for_crop(&self, index: usize) -> usize {
-        let mut price = self.static_data.crops[index].price;
-        for _ in 0..*self.dynamic_data.crop_amount.get(&index).unwrap() {
-            price = (price as f32 * 1.1) as usize;
-        }
-        price
-    }
-
-    pub fn get_price_for_tree(&self, index: usize) -> usize {
-        let mut price = self.static_data.trees[index].price;
-        for _ in 0..*self.dynamic_data.tree_amount.get(&index).unwrap() {
-            price = (price as f32 * 1.1) as usize;
-        }
-        price
-    }
-
-    pub fn get_price_for_animal(&self, index: usize) -> usize {
-        let mut price = self.static_data.animals[index].price;
-        for _ in 0..*self.dynamic_data.animal_amount.get(&index).unwrap() {
-            price = (price as f32 * 1.1) as usize;
-        }
-        price
-    }
-
-    pub fn get_price_for_beekeeping(&self, index: usize) -> usize {
-        let mut price = self.static_data.beekeeping[index].price;
-        for _ in 0..*self.dynamic_data.beekeeping_amount.get(&index).unwrap() {
-            price = (price as f32 * 1.1) as usize;
-        }
-        price
-    }
-
-    pub fn get_price_for_misc(&self, index: usize) -> usize {
-        let mut price = self.static_data.misc[index].price;
-        for _ in 0..*self.dynamic_data.misc_amount.get(&index).unwrap() {
-            price = (price as f32 * 1.1) as usize;
-        }
-        price
-    }
-
-    fn reload_static(&mut self, language_data: &HashMap<String, String>) {
-        self.static_data = ToolbarStatic::new(language_data);
-    }
-
-    pub fn save(&self) {
-        let serialized = serde_json::to_string_pretty(&self.dynamic_data).expect("err");
-        std::fs::create_dir_all("dynamic").expect("Couldn't create dir");
-        std::fs::write("dynamic/toolbar_save.json", serialized)
-            .expect("Couldn't write toolbar data to json");
-    }
-}
-
-#[derive(PartialEq)]
-pub enum MenuMode {
-    Crops,
-    Trees,
-    Animals,
-    Beekeeping,
-    Misc,
-}
-
-pub struct Canvas {
-    pub mode: MenuMode,
-    pub selected: usize,
+/// Where the shop buttons are on screen, the submenu ones are laid out again on every draw
+pub struct ShopLayout {
     content: Vec<Rectangle>,
     subcontent: Vec<Rectangle>,
-    pub toolbar_data: ToolbarData,
 }
 
-impl Canvas {
-    pub fn new(language_data: &HashMap<String, String>) -> Self {
+impl ShopLayout {
+    pub fn new() -> Self {
         Self {
-            mode: MenuMode::Crops,
-            selected: 0,
             content: vec![
                 Rectangle::new(
                     10.,
@@ -284,16 +59,19 @@ impl Canvas {
                     UI_BUTTON_SIZE,
                     UI_BUTTON_SIZE,
                 ),
+                Rectangle::new(
+                    10.,
+                    6. * UI_BUTTON_SIZE + UI_GAPS * 3.5,
+                    UI_BUTTON_SIZE,
+                    UI_BUTTON_SIZE,
+                ),
             ],
             subcontent: vec![],
-            toolbar_data: ToolbarData::new(language_data),
         }
     }
+}
 
-    pub fn reload_toolbar_static(&mut self, language_data: &HashMap<String, String>) {
-        self.toolbar_data.reload_static(language_data);
-    }
-
+impl Canvas {
     pub fn draw(
         &mut self,
         rl: &mut RaylibDrawHandle,
@@ -309,6 +87,7 @@ impl Canvas {
             MenuMode::Animals,
             MenuMode::Beekeeping,
             MenuMode::Misc,
+            MenuMode::Buildings,
         ];
 
         let texture_ids = [
@@ -317,6 +96,7 @@ impl Canvas {
             "animals_menu",
             "beekeeping_menu",
             "misc_menu",
+            "buildings_menu",
         ];
 
         let unlock_levels = [
@@ -325,17 +105,18 @@ impl Canvas {
             self.toolbar_data.static_data.animals[0].unlock_level,
             self.toolbar_data.static_data.beekeeping[0].unlock_level,
             self.toolbar_data.static_data.misc[0].unlock_level,
+            self.toolbar_data.static_data.buildings[0].unlock_level,
         ];
 
-        for i in 0..5 {
-            let position = Vector2::new(self.content[i].x, self.content[i].y);
+        for i in 0..modes.len() {
+            let position = Vector2::new(self.layout.content[i].x, self.layout.content[i].y);
             let color = if unlock_levels[i] > player.level {
                 Color::BLACK
             } else {
                 Color::WHITE
             };
             rl.draw_rectangle_rec(
-                self.content[i],
+                self.layout.content[i],
                 if self.mode == modes[i] {
                     Color::RAYWHITE.alpha(0.9)
                 } else {
@@ -358,15 +139,16 @@ impl Canvas {
             MenuMode::Beekeeping => {
                 map.static_data.hive_data.len() + map.static_data.flower_data.len()
             }
-            MenuMode::Misc => 2,
+            MenuMode::Misc => self.toolbar_data.static_data.misc.len(),
+            MenuMode::Buildings => map.recipes.buildings.len(),
         };
-        self.subcontent.clear();
+        self.layout.subcontent.clear();
 
         rl.draw_rectangle(
             (UI_BUTTON_SIZE + UI_GAPS) as i32,
             (UI_BUTTON_SIZE + UI_GAPS) as i32,
             UI_GAPS as i32 / 2,
-            UI_BUTTON_SIZE as i32 * 5 + (UI_GAPS * 2.) as i32,
+            UI_BUTTON_SIZE as i32 * 6 + (UI_GAPS * 2.5) as i32,
             Color::BLACK.alpha(0.5),
         );
 
@@ -385,7 +167,7 @@ impl Canvas {
             };
 
             rl.draw_rectangle_rec(rect, color);
-            self.subcontent.push(rect);
+            self.layout.subcontent.push(rect);
 
             let tooltip_pool;
             let amount_pool;
@@ -395,7 +177,7 @@ impl Canvas {
                 MenuMode::Crops => {
                     tooltip_pool = &self.toolbar_data.static_data.crops;
                     amount_pool = &mut self.toolbar_data.dynamic_data.crop_amount;
-                    texture_id = format!("crop{i}");
+                    texture_id = format!("crop_{}", tooltip_pool[i].id);
                     source = Rectangle::new(
                         -TILE_PIXEL_SIZE as f32,
                         0.0,
@@ -406,7 +188,7 @@ impl Canvas {
                 MenuMode::Trees => {
                     tooltip_pool = &self.toolbar_data.static_data.trees;
                     amount_pool = &mut self.toolbar_data.dynamic_data.tree_amount;
-                    texture_id = format!("tree{i}");
+                    texture_id = format!("tree_{}", tooltip_pool[i].id);
                     source = Rectangle::new(
                         -TILE_PIXEL_SIZE as f32,
                         0.0,
@@ -417,24 +199,36 @@ impl Canvas {
                 MenuMode::Animals => {
                     tooltip_pool = &self.toolbar_data.static_data.animals;
                     amount_pool = &mut self.toolbar_data.dynamic_data.animal_amount;
-                    texture_id = format!("animal{i}");
+                    texture_id = format!("animal_{}", tooltip_pool[i].id);
                     source =
                         Rectangle::new(0.0, 0.0, TILE_PIXEL_SIZE as f32, TILE_PIXEL_SIZE as f32);
                 }
                 MenuMode::Beekeeping => {
                     tooltip_pool = &self.toolbar_data.static_data.beekeeping;
                     amount_pool = &mut self.toolbar_data.dynamic_data.beekeeping_amount;
-                    texture_id = format!("beekeeping{i}");
+                    texture_id = format!("beekeeping_{}", tooltip_pool[i].id);
                     source =
                         Rectangle::new(0.0, 0.0, TILE_PIXEL_SIZE as f32, TILE_PIXEL_SIZE as f32);
                 }
                 MenuMode::Misc => {
                     tooltip_pool = &self.toolbar_data.static_data.misc;
                     amount_pool = &mut self.toolbar_data.dynamic_data.misc_amount;
-                    texture_id = format!("misc{i}");
+                    texture_id = format!("misc_{}", tooltip_pool[i].id);
                     source =
                         Rectangle::new(0.0, 0.0, TILE_PIXEL_SIZE as f32, TILE_PIXEL_SIZE as f32);
                 }
+                MenuMode::Buildings => {
+                    let building = &map.recipes.buildings[i];
+                    tooltip_pool = &self.toolbar_data.static_data.buildings;
+                    amount_pool = &mut self.toolbar_data.dynamic_data.building_amount;
+                    texture_id = format!("building_{}", tooltip_pool[i].id);
+                    source = Rectangle::new(
+                        0.0,
+                        0.0,
+                        (building.width * TILE_PIXEL_SIZE) as f32,
+                        (building.height * TILE_PIXEL_SIZE) as f32,
+                    );
+                }
             }
 
             let color = if tooltip_pool[i].unlock_level > player.level {
@@ -489,8 +283,8 @@ impl Canvas {
         upgrade_handler: &UpgradeHandler,
         settings: &GameSettigns,
     ) {
-        for i in 0..self.content.len() {
-            let rect = self.content[i];
+        for i in 0..self.layout.content.len() {
+            let rect = self.layout.content[i];
             if rect.check_collision_point_rec(rl.get_mouse_position()) {
                 let (pool, mode, label) = match i {
                     0 => (
@@ -513,11 +307,16 @@ impl Canvas {
                         MenuMode::Beekeeping,
                         locale_handler.language_data.get("beekeeping").unwrap(),
                     ),
-                    _ => (
+                    4 => (
                         &self.toolbar_data.static_data.misc,
                         MenuMode::Misc,
                         locale_handler.language_data.get("misc").unwrap(),
                     ),
+                    _ => (
+                        &self.toolbar_data.static_data.buildings,
+                        MenuMode::Buildings,
+                        locale_handler.language_data.get("buildings").unwrap(),
+                    ),
                 };
 
                 if rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT) {
@@ -559,8 +358,8 @@ impl Canvas {
             }
         }
 
-        for i in 0..self.subcontent.len() {
-            let rect = self.subcontent[i];
+        for i in 0..self.layout.subcontent.len() {
+            let rect = self.layout.subcontent[i];
             if unsafe {
                 use raylib::ffi::{Rectangle, Vector2};
                 let rect = Rectangle {
@@ -575,77 +374,71 @@ impl Canvas {
                 };
                 CheckCollisionPointRec(mouse_pos, rect)
             } {
-                let crops_len = self.toolbar_data.static_data.crops.len();
-                let trees_len = self.toolbar_data.static_data.trees.len();
-                let animals_len = animal_handler.static_data.animal_data.len();
-                let (toolbar_item, amount, output_price, output_exp) = match self.mode {
+                let toolbar_data = &self.toolbar_data;
+                let (toolbar_item, price, (output_price, output_exp)) = match self.mode {
                     MenuMode::Crops => (
-                        &self.toolbar_data.static_data.crops[i],
-                        self.toolbar_data.dynamic_data.crop_amount.get(&i).unwrap(),
-                        map.static_data.crops_data[i].sell_price
-                            * upgrade_handler.get_multiplier_for_crop(i),
-                        map.static_data.crops_data[i].exp
-                            * upgrade_handler.get_multiplier_for_crop(i),
+                        &toolbar_data.static_data.crops[i],
+                        toolbar_data.get_price_for_crop(i, upgrade_handler),
+                        upgrade_handler.apply(
+                            UpgradeTarget::Crop(i),
+                            map.static_data.crops_data[i].sell_price,
+                            map.static_data.crops_data[i].exp,
+                        ),
                     ),
                     MenuMode::Trees => (
-                        &self.toolbar_data.static_data.trees[i],
-                        self.toolbar_data.dynamic_data.tree_amount.get(&i).unwrap(),
-                        map.static_data.tree_data[i].sell_price
-                            * upgrade_handler.get_multiplier_for_tree(i, crops_len),
-                        map.static_data.tree_data[i].exp
-                            * upgrade_handler.get_multiplier_for_tree(i, crops_len),
+                        &toolbar_data.static_data.trees[i],
+                        toolbar_data.get_price_for_tree(i, upgrade_handler),
+                        upgrade_handler.apply(
+                            UpgradeTarget::Tree(i),
+                            map.static_data.tree_data[i].sell_price,
+                            map.static_data.tree_data[i].exp,
+                        ),
                     ),
                     MenuMode::Animals => (
-                        &self.toolbar_data.static_data.animals[i],
-                        self.toolbar_data
-                            .dynamic_data
-                            .animal_amount
-                            .get(&i)
-                            .unwrap(),
-                        animal_handler.static_data.animal_data[i].drop_cost
-                            * upgrade_handler.get_multiplier_for_animal(i, crops_len, trees_len),
-                        animal_handler.static_data.animal_data[i].exp
-                            * upgrade_handler.get_multiplier_for_animal(i, crops_len, trees_len),
+                        &toolbar_data.static_data.animals[i],
+                        toolbar_data.get_price_for_animal(i, upgrade_handler),
+                        upgrade_handler.apply(
+                            UpgradeTarget::Animal(i),
+                            animal_handler.static_data.animal_data[i].drop_cost,
+                            animal_handler.static_data.animal_data[i].exp,
+                        ),
                     ),
                     MenuMode::Beekeeping => {
-                        let (price, exp) = if i == 0 {
+                        let output = if i == 0 {
                             (
                                 map.static_data.hive_data[0].sell_price,
                                 map.static_data.hive_data[0].exp,
                             )
                         } else {
-                            (
-                                map.static_data.flower_data[i - 1].sell_price
-                                    * upgrade_handler.get_multiplier_for_beehive(
-                                        crops_len,
-                                        trees_len,
-                                        animals_len,
-                                    ),
-                                map.static_data.flower_data[i - 1].exp
-                                    * upgrade_handler.get_multiplier_for_beehive(
-                                        crops_len,
-                                        trees_len,
-                                        animals_len,
-                                    ),
+                            upgrade_handler.apply(
+                                UpgradeTarget::Hive,
+                                map.static_data.flower_data[i - 1].sell_price,
+                                map.static_data.flower_data[i - 1].exp,
                             )
                         };
                         (
-                            &self.toolbar_data.static_data.beekeeping[i],
-                            self.toolbar_data
-                                .dynamic_data
-                                .beekeeping_amount
-                                .get(&i)
-                                .unwrap(),
-                            price,
-                            exp,
+                            &toolbar_data.static_data.beekeeping[i],
+                            toolbar_data.get_price_for_beekeeping(i, upgrade_handler),
+                            output,
                         )
                     }
                     MenuMode::Misc => (
-                        &self.toolbar_data.static_data.misc[i],
-                        self.toolbar_data.dynamic_data.misc_amount.get(&i).unwrap(),
-                        0,
-                        0,
+                        &toolbar_data.static_data.misc[i],
+                        toolbar_data.get_price_for_misc(i, upgrade_handler),
+                        (0, 0),
                     ),
+                    MenuMode::Buildings => {
+                        let building = &map.recipes.buildings[i];
+                        let good = &map.recipes.goods[building.output];
+                        (
+                            &toolbar_data.static_data.buildings[i],
+                            toolbar_data.get_price_for_building(i),
+                            (
+                                good.sell_price * building.output_amount,
+                                good.exp * building.output_amount,
+                            ),
+                        )
+                    }
                 };
 
                 let tooltip_text = if toolbar_item.unlock_level > player.level {
@@ -658,11 +451,6 @@ impl Canvas {
                     if toolbar_item.price <= 0 {
                         format!("{}", toolbar_item.tooltip)
                     } else {
-                        let mut price = toolbar_item.price;
-                        for _ in 0..*amount {
-                            price = (price as f32 * 1.1) as usize;
-                        }
-
                         format!(
                             "{}\n{}",
                             toolbar_item.tooltip,
@@ -686,12 +474,38 @@ impl Canvas {
                         format!("{}: {}\n", lang.get("climate").unwrap().to_string(), old);
                 }
 
+                // animals only produce when fed, so the shop says what they eat
+                if self.mode == MenuMode::Animals {
+                    let feed = &animal_handler.static_data.animal_data[i].feed;
+                    if let Some(feed_name) = lang.get(&format!("plant_{feed}")) {
+                        climate_string +=
+                            &format!("{}: {}\n", lang.get("eats").unwrap(), feed_name);
+                    }
+                }
+
+                // buildings say what goes in and what comes out of one batch
+                if self.mode == MenuMode::Buildings {
+                    let building = &map.recipes.buildings[i];
+                    let name = |produce| produce_name(produce, map, animal_handler, locale_handler);
+                    let inputs = building
+                        .inputs
+                        .iter()
+                        .map(|(produce, amount)| format!("{amount} {}", name(*produce)))
+                        .collect::<Vec<_>>()
+                        .join(" + ");
+                    climate_string += &format!(
+                        "{inputs} -> {} {}\n",
+                        building.output_amount,
+                        name(Produce::Good(building.output))
+                    );
+                }
+
                 let tooltip_extra = if output_price > 0 && toolbar_item.unlock_level <= player.level
                 {
                     format!(
                         "{}{} {}\n{} {}",
-						climate_string,
-						output_price,
+                        climate_string,
+                        output_price,
                         lang.get("per_harvest").unwrap(),
                         output_exp,
                         lang.get("exp_per_harvest").unwrap(),
@@ -751,7 +565,7 @@ impl Canvas {
     }
 
     pub fn blocks_mouse(&self, mouse_position: Vector2) -> bool {
-        for node in self.content.iter() {
+        for node in self.layout.content.iter() {
             // stupid unsafe conversion
             if unsafe {
                 use raylib::ffi::{Rectangle, Vector2};
@@ -771,7 +585,7 @@ impl Canvas {
             }
         }
 
-        for node in self.subcontent.iter() {
+        for node in self.layout.subcontent.iter() {
             // stupid unsafe conversion
             if unsafe {
                 use raylib::ffi::{Rectangle, Vector2};
@@ -0,0 +1,555 @@
+use std::{collections::HashMap, time::Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    achievements::AchievementHandler,
+    animal::AnimalHandler,
+    content::ContentIds,
+    contracts::{ContractBoard, Reward},
+    inventory::all_produce,
+    map::{CHUNK_HEIGHT, CHUNK_WIDTH, Map, TileType},
+    market::Market,
+    player::Player,
+    prestige::Prestige,
+    save::SaveFile,
+    upgrades::UpgradeHandler,
+    weather::Weather,
+    worker::{Worker, WorkerHandler},
+};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Produce {
+    Crop(usize),
+    Tree(usize),
+    Animal(usize),
+    Honey,
+    // made in a building
+    Good(usize),
+}
+
+/// Something that happened during a tick. The simulation doesn't play sounds or draw anything,
+/// the front end drains these and decides how to present them
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SimEvent {
+    Harvest {
+        position: (i32, i32),
+        produce: Produce,
+        money: usize,
+        exp: usize,
+    },
+    // goods sold on their own, after the harvest that brought them in
+    Sold {
+        position: (i32, i32),
+        produce: Produce,
+        money: usize,
+    },
+    LevelUp {
+        level: usize,
+    },
+    WagesPaid {
+        money: usize,
+    },
+    AchievementUnlocked {
+        index: usize,
+    },
+}
+
+/// Owns the whole farm economy and advances it one tick at a time, without touching raylib
+pub struct Simulation {
+    pub map: Map,
+    pub player: Player,
+    pub worker_handler: WorkerHandler,
+    pub animal_handler: AnimalHandler,
+    pub upgrade_handler: UpgradeHandler,
+    pub market: Market,
+    pub contracts: ContractBoard,
+    pub achievements: AchievementHandler,
+    pub prestige: Prestige,
+    pub weather: Weather,
+    events: Vec<SimEvent>,
+}
+
+impl Simulation {
+    pub fn new(
+        language_data: &HashMap<String, String>,
+        content_ids: &ContentIds,
+        save: Option<SaveFile>,
+    ) -> Self {
+        let Some(save) = save else {
+            return Self {
+                map: Map::new(None),
+                player: Player::new(),
+                worker_handler: WorkerHandler::new(),
+                animal_handler: AnimalHandler::new(None),
+                upgrade_handler: UpgradeHandler::new(language_data, content_ids, None),
+                market: Market::new(rand::random()),
+                contracts: ContractBoard::new(),
+                achievements: AchievementHandler::new(),
+                prestige: Prestige::new(),
+                weather: Weather::new(rand::random()),
+                events: vec![],
+            };
+        };
+
+        let mut worker_handler = save.workers.unwrap_or_else(WorkerHandler::new);
+        worker_handler.assign_ids();
+
+        let mut player = save.player;
+        player.stats.play_time = save.play_time;
+
+        let prestige = save.prestige.unwrap_or_else(Prestige::new);
+        let mut upgrade_handler = UpgradeHandler::new(language_data, content_ids, save.upgrades);
+        upgrade_handler.permanent_bonus = prestige.bonus();
+
+        Self {
+            map: Map::new(Some(save.map)),
+            player,
+            worker_handler,
+            animal_handler: AnimalHandler::new(save.animals),
+            upgrade_handler,
+            market: save.market.unwrap_or_else(|| Market::new(rand::random())),
+            contracts: save.contracts.unwrap_or_else(ContractBoard::new),
+            achievements: save.achievements.unwrap_or_else(AchievementHandler::new),
+            prestige,
+            weather: save.weather.unwrap_or_else(|| Weather::new(rand::random())),
+            events: vec![],
+        }
+    }
+
+    pub fn tick(&mut self) {
+        let first_event = self.events.len();
+
+        self.weather.update();
+        self.map.update_tiles(&self.upgrade_handler, &self.weather);
+        self.player.exp += self.map.update_buildings(&self.player.inventory);
+        self.market
+            .update(&all_produce(&self.map, &self.animal_handler));
+
+        self.worker_handler.advance_workers(
+            &mut self.player,
+            &mut self.map,
+            &self.animal_handler,
+            &self.upgrade_handler,
+            &mut self.market,
+            &mut self.events,
+        );
+        self.animal_handler
+            .move_animals(&mut self.map, &self.upgrade_handler);
+
+        self.contracts.update(
+            self.player.level,
+            &self.map,
+            &self.animal_handler,
+            &self.upgrade_handler,
+        );
+
+        self.player.update_money();
+        self.player.update_exp(&mut self.events);
+
+        for event in self.events[first_event..].iter() {
+            if let SimEvent::Harvest { produce, .. } = event {
+                self.player.stats.record_harvest(*produce);
+            }
+        }
+        self.achievements.update(&self.player, &mut self.events);
+
+        // everything that keeps track of the layout has caught up with the changes by now
+        self.map.changed_tiles.clear();
+    }
+
+    /// Hands goods from storage over to an order on the contracts board, paying out if that finished it
+    pub fn deliver_contract(&mut self, index: usize) {
+        match self.contracts.deliver(index, &mut self.player.inventory) {
+            Some(Reward::Money(money)) => self.player.earn(money),
+            Some(Reward::Exp(exp)) => self.player.exp += exp,
+            Some(Reward::Worker) => self.worker_handler.add_worker(Worker::new(0, 0)),
+            None => {}
+        }
+    }
+
+    /// Starts over on a fresh island for prestige points. Prestige, the stats, the achievements, the
+    /// market and the time of year carry over, the toolbar counts are up to the front end
+    pub fn reset_island(&mut self) {
+        let points = self.prestige.points_for(self.player.alltime_max_money);
+        if points == 0 {
+            return;
+        }
+        self.prestige.points += points;
+        self.prestige.resets += 1;
+
+        let stats = std::mem::take(&mut self.player.stats);
+        self.map = Map::new(None);
+        self.player = Player::new();
+        self.player.stats = stats;
+        self.worker_handler = WorkerHandler::new();
+        self.animal_handler = AnimalHandler::new(None);
+        self.upgrade_handler.dynamic_data.purchased_upgrades.clear();
+        self.upgrade_handler.permanent_bonus = self.prestige.bonus();
+        self.contracts = ContractBoard::new();
+        self.events.clear();
+
+        for _ in 0..self.prestige.starting_land() {
+            self.map.add_free_land();
+        }
+        // workers from perks aren't hired, so a new island doesn't start out owing them wages
+        for _ in 0..self.prestige.starting_workers() {
+            self.worker_handler.add_worker(Worker::new(0, 0));
+        }
+    }
+
+    pub fn buy_perk(&mut self, index: usize) {
+        self.prestige.buy(index);
+        self.upgrade_handler.permanent_bonus = self.prestige.bonus();
+    }
+
+    pub fn drain_events(&mut self) -> std::vec::Drain<'_, SimEvent> {
+        self.events.drain(..)
+    }
+}
+
+/// Runs a save (or a fresh farm) for a number of ticks with no window or audio device and prints the result.
+/// Nothing is written back, so it's safe to point at a real farm
+pub fn run_headless(
+    ticks: usize,
+    language_data: &HashMap<String, String>,
+    content_ids: &ContentIds,
+    save: Option<SaveFile>,
+) {
+    let mut sim = Simulation::new(language_data, content_ids, save);
+
+    let start_money = sim.player.money;
+    let start_level = sim.player.level;
+    let mut harvests = 0;
+    let mut wages = 0;
+
+    for _ in 0..ticks {
+        sim.tick();
+        for event in sim.drain_events() {
+            match event {
+                SimEvent::Harvest { .. } => harvests += 1,
+                SimEvent::WagesPaid { money } => wages += money,
+                SimEvent::Sold { .. }
+                | SimEvent::LevelUp { .. }
+                | SimEvent::AchievementUnlocked { .. } => {}
+            }
+        }
+    }
+
+    println!(
+        "simulated {ticks} ticks: money {start_money} -> {}, level {start_level} -> {}, {harvests} harvests by {} workers ({wages} paid in wages), {} animals, {} goods in storage, prices at {:.0}% on average, {} in {}",
+        sim.player.money,
+        sim.player.level,
+        sim.worker_handler.workers.len(),
+        sim.animal_handler.dynamic_data.animals.len(),
+        sim.player.inventory.total(),
+        sim.market.average_price() * 100.,
+        sim.weather.current().id,
+        sim.weather.season().id,
+    );
+}
+
+const CHUNK_TILES: usize = CHUNK_WIDTH * CHUNK_HEIGHT;
+
+/// Times the workers on a big made up island, to see how pathfinding holds up as farms grow
+pub fn run_pathfinding_benchmark(
+    chunks: usize,
+    workers: usize,
+    ticks: usize,
+    language_data: &HashMap<String, String>,
+    content_ids: &ContentIds,
+) {
+    let mut sim = Simulation::new(language_data, content_ids, None);
+
+    // so unpaid wages never slow the workers down
+    sim.player.money = usize::MAX / 2;
+
+    // bought land gets too expensive to afford after a hundred or so chunks
+    while sim.map.dynamic_data.tiles.len() < chunks * CHUNK_TILES
+        && !sim.map.land_expansion_points().is_empty()
+    {
+        sim.map.add_free_land();
+    }
+
+    // a fixed pattern of ripe fields and orchards, so runs can be compared
+    let tree = &sim.map.static_data.tree_data[0];
+    let (time_to_grow, time_to_fruit) = (tree.time_to_grow, tree.time_to_fruit);
+    let time_to_ripen = sim.map.static_data.crops_data[0].time_to_grow;
+    for (position, tile) in sim.map.dynamic_data.tiles.iter_mut() {
+        *tile = match (position.0 * 7 + position.1 * 13).rem_euclid(10) {
+            0 | 1 => TileType::Tree {
+                tree: 0,
+                grow: time_to_grow,
+                stage: time_to_fruit,
+            },
+            2..=5 => TileType::Farmland {
+                crop: 0,
+                stage: time_to_ripen,
+            },
+            _ => TileType::Grass,
+        };
+    }
+
+    let mut positions: Vec<(i32, i32)> = sim.map.dynamic_data.tiles.keys().copied().collect();
+    positions.sort();
+    let step = (positions.len() / workers.max(1)).max(1);
+    sim.worker_handler = WorkerHandler::new();
+    sim.worker_handler.workers.clear();
+    for position in positions.iter().step_by(step).take(workers) {
+        sim.worker_handler
+            .add_worker(Worker::new(position.0, position.1));
+    }
+
+    let start = Instant::now();
+    let mut slowest = 0.;
+    for _ in 0..ticks {
+        let tick_start = Instant::now();
+        sim.tick();
+        sim.drain_events().for_each(drop);
+        slowest = f64::max(slowest, tick_start.elapsed().as_secs_f64());
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+
+    println!(
+        "{} tiles, {} workers, {ticks} ticks: {:.3} ms per tick, {:.3} ms slowest, {} path searches, {} cache hits",
+        sim.map.dynamic_data.tiles.len(),
+        sim.worker_handler.workers.len(),
+        elapsed * 1000. / ticks.max(1) as f64,
+        slowest * 1000.,
+        sim.worker_handler.pathfinder.searches,
+        sim.worker_handler.pathfinder.cache_hits,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{contracts::Contract, localization::load_language, worker::TICKS_PER_MINUTE};
+
+    fn fresh_sim() -> Simulation {
+        Simulation::new(&load_language("en"), &ContentIds::load(), None)
+    }
+
+    #[test]
+    fn ripe_crops_get_harvested_without_a_window() {
+        let mut sim = fresh_sim();
+        let time_to_grow = sim.map.static_data.crops_data[0].time_to_grow;
+        for tile in sim.map.dynamic_data.tiles.values_mut() {
+            *tile = TileType::Farmland {
+                crop: 0,
+                stage: time_to_grow,
+            };
+        }
+
+        let mut harvests = 0;
+        for _ in 0..40 {
+            sim.tick();
+            harvests += sim
+                .drain_events()
+                .filter(|event| matches!(event, SimEvent::Harvest { .. }))
+                .count();
+        }
+
+        assert!(harvests > 0);
+        assert_eq!(sim.player.stats.total_harvests(), harvests);
+        assert!(sim.player.exp > 0 || sim.player.level > 1);
+    }
+
+    #[test]
+    fn levelling_up_is_reported_as_an_event() {
+        let mut sim = fresh_sim();
+        sim.player.exp = sim.player.exp_to_lvl_up;
+
+        sim.tick();
+
+        assert_eq!(sim.player.level, 2);
+        assert!(
+            sim.drain_events()
+                .any(|event| event == SimEvent::LevelUp { level: 2 })
+        );
+    }
+
+    #[test]
+    fn only_hired_workers_draw_wages() {
+        let mut sim = fresh_sim();
+        let mut hired = Worker::new(0, 0);
+        hired.hired = true;
+        sim.worker_handler.add_worker(hired);
+
+        let mut paid = 0;
+        for _ in 0..3 * TICKS_PER_MINUTE {
+            sim.tick();
+            for event in sim.drain_events() {
+                if let SimEvent::WagesPaid { money } = event {
+                    paid += money;
+                }
+            }
+        }
+
+        assert_eq!(paid, 3 * sim.worker_handler.settings.wage_per_minute);
+    }
+
+    #[test]
+    fn kept_harvests_are_stored_by_the_piece() {
+        let mut sim = fresh_sim();
+        let crop = Produce::Crop(0);
+        let crop_data = &sim.map.static_data.crops_data[0];
+        let (time_to_grow, climate) = (crop_data.time_to_grow, crop_data.climate);
+        sim.player.inventory.toggle_kept(crop);
+
+        sim.map
+            .dynamic_data
+            .tiles
+            .insert((-2, -2), TileType::Storage);
+        sim.map.mark_changed((-2, -2));
+        sim.map.dynamic_data.tiles.insert(
+            (1, 1),
+            TileType::Farmland {
+                crop: 0,
+                stage: time_to_grow,
+            },
+        );
+        // a good climate grows more, the worker is too new to bring in double
+        let pieces = sim.map.biomes.climate_multiplier((1, 1), climate);
+
+        for _ in 0..50 {
+            sim.tick();
+            if sim.player.inventory.total() > 0 {
+                break;
+            }
+        }
+
+        assert_eq!(sim.player.inventory.amount(crop), pieces);
+    }
+
+    #[test]
+    fn kept_honey_counts_the_flowers_around_the_hive() {
+        let mut sim = fresh_sim();
+        let time_to_honey = sim.map.static_data.hive_data[0].time_to_honey;
+        sim.player.inventory.toggle_kept(Produce::Honey);
+
+        sim.map
+            .dynamic_data
+            .tiles
+            .insert((-2, -2), TileType::Storage);
+        sim.map.mark_changed((-2, -2));
+        for x in 0..=2 {
+            for y in 0..=2 {
+                sim.map
+                    .dynamic_data
+                    .tiles
+                    .insert((x, y), TileType::Flower { flower: 0 });
+            }
+        }
+        // ripens on the first tick, counting the flowers around it
+        sim.map.dynamic_data.tiles.insert(
+            (1, 1),
+            TileType::Beehive {
+                stage: time_to_honey - 1,
+                price: 0,
+                xp: 0,
+            },
+        );
+
+        for _ in 0..50 {
+            sim.tick();
+            if sim.player.inventory.total() > 0 {
+                break;
+            }
+        }
+
+        assert!(sim.player.inventory.amount(Produce::Honey) > 1);
+    }
+
+    #[test]
+    fn unpaid_wages_add_up_until_they_can_be_paid() {
+        let mut sim = fresh_sim();
+        let mut hired = Worker::new(0, 0);
+        hired.hired = true;
+        sim.worker_handler.add_worker(hired);
+
+        sim.player.money = 0;
+        for _ in 0..3 * TICKS_PER_MINUTE {
+            sim.tick();
+        }
+        assert!(sim.worker_handler.unpaid());
+        assert!(
+            sim.drain_events()
+                .all(|event| !matches!(event, SimEvent::WagesPaid { .. }))
+        );
+
+        sim.player.money = 1_000_000;
+        sim.tick();
+
+        let paid: usize = sim
+            .drain_events()
+            .filter_map(|event| match event {
+                SimEvent::WagesPaid { money } => Some(money),
+                _ => None,
+            })
+            .sum();
+        assert_eq!(paid, 3 * sim.worker_handler.settings.wage_per_minute);
+        assert!(!sim.worker_handler.unpaid());
+    }
+
+    #[test]
+    fn workers_from_contracts_come_for_free() {
+        let mut sim = fresh_sim();
+        sim.contracts.contracts.push(Contract {
+            requirements: vec![],
+            ticks_left: 10,
+            reward: Reward::Worker,
+        });
+
+        sim.deliver_contract(0);
+
+        assert_eq!(sim.worker_handler.workers.len(), 2);
+        assert_eq!(sim.worker_handler.wages_per_minute(), 0);
+    }
+
+    #[test]
+    fn workers_from_perks_draw_no_wages() {
+        let mut sim = fresh_sim();
+        let loyal_crew = sim
+            .prestige
+            .settings
+            .perks
+            .iter()
+            .position(|perk| perk.id == "loyal_crew")
+            .unwrap();
+        sim.prestige.points = 1000;
+        for _ in 0..3 {
+            sim.buy_perk(loyal_crew);
+        }
+        sim.player.alltime_max_money = 10 * sim.prestige.settings.money_per_point;
+
+        sim.reset_island();
+
+        // the one every farm starts with and three from the perk
+        assert_eq!(sim.worker_handler.workers.len(), 4);
+        assert_eq!(sim.worker_handler.wages_per_minute(), 0);
+    }
+
+    #[test]
+    fn crops_grow_on_their_own() {
+        let mut sim = fresh_sim();
+        sim.worker_handler.workers.clear();
+        sim.map
+            .dynamic_data
+            .tiles
+            .insert((0, 0), TileType::Farmland { crop: 0, stage: 0 });
+
+        for _ in 0..100 {
+            sim.tick();
+        }
+
+        let time_to_grow = sim.map.static_data.crops_data[0].time_to_grow;
+        assert_eq!(
+            sim.map.dynamic_data.tiles[&(0, 0)],
+            TileType::Farmland {
+                crop: 0,
+                stage: time_to_grow
+            }
+        );
+    }
+}
@@ -0,0 +1,377 @@
+use raylib::prelude::*;
+
+use crate::{
+    common::get_unix_time,
+    content::ContentIds,
+    localization::LocaleHandler,
+    pause_menu::{Button, ButtonState},
+    save::{self, MAX_SLOT_NAME_LENGTH, SaveFile, SaveSlot, SlotMeta},
+    settings::GameSettigns,
+    utils::{get_game_height, get_game_width, shrink_number_for_display},
+};
+
+const ROW_HEIGHT: f32 = 90.;
+const SLOT_BUTTON_WIDTH: f32 = 400.;
+const ACTION_BUTTON_WIDTH: f32 = 140.;
+const GAPS: f32 = 10.;
+
+struct SlotRow {
+    play: Button,
+    copy: Button,
+    rename: Button,
+    delete: Button,
+}
+
+pub struct SlotPicker {
+    slots: Vec<SaveSlot>,
+    rows: Vec<SlotRow>,
+    new_farm: Button,
+    scroll: usize,
+    // index of the slot being renamed and the name typed so far
+    renaming: Option<(usize, String)>,
+    // deleting takes a second click on the same button
+    confirm_delete: Option<usize>,
+    // shown under the title, for a slot that couldn't be played
+    notice: Option<String>,
+}
+
+impl SlotPicker {
+    fn new(rl: &mut RaylibHandle, locale_handler: &LocaleHandler) -> Self {
+        let mut picker = Self {
+            slots: vec![],
+            rows: vec![],
+            new_farm: Button::new(Rectangle::default(), String::new()),
+            scroll: 0,
+            renaming: None,
+            confirm_delete: None,
+            notice: None,
+        };
+
+        picker.refresh(rl, locale_handler);
+
+        picker
+    }
+
+    fn refresh(&mut self, rl: &mut RaylibHandle, locale_handler: &LocaleHandler) {
+        self.slots = save::list_slots();
+        self.renaming = None;
+        self.confirm_delete = None;
+        self.layout(rl, locale_handler);
+    }
+
+    fn visible_rows(rl: &mut RaylibHandle) -> usize {
+        ((get_game_height(rl) as f32 - 260.) / ROW_HEIGHT).max(1.) as usize
+    }
+
+    fn layout(&mut self, rl: &mut RaylibHandle, locale_handler: &LocaleHandler) {
+        let lang = &locale_handler.language_data;
+
+        let row_width = SLOT_BUTTON_WIDTH + 3. * (ACTION_BUTTON_WIDTH + GAPS);
+        let left = get_game_width(rl) as f32 / 2. - row_width / 2.;
+        let top = 100.;
+
+        let visible_rows = Self::visible_rows(rl);
+        self.scroll = self
+            .scroll
+            .min(self.slots.len().saturating_sub(visible_rows));
+
+        self.rows.clear();
+        for (index, slot) in self
+            .slots
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(visible_rows)
+        {
+            let y = top + (index - self.scroll) as f32 * ROW_HEIGHT;
+            let action_x =
+                |n: f32| left + SLOT_BUTTON_WIDTH + GAPS + n * (ACTION_BUTTON_WIDTH + GAPS);
+
+            let label = match &self.renaming {
+                Some((renaming, name)) if *renaming == index => format!("{name}_"),
+                _ => slot.name.clone(),
+            };
+            let delete_label = if self.confirm_delete == Some(index) {
+                lang.get("confirm_delete").unwrap()
+            } else {
+                lang.get("delete").unwrap()
+            };
+
+            self.rows.push(SlotRow {
+                play: Button::new(Rectangle::new(left, y, SLOT_BUTTON_WIDTH, 50.), label),
+                copy: Button::new(
+                    Rectangle::new(action_x(0.), y, ACTION_BUTTON_WIDTH, 50.),
+                    lang.get("copy").unwrap().to_string(),
+                ),
+                rename: Button::new(
+                    Rectangle::new(action_x(1.), y, ACTION_BUTTON_WIDTH, 50.),
+                    lang.get("rename").unwrap().to_string(),
+                ),
+                delete: Button::new(
+                    Rectangle::new(action_x(2.), y, ACTION_BUTTON_WIDTH, 50.),
+                    delete_label.to_string(),
+                ),
+            });
+        }
+
+        self.new_farm = Button::new(
+            Rectangle::new(
+                left,
+                top + visible_rows.min(self.slots.len()) as f32 * ROW_HEIGHT + 20.,
+                SLOT_BUTTON_WIDTH,
+                50.,
+            ),
+            lang.get("new_farm").unwrap().to_string(),
+        );
+    }
+
+    /// Returns the slot to play and its save once one is chosen
+    fn update(
+        &mut self,
+        rl: &mut RaylibHandle,
+        locale_handler: &LocaleHandler,
+        content_ids: &ContentIds,
+    ) -> Option<(SaveSlot, Option<SaveFile>)> {
+        if rl.is_window_resized() {
+            self.layout(rl, locale_handler);
+        }
+
+        if self.renaming.is_some() {
+            self.update_rename(rl, locale_handler);
+            return None;
+        }
+
+        let wheel = rl.get_mouse_wheel_move();
+        if wheel != 0. {
+            self.scroll = if wheel > 0. {
+                self.scroll.saturating_sub(1)
+            } else {
+                self.scroll + 1
+            };
+            self.layout(rl, locale_handler);
+        }
+
+        self.new_farm.update(rl);
+        if self.new_farm.state == ButtonState::Pressed {
+            match save::create_slot(locale_handler.language_data.get("farm_name").unwrap()) {
+                Ok(slot) => return Some((slot, None)),
+                Err(e) => println!("couldn't create a save slot ({e})"),
+            }
+        }
+
+        for row in self.rows.iter_mut() {
+            row.play.update(rl);
+            row.copy.update(rl);
+            row.rename.update(rl);
+            row.delete.update(rl);
+        }
+
+        let pressed = |button: &Button| button.state == ButtonState::Pressed;
+        let Some((row_index, row)) = self.rows.iter().enumerate().find(|(_, row)| {
+            pressed(&row.play) || pressed(&row.copy) || pressed(&row.rename) || pressed(&row.delete)
+        }) else {
+            if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+                && self.confirm_delete.is_some()
+            {
+                self.confirm_delete = None;
+                self.layout(rl, locale_handler);
+            }
+            return None;
+        };
+        let index = self.scroll + row_index;
+        let (play, copy, rename) = (pressed(&row.play), pressed(&row.copy), pressed(&row.rename));
+
+        if play {
+            match save::load(&self.slots[index], content_ids) {
+                Ok(save) => return Some((self.slots.swap_remove(index), save)),
+                Err(newer) => {
+                    self.notice = Some(format!(
+                        "{} ({})",
+                        locale_handler.language_data.get("save_too_new").unwrap(),
+                        newer.version
+                    ));
+                    return None;
+                }
+            }
+        }
+
+        if copy {
+            if let Err(e) = save::copy_slot(&self.slots[index]) {
+                println!("couldn't copy the save slot ({e})");
+            }
+            self.refresh(rl, locale_handler);
+        } else if rename {
+            self.confirm_delete = None;
+            self.renaming = Some((index, self.slots[index].name.clone()));
+            self.layout(rl, locale_handler);
+        } else {
+            if self.confirm_delete == Some(index) {
+                if let Err(e) = save::delete_slot(&self.slots[index]) {
+                    println!("couldn't delete the save slot ({e})");
+                }
+                self.refresh(rl, locale_handler);
+            } else {
+                self.confirm_delete = Some(index);
+                self.layout(rl, locale_handler);
+            }
+        }
+
+        None
+    }
+
+    fn update_rename(&mut self, rl: &mut RaylibHandle, locale_handler: &LocaleHandler) {
+        let Some((index, name)) = self.renaming.as_mut() else {
+            return;
+        };
+
+        while let Some(c) = rl.get_char_pressed() {
+            if name.chars().count() < MAX_SLOT_NAME_LENGTH {
+                name.push(c);
+            }
+        }
+
+        if rl.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+            name.pop();
+        }
+
+        if rl.is_key_pressed(KeyboardKey::KEY_ENTER) {
+            let (index, name) = (*index, name.clone());
+            if let Err(e) = save::rename_slot(&self.slots[index], &name) {
+                println!("couldn't rename the save slot ({e})");
+            }
+            self.refresh(rl, locale_handler);
+            return;
+        }
+
+        if rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+            self.renaming = None;
+        }
+
+        self.layout(rl, locale_handler);
+    }
+
+    fn draw(
+        &self,
+        rl: &mut RaylibDrawHandle,
+        font: &Font,
+        locale_handler: &LocaleHandler,
+        settings: &GameSettigns,
+    ) {
+        let lang = &locale_handler.language_data;
+
+        rl.clear_background(Color::DARKGREEN);
+
+        let screen_width = get_game_width(rl);
+
+        let title = lang.get("select_farm").unwrap();
+        rl.draw_text_ex(
+            font,
+            title,
+            Vector2::new(
+                screen_width as f32 / 2. - title.chars().count() as f32 * 8.,
+                40.,
+            ),
+            32.,
+            0.,
+            Color::RAYWHITE,
+        );
+
+        for (row_index, row) in self.rows.iter().enumerate() {
+            row.play.draw(rl, font);
+            row.copy.draw(rl, font);
+            row.rename.draw(rl, font);
+            row.delete.draw(rl, font);
+
+            let slot = &self.slots[self.scroll + row_index];
+            let details = match &slot.meta {
+                Some(meta) => describe_slot(meta, locale_handler, settings),
+                None => lang.get("new_farm").unwrap().to_string(),
+            };
+            rl.draw_text_ex(
+                font,
+                &details,
+                Vector2::new(row.play.rect.x + 4., row.play.rect.y + 54.),
+                20.,
+                0.,
+                Color::LIGHTGRAY,
+            );
+        }
+
+        self.new_farm.draw(rl, font);
+
+        if let Some(notice) = &self.notice {
+            rl.draw_text_ex(
+                font,
+                notice,
+                Vector2::new(
+                    screen_width as f32 / 2. - notice.chars().count() as f32 * 5.,
+                    74.,
+                ),
+                20.,
+                0.,
+                Color::ORANGE,
+            );
+        }
+    }
+}
+
+/// Level, money, play time and when the slot was last played, in one line
+fn describe_slot(
+    meta: &SlotMeta,
+    locale_handler: &LocaleHandler,
+    settings: &GameSettigns,
+) -> String {
+    let lang = &locale_handler.language_data;
+
+    let since = get_unix_time().saturating_sub(meta.last_played);
+    let last_played = if since >= 24 * 60 * 60 {
+        format!(
+            "{} {}",
+            since / (24 * 60 * 60),
+            lang.get("days_ago").unwrap()
+        )
+    } else if since >= 60 * 60 {
+        format!("{} {}", since / (60 * 60), lang.get("hours_ago").unwrap())
+    } else {
+        format!("{} {}", since / 60, lang.get("minutes_ago").unwrap())
+    };
+
+    format!(
+        "{} {}, {} {}, {}:{:02} {}, {}",
+        lang.get("level").unwrap(),
+        meta.level,
+        shrink_number_for_display(meta.money, locale_handler, settings),
+        lang.get("offline_money").unwrap(),
+        meta.play_time / 3600,
+        meta.play_time % 3600 / 60,
+        lang.get("played").unwrap(),
+        last_played,
+    )
+}
+
+/// Runs the slot picker until a slot is chosen and its save loaded, None if the window was closed
+pub fn pick_slot(
+    rl: &mut RaylibHandle,
+    thread: &RaylibThread,
+    font: &Font,
+    locale_handler: &LocaleHandler,
+    settings: &GameSettigns,
+    content_ids: &ContentIds,
+) -> Option<(SaveSlot, Option<SaveFile>)> {
+    if let Err(e) = save::adopt_unslotted_save() {
+        println!("couldn't move the old save into a slot ({e})");
+    }
+
+    let mut picker = SlotPicker::new(rl, locale_handler);
+
+    while !rl.window_should_close() {
+        if let Some(picked) = picker.update(rl, locale_handler, content_ids) {
+            return Some(picked);
+        }
+
+        let mut d = rl.begin_drawing(thread);
+        picker.draw(&mut d, font, locale_handler, settings);
+    }
+
+    None
+}
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+use crate::{
+    animal::AnimalHandler,
+    inventory::produce_name,
+    localization::LocaleHandler,
+    map::Map,
+    settings::GameSettigns,
+    sim::{Produce, Simulation},
+    utils::shrink_number_for_display,
+};
+
+/// Lifetime counters of the farm, what achievements and the session summary are made from
+#[serde_as]
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct Stats {
+    #[serde_as(as = "Vec<(_, _)>")]
+    pub harvests: HashMap<Produce, usize>,
+    // chunks of land
+    pub land_bought: usize,
+    pub workers_hired: usize,
+    pub money_earned: usize,
+    // in seconds. the save already keeps it next to the slot info, so it's filled in from there
+    #[serde(skip)]
+    pub play_time: u64,
+}
+
+impl Stats {
+    pub fn record_harvest(&mut self, produce: Produce) {
+        *self.harvests.entry(produce).or_insert(0) += 1;
+    }
+
+    pub fn total_harvests(&self) -> usize {
+        self.harvests.values().sum()
+    }
+
+    pub fn harvests_of(&self, produce: Produce) -> usize {
+        self.harvests.get(&produce).copied().unwrap_or(0)
+    }
+
+    /// What was added to the counters since `start`, to tell a session apart from the whole farm
+    pub fn since(&self, start: &Stats) -> Stats {
+        Stats {
+            harvests: self
+                .harvests
+                .iter()
+                .map(|(produce, amount)| {
+                    (*produce, amount.saturating_sub(start.harvests_of(*produce)))
+                })
+                .filter(|(_, amount)| *amount > 0)
+                .collect(),
+            // a restored backup can take the counters below where the session started
+            land_bought: self.land_bought.saturating_sub(start.land_bought),
+            workers_hired: self.workers_hired.saturating_sub(start.workers_hired),
+            money_earned: self.money_earned.saturating_sub(start.money_earned),
+            play_time: self.play_time.saturating_sub(start.play_time),
+        }
+    }
+
+    /// One line per counter, most harvested goods first, for the pause menu and the session summary
+    pub fn lines(
+        &self,
+        map: &Map,
+        animal_handler: &AnimalHandler,
+        locale_handler: &LocaleHandler,
+        settings: &GameSettigns,
+    ) -> Vec<String> {
+        let lang = &locale_handler.language_data;
+
+        let mut lines = vec![
+            format!(
+                "{}: {}h {}m",
+                lang.get("stat_play_time").unwrap(),
+                self.play_time / 3600,
+                self.play_time % 3600 / 60
+            ),
+            format!(
+                "{}: {}",
+                lang.get("stat_money_earned").unwrap(),
+                shrink_number_for_display(self.money_earned, locale_handler, settings)
+            ),
+            format!(
+                "{}: {}",
+                lang.get("stat_land_bought").unwrap(),
+                self.land_bought
+            ),
+            format!(
+                "{}: {}",
+                lang.get("stat_workers_hired").unwrap(),
+                self.workers_hired
+            ),
+            format!(
+                "{}: {}",
+                lang.get("stat_harvests").unwrap(),
+                self.total_harvests()
+            ),
+        ];
+
+        let mut harvests: Vec<(&Produce, &usize)> = self.harvests.iter().collect();
+        harvests.sort_by_key(|(_, amount)| std::cmp::Reverse(**amount));
+        lines.extend(harvests.into_iter().map(|(produce, amount)| {
+            format!(
+                "  {}: {amount}",
+                produce_name(*produce, map, animal_handler, locale_handler)
+            )
+        }));
+
+        lines
+    }
+}
+
+/// Plain text on what happened this session and on the farm as a whole, to be written next to the save
+pub fn session_summary(
+    sim: &Simulation,
+    session_start: &Stats,
+    locale_handler: &LocaleHandler,
+    settings: &GameSettigns,
+) -> String {
+    let lang = &locale_handler.language_data;
+    let (map, animal_handler) = (&sim.map, &sim.animal_handler);
+    let stats = &sim.player.stats;
+
+    let mut lines = vec![
+        format!(
+            "{} {}, {} {}",
+            lang.get("level").unwrap(),
+            sim.player.level,
+            lang.get("stat_money").unwrap(),
+            shrink_number_for_display(sim.player.money, locale_handler, settings)
+        ),
+        String::new(),
+        lang.get("this_session").unwrap().to_string(),
+    ];
+    lines.extend(
+        stats
+            .since(session_start)
+            .lines(map, animal_handler, locale_handler, settings)
+            .into_iter()
+            .map(|line| format!("  {line}")),
+    );
+
+    lines.push(String::new());
+    lines.push(lang.get("all_time").unwrap().to_string());
+    lines.extend(
+        stats
+            .lines(map, animal_handler, locale_handler, settings)
+            .into_iter()
+            .map(|line| format!("  {line}")),
+    );
+
+    let achievements = sim.achievements.lines(&sim.player, locale_handler);
+    lines.push(String::new());
+    lines.push(format!(
+        "{} {}/{}",
+        lang.get("achievements").unwrap(),
+        sim.achievements.unlocked_count(),
+        achievements.len()
+    ));
+    lines.extend(achievements.into_iter().map(|achievement| {
+        let (current, target) = achievement.progress;
+        if achievement.unlocked {
+            format!("  [x] {}", achievement.name)
+        } else {
+            format!(
+                "  [ ] {} {}/{target}",
+                achievement.name,
+                current.min(target)
+            )
+        }
+    }));
+
+    lines.join("\n") + "\n"
+}
@@ -0,0 +1,254 @@
+use raylib::prelude::*;
+
+use crate::{
+    inventory::{all_produce, produce_name, unit_price},
+    localization::LocaleHandler,
+    pause_menu::{Button, ButtonState},
+    settings::GameSettigns,
+    sim::{Produce, Simulation},
+    utils::{get_game_height, get_game_width, shrink_number_for_display},
+};
+
+const ROW_HEIGHT: f32 = 44.;
+const NAME_WIDTH: f32 = 170.;
+const AMOUNT_WIDTH: f32 = 80.;
+const KEEP_WIDTH: f32 = 210.;
+const SELL_WIDTH: f32 = 220.;
+const GAPS: f32 = 10.;
+const TOP: f32 = 120.;
+
+struct StorageRow {
+    produce: Produce,
+    name: String,
+    amount: usize,
+    keep: Button,
+    sell: Button,
+}
+
+/// Lists every kind of goods with how much is in storage. Each kind can be kept, so workers bring it
+/// to storage instead of selling it, and whatever is stored can be sold from here
+pub struct StoragePanel {
+    pub open: bool,
+    rows: Vec<StorageRow>,
+    // goods stored and how many fit
+    used: (usize, usize),
+    scroll: usize,
+    rect: Rectangle,
+}
+
+impl StoragePanel {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            rows: vec![],
+            used: (0, 0),
+            scroll: 0,
+            rect: Rectangle::default(),
+        }
+    }
+
+    pub fn blocks_mouse(&self, mouse_position: Vector2) -> bool {
+        self.open && self.rect.check_collision_point_rec(mouse_position)
+    }
+
+    fn visible_rows(rl: &mut RaylibHandle) -> usize {
+        ((get_game_height(rl) as f32 - TOP - 40.) / ROW_HEIGHT).max(1.) as usize
+    }
+
+    fn layout(
+        &mut self,
+        rl: &mut RaylibHandle,
+        sim: &Simulation,
+        locale_handler: &LocaleHandler,
+        settings: &GameSettigns,
+    ) {
+        let lang = &locale_handler.language_data;
+        let inventory = &sim.player.inventory;
+        let (map, animal_handler) = (&sim.map, &sim.animal_handler);
+
+        let row_width = NAME_WIDTH + AMOUNT_WIDTH + KEEP_WIDTH + SELL_WIDTH;
+        let left = get_game_width(rl) as f32 - row_width - GAPS * 4.;
+
+        let produce = all_produce(map, animal_handler);
+        let visible_rows = Self::visible_rows(rl);
+        self.scroll = self.scroll.min(produce.len().saturating_sub(visible_rows));
+
+        let shown = produce.len().min(visible_rows);
+        self.rect = Rectangle::new(
+            left - GAPS * 2.,
+            TOP - 60.,
+            row_width + GAPS * 4.,
+            shown as f32 * ROW_HEIGHT + 80.,
+        );
+
+        self.used = (inventory.total(), map.storage_capacity());
+
+        self.rows = produce
+            .into_iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(visible_rows)
+            .map(|(index, produce)| {
+                let y = TOP + (index - self.scroll) as f32 * ROW_HEIGHT;
+                let amount = inventory.amount(produce);
+
+                let keep = Button::new(
+                    Rectangle::new(
+                        left + NAME_WIDTH + AMOUNT_WIDTH,
+                        y,
+                        KEEP_WIDTH - GAPS,
+                        ROW_HEIGHT - 8.,
+                    ),
+                    lang.get(if inventory.is_kept(produce) {
+                        "keep"
+                    } else {
+                        "sell_at_harvest"
+                    })
+                    .unwrap()
+                    .to_string(),
+                );
+
+                let price = unit_price(produce, map, animal_handler, &sim.upgrade_handler);
+                let worth = (amount as f32 * price as f32 * sim.market.price(produce)) as usize;
+                let sell = Button::new(
+                    Rectangle::new(
+                        left + NAME_WIDTH + AMOUNT_WIDTH + KEEP_WIDTH,
+                        y,
+                        SELL_WIDTH,
+                        ROW_HEIGHT - 8.,
+                    ),
+                    format!(
+                        "{} {}",
+                        lang.get("sell_for").unwrap(),
+                        shrink_number_for_display(worth, locale_handler, settings)
+                    ),
+                );
+
+                StorageRow {
+                    produce,
+                    name: produce_name(produce, map, animal_handler, locale_handler),
+                    amount,
+                    keep,
+                    sell,
+                }
+            })
+            .collect();
+    }
+
+    /// Returns whether goods were sold or marked to keep
+    pub fn update(
+        &mut self,
+        rl: &mut RaylibHandle,
+        sim: &mut Simulation,
+        locale_handler: &LocaleHandler,
+        settings: &GameSettigns,
+    ) -> bool {
+        if rl.is_key_pressed(KeyboardKey::KEY_I) {
+            self.open = !self.open;
+        }
+
+        if !self.open {
+            return false;
+        }
+
+        let wheel = rl.get_mouse_wheel_move();
+        if wheel != 0. && self.blocks_mouse(rl.get_mouse_position()) {
+            self.scroll = if wheel > 0. {
+                self.scroll.saturating_sub(1)
+            } else {
+                self.scroll + 1
+            };
+        }
+
+        // goods come in every tick, so the rows are rebuilt every frame
+        self.layout(rl, sim, locale_handler, settings);
+
+        let mut changed = false;
+        for row in self.rows.iter_mut() {
+            row.keep.update(rl);
+            if row.keep.state == ButtonState::Pressed {
+                sim.player.inventory.toggle_kept(row.produce);
+                changed = true;
+            }
+
+            row.sell.update(rl);
+            if row.sell.state == ButtonState::Pressed && row.amount > 0 {
+                let sold = sim.player.inventory.take(row.produce, row.amount);
+                let price = unit_price(
+                    row.produce,
+                    &sim.map,
+                    &sim.animal_handler,
+                    &sim.upgrade_handler,
+                );
+                let money = sim.market.sell(row.produce, sold * price, price);
+                sim.player.earn(money);
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    pub fn draw(&self, rl: &mut RaylibDrawHandle, font: &Font, locale_handler: &LocaleHandler) {
+        if !self.open {
+            return;
+        }
+
+        let lang = &locale_handler.language_data;
+
+        rl.draw_rectangle_rec(self.rect, Color::BLACK.alpha(0.75));
+        rl.draw_text_ex(
+            font,
+            lang.get("storage").unwrap(),
+            Vector2::new(self.rect.x + GAPS * 2., self.rect.y + GAPS),
+            32.,
+            0.,
+            Color::RAYWHITE,
+        );
+
+        let (used, capacity) = self.used;
+        rl.draw_text_ex(
+            font,
+            &if capacity == 0 {
+                lang.get("no_storage").unwrap().to_string()
+            } else {
+                format!("{used}/{capacity}")
+            },
+            Vector2::new(
+                self.rect.x + NAME_WIDTH + AMOUNT_WIDTH,
+                self.rect.y + GAPS + 6.,
+            ),
+            24.,
+            0.,
+            if capacity == 0 || used >= capacity {
+                Color::ORANGE
+            } else {
+                Color::RAYWHITE
+            },
+        );
+
+        for row in self.rows.iter() {
+            let y = row.keep.rect.y + 6.;
+            let left = row.keep.rect.x - NAME_WIDTH - AMOUNT_WIDTH;
+
+            rl.draw_text_ex(
+                font,
+                &row.name,
+                Vector2::new(left, y),
+                24.,
+                0.,
+                Color::RAYWHITE,
+            );
+            rl.draw_text_ex(
+                font,
+                &row.amount.to_string(),
+                Vector2::new(left + NAME_WIDTH, y),
+                24.,
+                0.,
+                Color::LIGHTGRAY,
+            );
+
+            row.keep.draw(rl, font);
+            row.sell.draw(rl, font);
+        }
+    }
+}
@@ -2,6 +2,8 @@ use std::{collections::HashMap, fs};
 
 use raylib::prelude::*;
 
+use crate::mods;
+
 pub struct TextureHandler {
     pub textures: HashMap<String, Texture2D>,
 }
@@ -10,27 +12,30 @@ impl TextureHandler {
     pub fn new(rl: &mut RaylibHandle, thread: &RaylibThread) -> Self {
         let mut textures = HashMap::new();
 
-        let filenames = fs::read_dir("static/textures/").unwrap();
-
-        for filename in filenames {
-            let file = match filename {
-                Ok(f) => f,
-                Err(e) => panic!("couldn't load this particular texture {e}"),
-            };
-
-            let name = file
-                .file_name()
-                .into_string()
-                .unwrap()
-                .split('.')
-                .next()
-                .unwrap()
-                .to_string();
-
-            let texture = rl
-                .load_texture(&thread, file.path().to_str().unwrap())
-                .unwrap();
-            textures.insert(name, texture);
+        // packs load after the game, so their textures replace the game's ones with the same name
+        for dir in mods::asset_dirs("textures") {
+            let filenames = fs::read_dir(dir).unwrap();
+
+            for filename in filenames {
+                let file = match filename {
+                    Ok(f) => f,
+                    Err(e) => panic!("couldn't load this particular texture {e}"),
+                };
+
+                let name = file
+                    .file_name()
+                    .into_string()
+                    .unwrap()
+                    .split('.')
+                    .next()
+                    .unwrap()
+                    .to_string();
+
+                let texture = rl
+                    .load_texture(&thread, file.path().to_str().unwrap())
+                    .unwrap();
+                textures.insert(name, texture);
+            }
         }
 
         Self { textures }
@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "gui")]
+use crate::shop_ui::ShopLayout;
+use crate::{
+    map::Climate,
+    mods,
+    upgrades::{UpgradeHandler, UpgradeTarget},
+};
+
+#[derive(Deserialize)]
+pub struct ToolbarItem {
+    pub id: String,
+    pub tooltip: String,
+    pub unlock_level: usize,
+    pub price: usize,
+    // every one bought makes the next this much more expensive
+    price_growth: f32,
+    pub climate: Climate,
+}
+
+impl ToolbarItem {
+    fn new(tooltip: String, data: ToolbarItemData, climate: Climate) -> Self {
+        Self {
+            id: data.id,
+            tooltip,
+            unlock_level: data.unlock_level,
+            price: data.price,
+            price_growth: data.price_growth,
+            climate,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ToolbarItemData {
+    id: String,
+    unlock_level: usize,
+    price: usize,
+    #[serde(default = "default_price_growth")]
+    price_growth: f32,
+}
+
+fn default_price_growth() -> f32 {
+    1.1
+}
+
+#[derive(Deserialize)]
+struct ClimateEntry {
+    id: String,
+    #[serde(default)]
+    climate: Climate,
+}
+
+#[derive(Deserialize)]
+pub struct ToolbarStatic {
+    pub crops: Vec<ToolbarItem>,
+    pub trees: Vec<ToolbarItem>,
+    pub animals: Vec<ToolbarItem>,
+    pub beekeeping: Vec<ToolbarItem>,
+    pub misc: Vec<ToolbarItem>,
+    pub buildings: Vec<ToolbarItem>,
+}
+
+impl ToolbarStatic {
+    fn new(language_data: &HashMap<String, String>) -> Self {
+        let mut data: HashMap<String, Vec<ToolbarItemData>> =
+            mods::load_content("toolbar.json").expect("no toolbar");
+
+        // the shop shows the climate from the content itself, so it can't disagree with the map
+        let mut climates: HashMap<String, Vec<ClimateEntry>> =
+            mods::load_content("tiles.json").expect("Can't deserialize");
+        climates.extend(
+            mods::load_content::<HashMap<String, Vec<ClimateEntry>>>("animals.json")
+                .expect("no animals??"),
+        );
+        let climate_of = |lists: &[&str], id: &str| -> Climate {
+            lists
+                .iter()
+                .flat_map(|list| climates.get(*list).into_iter().flatten())
+                .find(|entry| entry.id == id)
+                .map_or(Climate::Unapplicable, |entry| entry.climate)
+        };
+
+        // tooltips are looked up by the item id, e.g. plant_carrot
+        let mut items = |key: &str, locale_prefix: &str, lists: &[&str]| -> Vec<ToolbarItem> {
+            data.remove(key)
+                .unwrap()
+                .into_iter()
+                .map(|data| {
+                    let tooltip = language_data
+                        .get(&format!("{locale_prefix}_{}", data.id))
+                        .unwrap()
+                        .to_string();
+                    let climate = climate_of(lists, &data.id);
+                    ToolbarItem::new(tooltip, data, climate)
+                })
+                .collect()
+        };
+
+        Self {
+            crops: items("crops", "plant", &["crops_data"]),
+            trees: items("trees", "tree", &["tree_data"]),
+            animals: items("animals", "animal", &["animal_data"]),
+            beekeeping: items("beekeeping", "beekeeping", &["hive_data", "flower_data"]),
+            misc: items("misc", "misc", &[]),
+            buildings: items("buildings", "building", &[]),
+        }
+    }
+
+    /// Where the misc tool with this id is in the toolbar
+    pub fn misc_index(&self, id: &str) -> Option<usize> {
+        self.misc.iter().position(|item| item.id == id)
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ToolbarDynamic {
+    pub crop_amount: HashMap<usize, usize>,
+    pub tree_amount: HashMap<usize, usize>,
+    pub animal_amount: HashMap<usize, usize>,
+    pub beekeeping_amount: HashMap<usize, usize>,
+    pub misc_amount: HashMap<usize, usize>,
+    #[serde(default)]
+    pub building_amount: HashMap<usize, usize>,
+}
+
+impl ToolbarDynamic {
+    fn new(static_data: &ToolbarStatic) -> Self {
+        let mut dynamic_data = Self {
+            crop_amount: HashMap::new(),
+            tree_amount: HashMap::new(),
+            animal_amount: HashMap::new(),
+            beekeeping_amount: HashMap::new(),
+            misc_amount: HashMap::new(),
+            building_amount: HashMap::new(),
+        };
+        dynamic_data.fill_missing(static_data);
+
+        dynamic_data
+    }
+
+    /// Anything added to the toolbar after the save was made starts with none bought
+    fn fill_missing(&mut self, static_data: &ToolbarStatic) {
+        for i in 0..static_data.crops.len() {
+            self.crop_amount.entry(i).or_insert(0);
+        }
+        for i in 0..static_data.trees.len() {
+            self.tree_amount.entry(i).or_insert(0);
+        }
+        for i in 0..static_data.animals.len() {
+            self.animal_amount.entry(i).or_insert(0);
+        }
+        for i in 0..static_data.beekeeping.len() {
+            self.beekeeping_amount.entry(i).or_insert(0);
+        }
+        for i in 0..static_data.misc.len() {
+            self.misc_amount.entry(i).or_insert(0);
+        }
+        for i in 0..static_data.buildings.len() {
+            self.building_amount.entry(i).or_insert(0);
+        }
+    }
+}
+
+pub struct ToolbarData {
+    pub static_data: ToolbarStatic,
+    pub dynamic_data: ToolbarDynamic,
+}
+
+impl ToolbarData {
+    fn new(language_data: &HashMap<String, String>, dynamic_data: Option<ToolbarDynamic>) -> Self {
+        let static_data = ToolbarStatic::new(language_data);
+        let dynamic_data = match dynamic_data {
+            Some(mut dynamic_data) => {
+                dynamic_data.fill_missing(&static_data);
+                dynamic_data
+            }
+            None => ToolbarDynamic::new(&static_data),
+        };
+
+        Self {
+            static_data,
+            dynamic_data,
+        }
+    }
+
+    pub fn get_price_for_crop(&self, index: usize, upgrade_handler: &UpgradeHandler) -> usize {
+        let item = &self.static_data.crops[index];
+        let mut price = item.price;
+        for _ in 0..*self.dynamic_data.crop_amount.get(&index).unwrap() {
+            price = (price as f32 * item.price_growth) as usize;
+        }
+        upgrade_handler.discounted_price(UpgradeTarget::Crop(index), price)
+    }
+
+    pub fn get_price_for_tree(&self, index: usize, upgrade_handler: &UpgradeHandler) -> usize {
+        let item = &self.static_data.trees[index];
+        let mut price = item.price;
+        for _ in 0..*self.dynamic_data.tree_amount.get(&index).unwrap() {
+            price = (price as f32 * item.price_growth) as usize;
+        }
+        upgrade_handler.discounted_price(UpgradeTarget::Tree(index), price)
+    }
+
+    pub fn get_price_for_animal(&self, index: usize, upgrade_handler: &UpgradeHandler) -> usize {
+        let item = &self.static_data.animals[index];
+        let mut price = item.price;
+        for _ in 0..*self.dynamic_data.animal_amount.get(&index).unwrap() {
+            price = (price as f32 * item.price_growth) as usize;
+        }
+        upgrade_handler.discounted_price(UpgradeTarget::Animal(index), price)
+    }
+
+    pub fn get_price_for_beekeeping(
+        &self,
+        index: usize,
+        upgrade_handler: &UpgradeHandler,
+    ) -> usize {
+        let item = &self.static_data.beekeeping[index];
+        let mut price = item.price;
+        for _ in 0..*self.dynamic_data.beekeeping_amount.get(&index).unwrap() {
+            price = (price as f32 * item.price_growth) as usize;
+        }
+        upgrade_handler.discounted_price(UpgradeTarget::Hive, price)
+    }
+
+    pub fn get_price_for_misc(&self, index: usize, upgrade_handler: &UpgradeHandler) -> usize {
+        let item = &self.static_data.misc[index];
+        let mut price = item.price;
+        for _ in 0..*self.dynamic_data.misc_amount.get(&index).unwrap() {
+            price = (price as f32 * item.price_growth) as usize;
+        }
+
+        // only hiring workers is affected by upgrades here
+        if index == 0 {
+            upgrade_handler.discounted_price(UpgradeTarget::Worker, price)
+        } else {
+            price
+        }
+    }
+
+    pub fn get_price_for_building(&self, index: usize) -> usize {
+        let item = &self.static_data.buildings[index];
+        let mut price = item.price;
+        for _ in 0..*self.dynamic_data.building_amount.get(&index).unwrap() {
+            price = (price as f32 * item.price_growth) as usize;
+        }
+        price
+    }
+
+    fn reload_static(&mut self, language_data: &HashMap<String, String>) {
+        self.static_data = ToolbarStatic::new(language_data);
+    }
+}
+
+#[derive(PartialEq)]
+pub enum MenuMode {
+    Crops,
+    Trees,
+    Animals,
+    Beekeeping,
+    Misc,
+    Buildings,
+}
+
+pub struct Canvas {
+    pub mode: MenuMode,
+    pub selected: usize,
+    pub climate_overlay: bool,
+    pub job_board_overlay: bool,
+    // the headless simulation has no screen to lay the shop out on
+    #[cfg(feature = "gui")]
+    pub layout: ShopLayout,
+    pub toolbar_data: ToolbarData,
+}
+
+impl Canvas {
+    pub fn new(
+        language_data: &HashMap<String, String>,
+        toolbar_save: Option<ToolbarDynamic>,
+    ) -> Self {
+        Self {
+            mode: MenuMode::Crops,
+            selected: 0,
+            climate_overlay: false,
+            job_board_overlay: false,
+            #[cfg(feature = "gui")]
+            layout: ShopLayout::new(),
+            toolbar_data: ToolbarData::new(language_data, toolbar_save),
+        }
+    }
+
+    /// The climate the selected shop item likes, if it cares about one
+    pub fn selected_climate(&self) -> Climate {
+        let static_data = &self.toolbar_data.static_data;
+        let items = match self.mode {
+            MenuMode::Crops => &static_data.crops,
+            MenuMode::Trees => &static_data.trees,
+            MenuMode::Animals => &static_data.animals,
+            MenuMode::Beekeeping => &static_data.beekeeping,
+            MenuMode::Misc => &static_data.misc,
+            MenuMode::Buildings => &static_data.buildings,
+        };
+
+        items
+            .get(self.selected)
+            .map_or(Climate::Unapplicable, |item| item.climate)
+    }
+
+    pub fn reload_toolbar_static(&mut self, language_data: &HashMap<String, String>) {
+        self.toolbar_data.reload_static(language_data);
+    }
+
+    pub fn reload_toolbar_dynamic(&mut self, toolbar_save: Option<ToolbarDynamic>) {
+        self.toolbar_data.dynamic_data = match toolbar_save {
+            Some(mut dynamic_data) => {
+                dynamic_data.fill_missing(&self.toolbar_data.static_data);
+                dynamic_data
+            }
+            None => ToolbarDynamic::new(&self.toolbar_data.static_data),
+        };
+    }
+
+    /// The id of the misc tool in hand, if one is
+    pub fn selected_misc(&self) -> Option<&str> {
+        if self.mode != MenuMode::Misc {
+            return None;
+        }
+
+        self.toolbar_data
+            .static_data
+            .misc
+            .get(self.selected)
+            .map(|item| item.id.as_str())
+    }
+}
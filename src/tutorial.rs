@@ -1,6 +1,8 @@
+#[cfg(feature = "gui")]
 use raylib::prelude::*;
 
-use crate::{player::Player, utils::{get_game_height, parse_json}};
+#[cfg(feature = "gui")]
+use crate::utils::get_game_height;
 
 struct TutorialStep {
     label: String,
@@ -22,15 +24,10 @@ pub struct Tutorial {
 }
 
 impl Tutorial {
-    pub fn new(language_code: String) -> Self {
-        let player: Result<Player, serde_json::Error> = parse_json("dynamic/player_save.json");
+    pub fn new(language_code: String, is_new_game: bool) -> Self {
+        let mut hidden = !is_new_game;
 
-        let mut hidden = match player {
-            Ok(_) => true,
-            Err(_) => false,
-        };
-
-        if language_code != "ru".to_string() {
+        if language_code != "ru" {
             hidden = true;
         }
 
@@ -47,7 +44,10 @@ impl Tutorial {
     pub fn complete_step(&mut self, index: usize) {
         self.steps[index].completed = true;
     }
+}
 
+#[cfg(feature = "gui")]
+impl Tutorial {
     pub fn draw(&self, rl: &mut RaylibDrawHandle, font: &Font) {
         if self.hidden {
             return;
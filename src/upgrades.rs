@@ -1,72 +1,164 @@
 use std::collections::HashMap;
 
-use raylib::{ffi::CheckCollisionPointRec, prelude::*};
 use serde::{Deserialize, Serialize};
 
-use crate::{
-    UI_BUTTON_SIZE, UI_GAPS,
-    localization::LocaleHandler,
-    map::TILE_PIXEL_SIZE,
-    pause_menu::GameSettigns,
-    player::Player,
-    utils::{get_game_width, parse_json, shrink_number_for_display},
-};
+use crate::{content::ContentIds, mods, player::Player};
+
+/// What an upgrade applies to. Global covers every kind of produce, but not workers
+#[derive(PartialEq, Copy, Clone)]
+pub enum UpgradeTarget {
+    Crop(usize),
+    Tree(usize),
+    Animal(usize),
+    Hive,
+    Worker,
+    Global,
+}
 
+// the same as UpgradeTarget, but with content ids as they're written in upgrades.json
 #[derive(Deserialize)]
+enum TargetDefinition {
+    Crop(String),
+    Tree(String),
+    Animal(String),
+    Hive,
+    Worker,
+    Global,
+}
+
+impl TargetDefinition {
+    fn resolve(self, content_ids: &ContentIds) -> Option<UpgradeTarget> {
+        Some(match self {
+            Self::Crop(id) => UpgradeTarget::Crop(ContentIds::index_of(&content_ids.crops, &id)?),
+            Self::Tree(id) => UpgradeTarget::Tree(ContentIds::index_of(&content_ids.trees, &id)?),
+            Self::Animal(id) => {
+                UpgradeTarget::Animal(ContentIds::index_of(&content_ids.animals, &id)?)
+            }
+            Self::Hive => UpgradeTarget::Hive,
+            Self::Worker => UpgradeTarget::Worker,
+            Self::Global => UpgradeTarget::Global,
+        })
+    }
+}
+
+#[derive(Deserialize, Copy, Clone)]
+pub enum UpgradeEffect {
+    // money and exp are multiplied by this
+    Multiplier(usize),
+    // extra progress per tick, for workers it's extra steps
+    GrowthSpeed(usize),
+    // in percent of the exp
+    ExpBonus(usize),
+    // in percent of the shop price
+    PriceDiscount(usize),
+}
+
+#[derive(Deserialize)]
+struct UpgradeDefinition {
+    id: String,
+    target: TargetDefinition,
+    effect: UpgradeEffect,
+    cost: usize,
+    icon: usize,
+    #[serde(default)]
+    prerequisites: Vec<String>,
+}
+
 pub struct UpgradeData {
+    pub id: String,
     pub label: String,
     pub description: String,
+    pub target: UpgradeTarget,
+    pub effect: UpgradeEffect,
     pub cost: usize,
+    pub icon: usize,
+    pub prerequisites: Vec<String>,
 }
 
-#[derive(Deserialize)]
 pub struct UpgradeStatic {
     pub upgrade_data: Vec<UpgradeData>,
 }
 
+impl UpgradeData {
+    /// Upgrades without a translation, like ones from a content pack, are shown by their id
+    fn translate(&mut self, language_data: &HashMap<String, String>) {
+        let text = |key: String| {
+            language_data
+                .get(&key)
+                .cloned()
+                .unwrap_or_else(|| self.id.clone())
+        };
+        self.label = text(format!("upgrade_{}", self.id));
+        self.description = text(format!("upgrade_description_{}", self.id));
+    }
+}
+
 impl UpgradeStatic {
-    fn new(language_data: &HashMap<String, String>) -> Self {
-        let upgrade_costs: HashMap<String, Vec<usize>> =
-            parse_json("static/upgrades.json").expect("no upgrade data");
-        let upgrade_costs = upgrade_costs.get("upgrade_costs").unwrap();
-        let mut upgrade_data = vec![];
-        for (index, cost) in upgrade_costs.iter().enumerate() {
-            let upgrade_datum = UpgradeData {
-                label: language_data
-                    .get(&format!("upgrade{index}"))
-                    .unwrap()
-                    .to_string(),
-                description: language_data
-                    .get(&format!("upgrade_description{index}"))
-                    .unwrap()
-                    .to_string(),
-                cost: *cost,
-            };
-            upgrade_data.push(upgrade_datum);
+    fn new(language_data: &HashMap<String, String>, content_ids: &ContentIds) -> Self {
+        let mut definitions: HashMap<String, Vec<UpgradeDefinition>> =
+            mods::load_content("upgrades.json").expect("no upgrade data");
+
+        let mut upgrade_data: Vec<UpgradeData> = definitions
+            .remove("upgrades")
+            .unwrap()
+            .into_iter()
+            .map(|definition| UpgradeData {
+                target: definition
+                    .target
+                    .resolve(content_ids)
+                    .unwrap_or_else(|| panic!("upgrade {} targets unknown content", definition.id)),
+                label: String::new(),
+                description: String::new(),
+                id: definition.id,
+                effect: definition.effect,
+                cost: definition.cost,
+                icon: definition.icon,
+                prerequisites: definition.prerequisites,
+            })
+            .collect();
+        for upgrade in upgrade_data.iter_mut() {
+            upgrade.translate(language_data);
+        }
+
+        for upgrade in upgrade_data.iter() {
+            for prerequisite in upgrade.prerequisites.iter() {
+                if !upgrade_data.iter().any(|other| other.id == *prerequisite) {
+                    panic!(
+                        "upgrade {} requires unknown upgrade {prerequisite}",
+                        upgrade.id
+                    );
+                }
+            }
         }
+
         Self { upgrade_data }
     }
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct UpgradeDynamic {
-    pub purchased_upgrades: Vec<usize>,
+    pub purchased_upgrades: Vec<String>,
 }
 
 pub struct UpgradeHandler {
     pub static_data: UpgradeStatic,
     pub dynamic_data: UpgradeDynamic,
     pub ui_blocks_mouse: bool,
+    // in percent of money and exp, bought with prestige and kept through resets
+    pub permanent_bonus: (usize, usize),
 }
 
 impl UpgradeHandler {
-    pub fn new(language_data: &HashMap<String, String>) -> Self {
-        let static_data = UpgradeStatic::new(language_data);
-
-        let res = parse_json("dynamic/upgrades_save.json");
-        let dynamic_data = match res {
-            Ok(dynamic_data) => dynamic_data,
-            Err(_) => UpgradeDynamic {
+    pub fn new(
+        language_data: &HashMap<String, String>,
+        content_ids: &ContentIds,
+        dynamic_data: Option<UpgradeDynamic>,
+    ) -> Self {
+        let static_data = UpgradeStatic::new(language_data, content_ids);
+
+        let dynamic_data = match dynamic_data {
+            Some(dynamic_data) => dynamic_data,
+            None => UpgradeDynamic {
                 purchased_upgrades: vec![],
             },
         };
@@ -75,257 +167,245 @@ impl UpgradeHandler {
             static_data,
             dynamic_data,
             ui_blocks_mouse: false,
+            permanent_bonus: (0, 0),
         }
     }
 
-    pub fn reload_static(&mut self, language_data: &HashMap<String, String>) {
-        self.static_data = UpgradeStatic::new(language_data);
+    /// Puts the names and descriptions into another language
+    pub fn translate(&mut self, language_data: &HashMap<String, String>) {
+        for upgrade in self.static_data.upgrade_data.iter_mut() {
+            upgrade.translate(language_data);
+        }
     }
 
-    pub fn get_multiplier_for_crop(&self, crop: usize) -> usize {
-        let mut temp = 1;
-        if self.dynamic_data.purchased_upgrades.contains(&(crop * 3)) {
-            temp *= 2;
-        }
-        if self
-            .dynamic_data
+    fn is_purchased(&self, id: &str) -> bool {
+        self.dynamic_data
             .purchased_upgrades
-            .contains(&(crop * 3 + 1))
-        {
-            temp *= 2;
-        }
-        if self
-            .dynamic_data
-            .purchased_upgrades
-            .contains(&(crop * 3 + 2))
-        {
-            temp *= 2;
-        }
+            .iter()
+            .any(|purchased| purchased == id)
+    }
 
-        temp
+    /// Shown in the shop once the player got close to affording it and owns everything it requires
+    pub fn is_available(&self, upgrade: &UpgradeData, player: &Player) -> bool {
+        upgrade.cost / 2 <= player.alltime_max_money
+            && !self.is_purchased(&upgrade.id)
+            && upgrade
+                .prerequisites
+                .iter()
+                .all(|prerequisite| self.is_purchased(prerequisite))
     }
 
-    pub fn get_multiplier_for_tree(&self, tree: usize, crops_len: usize) -> usize {
-        let mut temp = 1;
-        if self
-            .dynamic_data
-            .purchased_upgrades
-            .contains(&(crops_len * 3 + tree * 3))
-        {
-            temp *= 2;
-        }
-        if self
-            .dynamic_data
-            .purchased_upgrades
-            .contains(&(crops_len * 3 + tree * 3 + 1))
-        {
-            temp *= 2;
-        }
-        if self
-            .dynamic_data
-            .purchased_upgrades
-            .contains(&(crops_len * 3 + tree * 3 + 2))
-        {
-            temp *= 2;
+    /// Effects of the purchased upgrades that apply to the target
+    fn effects_for(&self, target: UpgradeTarget) -> impl Iterator<Item = UpgradeEffect> {
+        self.static_data
+            .upgrade_data
+            .iter()
+            .filter(move |upgrade| {
+                upgrade.target == target
+                    || (upgrade.target == UpgradeTarget::Global && target != UpgradeTarget::Worker)
+            })
+            .filter(|upgrade| self.is_purchased(&upgrade.id))
+            .map(|upgrade| upgrade.effect)
+    }
+
+    /// Money and exp a produce gives once upgrades are applied
+    pub fn apply(&self, target: UpgradeTarget, money: usize, exp: usize) -> (usize, usize) {
+        let mut multiplier = 1;
+        let mut exp_bonus = 0;
+        for effect in self.effects_for(target) {
+            match effect {
+                UpgradeEffect::Multiplier(amount) => multiplier *= amount,
+                UpgradeEffect::ExpBonus(percent) => exp_bonus += percent,
+                _ => {}
+            }
         }
 
-        temp
+        let (permanent_money, permanent_exp) = self.permanent_bonus;
+        (
+            money * multiplier * (100 + permanent_money) / 100,
+            exp * multiplier * (100 + exp_bonus + permanent_exp) / 100,
+        )
     }
 
-    pub fn get_multiplier_for_animal(
-        &self,
-        animal: usize,
-        crops_len: usize,
-        trees_len: usize,
-    ) -> usize {
-        let mut temp = 1;
-        if self
-            .dynamic_data
-            .purchased_upgrades
-            .contains(&(crops_len * 3 + trees_len * 3 + animal * 3))
-        {
-            temp *= 2;
-        }
-        if self
-            .dynamic_data
-            .purchased_upgrades
-            .contains(&(crops_len * 3 + trees_len * 3 + animal * 3 + 1))
-        {
-            temp *= 2;
-        }
-        if self
-            .dynamic_data
-            .purchased_upgrades
-            .contains(&(crops_len * 3 + trees_len * 3 + animal * 3 + 2))
-        {
-            temp *= 2;
-        }
+    pub fn growth_speed(&self, target: UpgradeTarget) -> usize {
+        1 + self
+            .effects_for(target)
+            .map(|effect| match effect {
+                UpgradeEffect::GrowthSpeed(amount) => amount,
+                _ => 0,
+            })
+            .sum::<usize>()
+    }
 
-        temp
+    pub fn discounted_price(&self, target: UpgradeTarget, price: usize) -> usize {
+        let discount: usize = self
+            .effects_for(target)
+            .map(|effect| match effect {
+                UpgradeEffect::PriceDiscount(percent) => percent,
+                _ => 0,
+            })
+            .sum();
+
+        // never give things away for free
+        price * (100 - discount.min(90)) / 100
     }
+}
 
-    pub fn get_multiplier_for_beehive(
-        &self,
-        crops_len: usize,
-        trees_len: usize,
-        animals_len: usize,
-    ) -> usize {
-        let mut temp = 1;
-        if self
-            .dynamic_data
-            .purchased_upgrades
-            .contains(&(crops_len * 3 + trees_len * 3 + animals_len * 3))
-        {
-            temp *= 2;
-        }
-        if self
-            .dynamic_data
-            .purchased_upgrades
-            .contains(&(crops_len * 3 + trees_len * 3 + animals_len * 3 + 1))
-        {
-            temp *= 2;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn upgrade(id: &str, target: UpgradeTarget, effect: UpgradeEffect) -> UpgradeData {
+        UpgradeData {
+            id: id.to_string(),
+            label: String::new(),
+            description: String::new(),
+            target,
+            effect,
+            cost: 1000,
+            icon: 0,
+            prerequisites: vec![],
         }
-        if self
-            .dynamic_data
-            .purchased_upgrades
-            .contains(&(crops_len * 3 + trees_len * 3 + animals_len * 3 + 2))
-        {
-            temp *= 2;
+    }
+
+    fn handler_with(upgrade_data: Vec<UpgradeData>, purchased: &[&str]) -> UpgradeHandler {
+        UpgradeHandler {
+            static_data: UpgradeStatic { upgrade_data },
+            dynamic_data: UpgradeDynamic {
+                purchased_upgrades: purchased.iter().map(|id| id.to_string()).collect(),
+            },
+            ui_blocks_mouse: false,
+            permanent_bonus: (0, 0),
         }
+    }
 
-        temp
+    #[test]
+    fn purchased_upgrades_raise_money_and_exp() {
+        let mut handler = handler_with(
+            vec![
+                upgrade(
+                    "double",
+                    UpgradeTarget::Crop(0),
+                    UpgradeEffect::Multiplier(2),
+                ),
+                upgrade("wisdom", UpgradeTarget::Global, UpgradeEffect::ExpBonus(50)),
+                upgrade(
+                    "triple",
+                    UpgradeTarget::Crop(1),
+                    UpgradeEffect::Multiplier(3),
+                ),
+            ],
+            &["double", "wisdom"],
+        );
+
+        assert_eq!(handler.apply(UpgradeTarget::Crop(0), 10, 10), (20, 30));
+        assert_eq!(handler.apply(UpgradeTarget::Crop(1), 10, 10), (10, 15));
+        // global upgrades are for produce only
+        assert_eq!(handler.apply(UpgradeTarget::Worker, 10, 10), (10, 10));
+
+        handler.permanent_bonus = (10, 0);
+        assert_eq!(handler.apply(UpgradeTarget::Crop(0), 10, 10), (22, 30));
     }
 
-    pub fn draw(
-        &mut self,
-        rl: &mut RaylibDrawHandle,
-        texture: &Texture2D,
-        font: &Font,
-        player: &mut Player,
-        locale_handler: &LocaleHandler,
-        settings: &GameSettigns,
-    ) {
-        let mut offset = 0;
-        for i in 0..self.static_data.upgrade_data.len() {
-            if self.static_data.upgrade_data[i].cost / 2 > player.alltime_max_money {
-                offset += 1;
-                continue;
-            }
+    #[test]
+    fn growth_speed_adds_up() {
+        let handler = handler_with(
+            vec![
+                upgrade(
+                    "fast",
+                    UpgradeTarget::Tree(0),
+                    UpgradeEffect::GrowthSpeed(2),
+                ),
+                upgrade(
+                    "faster",
+                    UpgradeTarget::Global,
+                    UpgradeEffect::GrowthSpeed(1),
+                ),
+            ],
+            &["fast", "faster"],
+        );
+
+        assert_eq!(handler.growth_speed(UpgradeTarget::Tree(0)), 4);
+        assert_eq!(handler.growth_speed(UpgradeTarget::Tree(1)), 2);
+        assert_eq!(handler.growth_speed(UpgradeTarget::Worker), 1);
+    }
 
-            if self.dynamic_data.purchased_upgrades.contains(&i) {
-                offset += 1;
-                continue;
-            }
+    #[test]
+    fn discounts_add_up_but_never_make_things_free() {
+        let target = UpgradeTarget::Animal(0);
+        let mut handler = handler_with(
+            vec![
+                upgrade("sale", target, UpgradeEffect::PriceDiscount(30)),
+                upgrade("bigger_sale", target, UpgradeEffect::PriceDiscount(30)),
+                upgrade("huge_sale", target, UpgradeEffect::PriceDiscount(50)),
+            ],
+            &["sale"],
+        );
+        assert_eq!(handler.discounted_price(target, 1000), 700);
+        assert_eq!(
+            handler.discounted_price(UpgradeTarget::Animal(1), 1000),
+            1000
+        );
+
+        handler.dynamic_data.purchased_upgrades =
+            vec!["sale".to_string(), "bigger_sale".to_string()];
+        assert_eq!(handler.discounted_price(target, 1000), 400);
+
+        handler
+            .dynamic_data
+            .purchased_upgrades
+            .push("huge_sale".to_string());
+        assert_eq!(handler.discounted_price(target, 1000), 100);
+    }
 
-            let i = i as i32;
-            let scale = UI_BUTTON_SIZE + UI_GAPS / 2.;
-            let button_rect = Rectangle::new(
-                ((i - offset) % 3) as f32 * scale + get_game_width(rl) as f32 - 3. * scale,
-                ((i - offset) / 3) as f32 * scale + UI_GAPS / 2.,
-                UI_BUTTON_SIZE,
-                UI_BUTTON_SIZE,
-            );
-
-            let source = Rectangle::new(
-                (i % 3 * TILE_PIXEL_SIZE) as f32,
-                (i / 3 * TILE_PIXEL_SIZE) as f32,
-                TILE_PIXEL_SIZE as f32,
-                TILE_PIXEL_SIZE as f32,
-            );
-
-            rl.draw_rectangle_rec(button_rect, Color::BLACK.alpha(0.5));
-            rl.draw_texture_pro(
-                texture,
-                source,
-                button_rect,
-                Vector2::zero(),
-                0.,
-                Color::WHITE,
-            );
-        }
+    #[test]
+    fn upgrades_wait_for_their_prerequisites() {
+        let mut silver = upgrade(
+            "silver",
+            UpgradeTarget::Crop(0),
+            UpgradeEffect::Multiplier(2),
+        );
+        silver.prerequisites = vec!["bronze".to_string()];
+        let mut handler = handler_with(
+            vec![
+                upgrade(
+                    "bronze",
+                    UpgradeTarget::Crop(0),
+                    UpgradeEffect::Multiplier(2),
+                ),
+                silver,
+            ],
+            &[],
+        );
+        let mut player = Player::new();
+        player.alltime_max_money = 500;
+        let [bronze, silver] = [0, 1];
+
+        let available = |handler: &UpgradeHandler, player: &Player, index: usize| {
+            handler.is_available(&handler.static_data.upgrade_data[index], player)
+        };
+        assert!(available(&handler, &player, bronze));
+        assert!(!available(&handler, &player, silver));
 
-        offset = 0;
-        self.ui_blocks_mouse = false;
-        for i in 0..self.static_data.upgrade_data.len() {
-            if self.static_data.upgrade_data[i].cost / 2 > player.alltime_max_money {
-                offset += 1;
-                continue;
-            }
+        handler
+            .dynamic_data
+            .purchased_upgrades
+            .push("bronze".to_string());
+        assert!(!available(&handler, &player, bronze));
+        assert!(available(&handler, &player, silver));
 
-            if self.dynamic_data.purchased_upgrades.contains(&i) {
-                offset += 1;
-                continue;
-            }
+        // and for the player to get close to affording them
+        player.alltime_max_money = 499;
+        assert!(!available(&handler, &player, silver));
+    }
 
-            let data = &self.static_data.upgrade_data[i];
-
-            let i = i as i32;
-            let scale = UI_BUTTON_SIZE + UI_GAPS / 2.;
-            let button_rect = Rectangle::new(
-                ((i - offset) % 3) as f32 * scale + get_game_width(rl) as f32 - 3. * scale,
-                ((i - offset) / 3) as f32 * scale + UI_GAPS / 2.,
-                UI_BUTTON_SIZE,
-                UI_BUTTON_SIZE,
-            );
-
-            if !unsafe {
-                CheckCollisionPointRec(rl.get_mouse_position().into(), button_rect.into())
-            } {
-                continue;
-            }
+    #[test]
+    fn untranslated_upgrades_are_shown_by_id() {
+        let mut upgrade = upgrade("modded", UpgradeTarget::Hive, UpgradeEffect::GrowthSpeed(1));
 
-            self.ui_blocks_mouse = true;
-
-            let x = rl.get_mouse_position().x
-                - data.description.chars().count() as f32 / 2. * UI_BUTTON_SIZE / 4.;
-            let y = rl.get_mouse_position().y;
-
-            let tooltip_rect = Rectangle::new(
-                x,
-                y,
-                data.description.chars().count() as f32 / 2. * UI_BUTTON_SIZE / 4.,
-                UI_BUTTON_SIZE * 2.,
-            );
-
-            rl.draw_rectangle_rec(tooltip_rect, Color::BLACK.alpha(0.75));
-            rl.draw_text_ex(
-                font,
-                &data.label,
-                Vector2::new(x + 5., y),
-                UI_BUTTON_SIZE / 2.,
-                0.,
-                Color::RAYWHITE,
-            );
-            rl.draw_text_ex(
-                font,
-                &data.description,
-                Vector2::new(x + 5., y + UI_BUTTON_SIZE / 2.),
-                UI_BUTTON_SIZE / 3.,
-                0.,
-                Color::DARKGRAY,
-            );
-            rl.draw_text_ex(
-                font,
-                &shrink_number_for_display(data.cost, locale_handler, settings),
-                Vector2::new(x + 5., y + UI_BUTTON_SIZE + UI_BUTTON_SIZE / 3.),
-                UI_BUTTON_SIZE / 2.,
-                0.,
-                Color::RAYWHITE,
-            );
-
-            if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
-                if player.money >= data.cost {
-                    player.money -= data.cost;
-                    self.dynamic_data.purchased_upgrades.push(i as usize);
-                }
-            }
-        }
-    }
+        upgrade.translate(&HashMap::new());
 
-    pub fn save(&self) {
-        let serialized = serde_json::to_string_pretty(&self.dynamic_data).expect("err");
-        std::fs::create_dir_all("dynamic").expect("Couldn't create dir");
-        std::fs::write("dynamic/upgrades_save.json", serialized)
-            .expect("Couldn't write upgrades data to json");
+        assert_eq!(upgrade.label, "modded");
+        assert_eq!(upgrade.description, "modded");
     }
 }
@@ -1,20 +1,12 @@
+#[cfg(feature = "gui")]
 use raylib::{
     RaylibHandle,
     window::{get_current_monitor, get_monitor_height, get_monitor_width},
 };
-use serde::de::{self, Error};
-use std::fs;
 
-use crate::{localization::LocaleHandler, pause_menu::GameSettigns};
-
-pub fn parse_json<T: de::DeserializeOwned>(path: &str) -> Result<T, serde_json::Error> {
-    let res = fs::read_to_string(path);
-    match res {
-        Ok(s) => return serde_json::from_str(&s),
-        Err(_) => return Result::Err(Error::custom("No such file")),
-    };
-}
+use crate::{localization::LocaleHandler, settings::GameSettigns};
 
+#[cfg(feature = "gui")]
 pub fn get_game_width(rl: &mut RaylibHandle) -> i32 {
     if rl.is_window_fullscreen() {
         get_monitor_width(get_current_monitor())
@@ -23,6 +15,7 @@ pub fn get_game_width(rl: &mut RaylibHandle) -> i32 {
     }
 }
 
+#[cfg(feature = "gui")]
 pub fn get_game_height(rl: &mut RaylibHandle) -> i32 {
     if rl.is_window_fullscreen() {
         get_monitor_height(get_current_monitor())
@@ -40,7 +33,7 @@ pub fn shrink_number_for_display(number: usize, locale_handler: &LocaleHandler,
         let leftover = (number % 1_000_000_000_000) / 1_000_000_000;
         let zeros = "0".repeat(3 - leftover.to_string().chars().count());
 
-        if leftover <= 0 {
+        if leftover == 0 {
             return format!(
                 "{} {}",
                 number / 1_000_000_000_000,
@@ -57,7 +50,7 @@ pub fn shrink_number_for_display(number: usize, locale_handler: &LocaleHandler,
         let leftover = (number % 1_000_000_000) / 1_000_000;
         let zeros = "0".repeat(3 - leftover.to_string().chars().count());
 
-        if leftover <= 0 {
+        if leftover == 0 {
             return format!(
                 "{} {}",
                 number / 1_000_000_000,
@@ -74,7 +67,7 @@ pub fn shrink_number_for_display(number: usize, locale_handler: &LocaleHandler,
         let leftover = (number % 1_000_000) / 1_000;
         let zeros = "0".repeat(3 - leftover.to_string().chars().count());
 
-        if leftover <= 0 {
+        if leftover == 0 {
             return format!(
                 "{} {}",
                 number / 1_000_000,
@@ -91,7 +84,7 @@ pub fn shrink_number_for_display(number: usize, locale_handler: &LocaleHandler,
         let leftover = number % 1_000;
         let zeros = "0".repeat(3 - leftover.to_string().chars().count());
 
-        if leftover <= 0 {
+        if leftover == 0 {
             return format!(
                 "{} {}",
                 number / 1_000,
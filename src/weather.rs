@@ -0,0 +1,262 @@
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::IndexedRandom};
+use serde::{Deserialize, Serialize};
+
+use crate::{common::parse_json, map::Climate};
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+pub enum Particles {
+    Rain,
+    Snow,
+}
+
+#[derive(Deserialize)]
+pub struct WeatherKind {
+    pub id: String,
+    // in ticks
+    min_duration: usize,
+    max_duration: usize,
+    // in percent of the usual speed
+    growth: usize,
+    // plants that like these climates lose their progress, each tick with harm_chance
+    #[serde(default)]
+    harms: Vec<Climate>,
+    #[serde(default)]
+    harm_chance: f64,
+    // chance each tick that a tree loses its fruit
+    #[serde(default)]
+    fruit_drop_chance: f64,
+    // colour mixed into the sea and over the island, the alpha is how much
+    pub tint: [u8; 4],
+    // added to the height of the waves
+    #[serde(default)]
+    pub waves: f32,
+    #[serde(default)]
+    pub particles: Option<Particles>,
+}
+
+#[derive(Deserialize)]
+pub struct Season {
+    pub id: String,
+    // in percent, plants of a climate that isn't listed grow as usual
+    growth: Vec<(Climate, usize)>,
+    // weather ids and how likely they are
+    pub weather: Vec<(String, usize)>,
+}
+
+impl Season {
+    fn growth_for(&self, climate: Climate) -> usize {
+        self.growth
+            .iter()
+            .find(|(other, _)| *other == climate)
+            .map(|(_, percent)| *percent)
+            .unwrap_or(100)
+    }
+}
+
+/// The calendar and the weather that can come with each season, read from static/weather.json
+#[derive(Deserialize)]
+pub struct WeatherSettings {
+    // in ticks
+    season_length: usize,
+    pub seasons: Vec<Season>,
+    pub weather: Vec<WeatherKind>,
+}
+
+impl WeatherSettings {
+    fn load() -> Self {
+        parse_json("static/weather.json").expect("no weather settings")
+    }
+}
+
+/// Where the farm is in the year and what the sky is doing. The weather is saved by id, so it stays put
+/// when weather is added or reordered. What comes next only depends on the seed and the tick, so the same
+/// farm always sees the same skies, loaded or not
+#[derive(Serialize, Deserialize)]
+pub struct Weather {
+    // farms from before the seed get a new one
+    #[serde(default = "rand::random")]
+    seed: u64,
+    // ticks since the first spring
+    tick: usize,
+    current: String,
+    ticks_left: usize,
+    #[serde(skip, default = "WeatherSettings::load")]
+    pub settings: WeatherSettings,
+}
+
+impl Weather {
+    pub fn new(seed: u64) -> Self {
+        let settings = WeatherSettings::load();
+        Self {
+            seed,
+            tick: 0,
+            current: settings.weather[0].id.clone(),
+            // rolled on the first tick
+            ticks_left: 0,
+            settings,
+        }
+    }
+
+    pub fn season(&self) -> &Season {
+        let seasons = &self.settings.seasons;
+        &seasons[self.tick / self.settings.season_length.max(1) % seasons.len()]
+    }
+
+    pub fn current(&self) -> &WeatherKind {
+        self.settings
+            .weather
+            .iter()
+            .find(|kind| kind.id == self.current)
+            .unwrap_or(&self.settings.weather[0])
+    }
+
+    pub fn update(&mut self) {
+        self.tick += 1;
+        if self.ticks_left > 0 {
+            self.ticks_left -= 1;
+            return;
+        }
+
+        let mut rng = StdRng::seed_from_u64(self.seed ^ self.tick as u64);
+        let Ok((id, _)) = self
+            .season()
+            .weather
+            .choose_weighted(&mut rng, |(_, weight)| *weight)
+        else {
+            return;
+        };
+        self.current = id.clone();
+
+        let kind = self.current();
+        self.ticks_left =
+            rng.random_range(kind.min_duration..=kind.max_duration.max(kind.min_duration));
+    }
+
+    /// The dice for what the weather does to the tile at this position this tick. They're rolled from the
+    /// seed and the tick like the weather itself, so the farm grows the same way loaded or not
+    pub fn rng_at(&self, position: (i32, i32)) -> StdRng {
+        let position = (position.0 as u32 as u64) << 32 | position.1 as u32 as u64;
+        StdRng::seed_from_u64(
+            self.seed ^ (self.tick as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ position,
+        )
+    }
+
+    /// How fast a plant that likes this climate grows right now, in percent of the usual speed.
+    /// The season and the weather both scale it
+    fn growth_percent(&self, climate: Climate) -> usize {
+        self.season().growth_for(climate) * self.current().growth / 100
+    }
+
+    /// How many growth steps a plant that likes this climate takes this tick, whatever is left over
+    /// from whole steps is a chance of one more
+    pub fn growth_steps(&self, climate: Climate, rng: &mut impl Rng) -> usize {
+        let percent = self.growth_percent(climate);
+        percent / 100 + rng.random_bool((percent % 100) as f64 / 100.) as usize
+    }
+
+    /// Whether a plant that likes this climate loses its progress this tick
+    pub fn harms(&self, climate: Climate, rng: &mut impl Rng) -> bool {
+        let kind = self.current();
+        kind.harms.contains(&climate) && rng.random_bool(kind.harm_chance.clamp(0., 1.))
+    }
+
+    pub fn knocks_fruit_off(&self, rng: &mut impl Rng) -> bool {
+        rng.random_bool(self.current().fruit_drop_chance.clamp(0., 1.))
+    }
+
+    /// The tint for the sea shader, in 0-1
+    pub fn shader_tint(&self) -> [f32; 4] {
+        self.current().tint.map(|channel| channel as f32 / 255.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // two short seasons, one kind to each, so every tick's growth is known
+    fn weather_with(current: &str) -> Weather {
+        let settings = serde_json::from_str(
+            r#"{
+                "season_length": 10,
+                "seasons": [
+                    { "id": "summer", "growth": [["Warm", 150]], "weather": [["rain", 1]] },
+                    { "id": "winter", "growth": [["Warm", 40], ["Cold", 200]], "weather": [["frost", 1]] }
+                ],
+                "weather": [
+                    { "id": "rain", "min_duration": 5, "max_duration": 5, "growth": 150, "tint": [0, 0, 0, 0] },
+                    { "id": "frost", "min_duration": 5, "max_duration": 5, "growth": 50, "tint": [0, 0, 0, 0] }
+                ]
+            }"#,
+        )
+        .unwrap();
+        Weather {
+            seed: 0,
+            tick: 0,
+            current: current.to_string(),
+            ticks_left: 5,
+            settings,
+        }
+    }
+
+    #[test]
+    fn seasons_and_weather_both_scale_growth() {
+        let mut weather = weather_with("rain");
+        assert_eq!(weather.growth_percent(Climate::Warm), 225);
+        // climates the season doesn't list grow as usual, apart from the weather
+        assert_eq!(weather.growth_percent(Climate::Temperate), 150);
+
+        weather.tick = 10;
+        weather.current = "frost".to_string();
+        assert_eq!(weather.season().id, "winter");
+        assert_eq!(weather.growth_percent(Climate::Warm), 20);
+        assert_eq!(weather.growth_percent(Climate::Cold), 100);
+    }
+
+    #[test]
+    fn leftover_growth_is_a_chance_of_one_more_step() {
+        let mut weather = weather_with("rain");
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let steps: Vec<usize> = (0..10000)
+            .map(|_| weather.growth_steps(Climate::Warm, &mut rng))
+            .collect();
+        assert!(steps.iter().all(|steps| (2..=3).contains(steps)));
+        let average = steps.iter().sum::<usize>() as f32 / steps.len() as f32;
+        assert!((average - 2.25).abs() < 0.05);
+
+        // whole steps leave nothing to chance
+        weather.tick = 10;
+        weather.current = "frost".to_string();
+        assert!((0..100).all(|_| weather.growth_steps(Climate::Cold, &mut rng) == 1));
+    }
+
+    fn skies(weather: &mut Weather, ticks: usize) -> Vec<(String, String)> {
+        (0..ticks)
+            .map(|_| {
+                weather.update();
+                (weather.season().id.clone(), weather.current.clone())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_weather() {
+        let skies_for = |seed| skies(&mut Weather::new(seed), 20000);
+        let first = skies_for(5);
+
+        assert_eq!(first, skies_for(5));
+        assert_ne!(first, skies_for(6));
+        assert!(first.iter().any(|(_, weather)| *weather != first[0].1));
+    }
+
+    #[test]
+    fn saving_doesnt_change_what_comes_next() {
+        let mut weather = Weather::new(9);
+        skies(&mut weather, 3000);
+        let mut loaded: Weather =
+            serde_json::from_str(&serde_json::to_string(&weather).unwrap()).unwrap();
+
+        assert_eq!(skies(&mut weather, 5000), skies(&mut loaded, 5000));
+    }
+}
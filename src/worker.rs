@@ -1,43 +1,164 @@
-use std::{
-    collections::{HashMap, VecDeque},
-    f32::INFINITY,
-};
+use std::collections::HashMap;
 
-use noise::NoiseFn;
-use raylib::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    TILE_UPDATE_TIME,
     animal::AnimalHandler,
-    map::{Climate, Map, TILE_PIXEL_SIZE, TILE_SIZE, TileType},
+    common::parse_json,
+    inventory::{Inventory, unit_price},
+    job_board::JobBoard,
+    map::{Map, TILE_SIZE, TROUGH_CAPACITY, TileType},
+    market::Market,
+    pathfinding::Pathfinder,
     player::Player,
-    upgrades::UpgradeHandler,
-    utils::parse_json,
+    sim::{Produce, SimEvent},
+    upgrades::{UpgradeHandler, UpgradeTarget},
 };
 
+pub const TICKS_PER_MINUTE: usize = (60. / TILE_UPDATE_TIME) as usize;
+
+/// Stamina and wage rates, read from static/workers.json
+#[derive(Deserialize)]
+pub struct WorkerSettings {
+    pub max_stamina: usize,
+    // used up by every finished job
+    stamina_per_job: usize,
+    // below this workers go rest before taking another job
+    pub tired_below: usize,
+    rest_per_tick: usize,
+    // for workers with no rest hut to get to
+    rest_per_tick_outside: usize,
+    // paid for every hired worker, 0 turns wages off
+    pub wage_per_minute: usize,
+    // unpaid workers only move every this many ticks
+    unpaid_step_interval: usize,
+    // harvests needed for each skill level
+    skill_levels: Vec<usize>,
+    double_harvest_chance_per_level: f64,
+    // extra share of the xp for every level
+    bonus_exp_per_level: f32,
+    // workers walk one more tile per tick for every this many levels over all skills
+    levels_per_extra_step: usize,
+}
+
+impl WorkerSettings {
+    fn load() -> Self {
+        parse_json("static/workers.json").expect("no worker settings")
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct WorkerHandler {
     pub workers: Vec<Worker>,
+    #[serde(default)]
+    next_worker_id: usize,
+    #[serde(default)]
+    pub job_board: JobBoard,
+    #[serde(skip)]
+    pub pathfinder: Pathfinder,
+    #[serde(skip, default = "WorkerSettings::load")]
+    pub settings: WorkerSettings,
+    // ticks until the next wages are due
+    #[serde(default)]
+    wage_timer: usize,
+    // wages owed for every minute there was no money to pay them
+    #[serde(default)]
+    wages_due: usize,
+    #[serde(skip)]
+    ticks: usize,
 }
 
 impl WorkerHandler {
     pub fn new() -> Self {
-        let res = parse_json("dynamic/workers_save.json");
+        let mut worker_handler = Self {
+            workers: vec![],
+            next_worker_id: 0,
+            job_board: JobBoard::default(),
+            pathfinder: Pathfinder::default(),
+            settings: WorkerSettings::load(),
+            wage_timer: 0,
+            wages_due: 0,
+            ticks: 0,
+        };
+        worker_handler.add_worker(Worker::new(0, 0));
 
-        match res {
-            Ok(handler) => handler,
-            Err(_) => {
-                println!("no worker");
+        worker_handler
+    }
 
-                Self {
-                    workers: vec![Worker::new(0, 0)],
-                }
-            }
+    pub fn add_worker(&mut self, mut worker: Worker) {
+        worker.id = self.next_worker_id;
+        self.next_worker_id += 1;
+        self.workers.push(worker);
+    }
+
+    /// Removes the worker standing on a tile, along with any job it had taken. Whatever it was
+    /// carrying goes into storage instead of leaving with it
+    pub fn dismiss_worker_at(
+        &mut self,
+        position: (i32, i32),
+        inventory: &mut Inventory,
+    ) -> Option<Worker> {
+        let index = self
+            .workers
+            .iter()
+            .position(|worker| worker.position == position)?;
+        let worker = self.workers.remove(index);
+        self.job_board.release_all(worker.id);
+
+        if let Some(crop) = worker.carrying {
+            inventory.put_back(Produce::Crop(crop), 1);
+        }
+        if let Some((produce, amount)) = worker.delivering {
+            inventory.put_back(produce, amount);
         }
+        if let Some((produce, amount, _)) = worker.supplying {
+            inventory.put_back(produce, amount);
+        }
+        Some(worker)
     }
 
-    pub fn add_worker(&mut self, worker: Worker) {
-        self.workers.push(worker);
+    /// Saves from before workers had ids load with every id at 0, those get numbered again
+    pub fn assign_ids(&mut self) {
+        let mut ids: Vec<usize> = self.workers.iter().map(|worker| worker.id).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        if ids.len() == self.workers.len() {
+            return;
+        }
+
+        self.job_board = JobBoard::default();
+        for (id, worker) in self.workers.iter_mut().enumerate() {
+            worker.id = id;
+            worker.drop_job();
+        }
+        self.next_worker_id = self.workers.len();
+    }
+
+    /// Only workers bought in the shop are paid, so a farm with no income yet can't run out of money
+    pub fn wages_per_minute(&self) -> usize {
+        self.settings.wage_per_minute * self.workers.iter().filter(|worker| worker.hired).count()
+    }
+
+    pub fn unpaid(&self) -> bool {
+        self.wages_due > 0
+    }
+
+    /// Pays everyone once a minute. Wages that can't be paid add up until the money is there for
+    /// all of them, and until then the workers slow down
+    fn pay_wages(&mut self, player: &mut Player, events: &mut Vec<SimEvent>) {
+        self.wage_timer += 1;
+        if self.wage_timer >= TICKS_PER_MINUTE {
+            self.wage_timer = 0;
+            self.wages_due += self.wages_per_minute();
+        }
+
+        if self.wages_due > 0 && player.money >= self.wages_due {
+            player.money -= self.wages_due;
+            events.push(SimEvent::WagesPaid {
+                money: std::mem::take(&mut self.wages_due),
+            });
+        }
     }
 
     pub fn advance_workers(
@@ -46,41 +167,237 @@ impl WorkerHandler {
         map: &mut Map,
         animal_handler: &AnimalHandler,
         upgrade_handler: &UpgradeHandler,
-        sounds: &HashMap<String, Sound<'_>>,
+        market: &mut Market,
+        events: &mut Vec<SimEvent>,
     ) {
+        self.pay_wages(player, events);
+        // before the unpaid workers sit out the tick, the tiles changed in it are only reported once
+        self.pathfinder.sync(map);
+        self.job_board.sync(map);
+
+        self.ticks += 1;
+        if self.unpaid()
+            && !self
+                .ticks
+                .is_multiple_of(self.settings.unpaid_step_interval.max(1))
+        {
+            return;
+        }
+
+        for cancelled in self.job_board.update(map) {
+            if let Some(worker) = self
+                .workers
+                .iter_mut()
+                .find(|worker| worker.id == cancelled)
+            {
+                worker.drop_job();
+            }
+        }
+
         self.workers.iter_mut().for_each(|worker| {
             // feels weird and illegal
-            let (money, exp) = worker.follow_path(map, animal_handler, upgrade_handler, &sounds);
-            player.money += money;
+            let (money, exp) = worker.follow_path(
+                map,
+                &mut self.pathfinder,
+                &mut self.job_board,
+                &self.settings,
+                &mut player.inventory,
+                animal_handler,
+                upgrade_handler,
+                market,
+                events,
+            );
+            player.earn(money);
             player.exp += exp;
         });
     }
-
-    pub fn save(&self) {
-        let serialized = serde_json::to_string_pretty(self).expect("couldn't save workers data");
-        std::fs::create_dir_all("dynamic").expect("Couldn't create dir");
-        std::fs::write("dynamic/workers_save.json", serialized)
-            .expect("Couldn't write map data to json file");
-    }
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Worker {
+    #[serde(default)]
+    pub id: usize,
     pub position: (i32, i32),
-    display_position: (f32, f32),
+    // where it's drawn, eased towards the position every frame
+    pub display_position: (f32, f32),
     path: Vec<(i32, i32)>,
     #[serde(skip_serializing, skip_deserializing)]
-    direction: (i32, i32),
+    pub direction: (i32, i32),
+    #[serde(default)]
+    pub role: WorkerRole,
+    #[serde(default = "default_priorities")]
+    priorities: HashMap<JobType, usize>,
+    // what the worker is walking to do, if anything
+    #[serde(default)]
+    pub job: Option<JobType>,
+    // the pathfinder generation the path was found in
+    #[serde(skip)]
+    path_generation: usize,
+    // stamina used up since the last full rest
+    #[serde(default)]
+    tiredness: usize,
+    #[serde(default)]
+    pub resting: bool,
+    // empty until the player names the worker
+    #[serde(default)]
+    pub name: String,
+    // harvests done in each category
+    #[serde(default)]
+    skill_exp: HashMap<Skill, usize>,
+    // a crop on its way to a trough
+    #[serde(default)]
+    pub carrying: Option<usize>,
+    // goods on their way to storage, and how many
+    #[serde(default)]
+    pub delivering: Option<(Produce, usize)>,
+    // goods taken out of storage for the building with its top left corner there
+    #[serde(default)]
+    pub supplying: Option<(Produce, usize, (i32, i32))>,
+    // bought in the shop, workers that came with the farm or for free don't draw a wage
+    #[serde(default)]
+    pub hired: bool,
 }
 
-#[derive(PartialEq)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug)]
 pub enum JobType {
+    // pick ripe crops and fruit
     Harvest,
+    // plant the same crop again on a harvested field
+    Replant,
+    // plant whatever the player marked with a blueprint
+    PlantFromPlan,
+    CollectDrops,
+    TendHives,
+    // bring harvested crops to the troughs instead of selling them
+    FeedAnimals,
+    // bring buildings what they need from storage and take what they made back
+    RunBuildings,
+}
+
+impl JobType {
+    pub const ALL: [JobType; 7] = [
+        JobType::Harvest,
+        JobType::Replant,
+        JobType::PlantFromPlan,
+        JobType::CollectDrops,
+        JobType::TendHives,
+        JobType::FeedAnimals,
+        JobType::RunBuildings,
+    ];
+
+    /// The job a tile needs done right now, if any. Feeding isn't tied to a tile, it's done by whoever
+    /// picked up the crop. Buildings are supplied from storage, so that's where the job starts
+    pub fn for_tile(map: &Map, tile: &TileType) -> Option<Self> {
+        match tile {
+            TileType::Farmland { crop, stage }
+                if *stage >= map.static_data.crops_data[*crop].time_to_grow =>
+            {
+                Some(JobType::Harvest)
+            }
+            TileType::Tree { tree, stage, .. }
+                if *stage >= map.static_data.tree_data[*tree].time_to_fruit =>
+            {
+                Some(JobType::Harvest)
+            }
+            TileType::Fallow { .. } => Some(JobType::Replant),
+            TileType::Blueprint { .. } => Some(JobType::PlantFromPlan),
+            TileType::AnimalDrop { .. } => Some(JobType::CollectDrops),
+            TileType::Beehive { stage, .. }
+                if *stage >= map.static_data.hive_data[0].time_to_honey =>
+            {
+                Some(JobType::TendHives)
+            }
+            TileType::Building { origin, .. } if map.dynamic_data.buildings[origin].output > 0 => {
+                Some(JobType::RunBuildings)
+            }
+            TileType::Storage if map.supplies_wanted => Some(JobType::RunBuildings),
+            _ => None,
+        }
+    }
+}
+
+// jobs with a priority of 0 are never taken
+pub const MAX_PRIORITY: usize = 3;
+
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Copy, Debug)]
+pub enum WorkerRole {
+    #[default]
+    Farmhand,
+    Harvester,
+    Planter,
+    Hauler,
+    Beekeeper,
+}
+
+impl WorkerRole {
+    pub const ALL: [WorkerRole; 5] = [
+        WorkerRole::Farmhand,
+        WorkerRole::Harvester,
+        WorkerRole::Planter,
+        WorkerRole::Hauler,
+        WorkerRole::Beekeeper,
+    ];
+
+    /// Job priorities a worker gets when given this role. Farmhands do a bit of everything,
+    /// everyone else sticks to their own jobs until told otherwise
+    pub fn priorities(self) -> HashMap<JobType, usize> {
+        JobType::ALL
+            .into_iter()
+            .map(|job| {
+                let priority = match (self, job) {
+                    (WorkerRole::Farmhand, _) => 2,
+                    (WorkerRole::Harvester, JobType::Harvest)
+                    | (WorkerRole::Planter, JobType::Replant | JobType::PlantFromPlan)
+                    | (
+                        WorkerRole::Hauler,
+                        JobType::CollectDrops | JobType::FeedAnimals | JobType::RunBuildings,
+                    )
+                    | (WorkerRole::Beekeeper, JobType::TendHives) => MAX_PRIORITY,
+                    _ => 0,
+                };
+                (job, priority)
+            })
+            .collect()
+    }
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|role| *role == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+/// What a worker gets better at by harvesting it
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum Skill {
+    Crops,
+    Trees,
+    Animals,
+    Bees,
+}
+
+impl Skill {
+    pub const ALL: [Skill; 4] = [Skill::Crops, Skill::Trees, Skill::Animals, Skill::Bees];
+
+    /// Goods made in buildings aren't harvested, so no skill comes with them
+    fn of(produce: Produce) -> Option<Self> {
+        match produce {
+            Produce::Crop(_) => Some(Skill::Crops),
+            Produce::Tree(_) => Some(Skill::Trees),
+            Produce::Animal(_) => Some(Skill::Animals),
+            Produce::Honey => Some(Skill::Bees),
+            Produce::Good(_) => None,
+        }
+    }
+}
+
+fn default_priorities() -> HashMap<JobType, usize> {
+    WorkerRole::default().priorities()
 }
 
 impl Worker {
     pub fn new(x: i32, y: i32) -> Self {
         Self {
+            id: 0,
             position: (x, y),
             display_position: (
                 (x * TILE_SIZE) as f32,
@@ -88,345 +405,493 @@ impl Worker {
             ),
             path: vec![],
             direction: (0, 0),
+            role: WorkerRole::default(),
+            priorities: default_priorities(),
+            job: None,
+            path_generation: 0,
+            tiredness: 0,
+            resting: false,
+            name: String::new(),
+            skill_exp: HashMap::new(),
+            carrying: None,
+            delivering: None,
+            supplying: None,
+            hired: false,
         }
     }
 
-    fn find_closest_target(&self, map: &mut Map, job: JobType) -> (i32, i32) {
-        let target_position;
-
-        match job {
-            JobType::Harvest => {
-                let mut closest = (INFINITY as i32, INFINITY as i32);
-                let mut shortest_distance = INFINITY;
-
-                // get closest crop tile
-                for (tile_position, tile) in map.dynamic_data.tiles.iter() {
-                    if let Some(occupation_tile) =
-                        map.dynamic_data.occupation_map.get(tile_position)
-                    {
-                        if *occupation_tile {
-                            // this tile is taken by other worker
-                            continue;
-                        }
-                    }
+    pub fn skill_level(&self, skill: Skill, settings: &WorkerSettings) -> usize {
+        let exp = self.skill_exp.get(&skill).copied().unwrap_or(0);
+        settings
+            .skill_levels
+            .iter()
+            .take_while(|needed| exp >= **needed)
+            .count()
+    }
 
-                    let tile_position_vec =
-                        Vector2::new(tile_position.0 as f32, tile_position.1 as f32);
-
-                    match tile {
-                        TileType::Farmland { crop, stage } => {
-                            if *stage >= map.static_data.crops_data[*crop].time_to_grow {
-                                let worker_position =
-                                    Vector2::new(self.position.0 as f32, self.position.1 as f32);
-
-                                if tile_position_vec.distance_to(worker_position)
-                                    < shortest_distance
-                                {
-                                    closest = *tile_position;
-                                    shortest_distance =
-                                        tile_position_vec.distance_to(worker_position);
-                                }
-                            }
-                        }
-                        TileType::Tree { tree, stage, .. } => {
-                            if *stage >= map.static_data.tree_data[*tree].time_to_fruit {
-                                let worker_position =
-                                    Vector2::new(self.position.0 as f32, self.position.1 as f32);
-
-                                if tile_position_vec.distance_to(worker_position)
-                                    < shortest_distance
-                                {
-                                    closest = *tile_position;
-                                    shortest_distance =
-                                        tile_position_vec.distance_to(worker_position);
-                                }
-                            }
-                        }
-                        TileType::AnimalDrop { .. } => {
-                            let worker_position =
-                                Vector2::new(self.position.0 as f32, self.position.1 as f32);
-
-                            if tile_position_vec.distance_to(worker_position) < shortest_distance {
-                                closest = *tile_position;
-                                shortest_distance = tile_position_vec.distance_to(worker_position);
-                            }
-                        }
-                        TileType::Beehive { stage, .. } => {
-                            if *stage >= map.static_data.hive_data[0].time_to_honey {
-                                let worker_position =
-                                    Vector2::new(self.position.0 as f32, self.position.1 as f32);
-
-                                if tile_position_vec.distance_to(worker_position)
-                                    < shortest_distance
-                                {
-                                    closest = *tile_position;
-                                    shortest_distance =
-                                        tile_position_vec.distance_to(worker_position);
-                                }
-                            }
-                        }
-                        _ => {}
-                    }
-                }
+    pub fn total_level(&self, settings: &WorkerSettings) -> usize {
+        Skill::ALL
+            .iter()
+            .map(|skill| self.skill_level(*skill, settings))
+            .sum()
+    }
 
-                target_position = closest;
-            } // _ => target_position = (0, 0),
-        }
+    /// Where the worker is walking to, if anywhere
+    pub fn target(&self) -> Option<(i32, i32)> {
+        self.path.last().copied()
+    }
+
+    pub fn next_step(&self) -> Option<(i32, i32)> {
+        self.path.first().copied()
+    }
+
+    pub fn stamina(&self, settings: &WorkerSettings) -> usize {
+        settings.max_stamina.saturating_sub(self.tiredness)
+    }
+
+    /// Stops walking to the current job, the worker looks for a new one next tick
+    pub fn drop_job(&mut self) {
+        self.path.clear();
+        self.job = None;
+    }
+
+    /// Gives the worker a role, resetting the job priorities to the role's ones
+    pub fn set_role(&mut self, role: WorkerRole) {
+        self.role = role;
+        self.priorities = role.priorities();
+    }
+
+    /// Jobs added after the worker was hired get the priority the role gives them
+    pub fn priority(&self, job: JobType) -> usize {
+        self.priorities
+            .get(&job)
+            .copied()
+            .unwrap_or_else(|| self.role.priorities()[&job])
+    }
 
-        map.dynamic_data
-            .occupation_map
-            .insert(target_position, true);
-        target_position
+    /// Bumps the priority of a job by one, wrapping back to 0 after the maximum
+    pub fn cycle_priority(&mut self, job: JobType) {
+        let priority = (self.priority(job) + 1) % (MAX_PRIORITY + 1);
+        self.priorities.insert(job, priority);
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn follow_path(
         &mut self,
         map: &mut Map,
+        pathfinder: &mut Pathfinder,
+        job_board: &mut JobBoard,
+        settings: &WorkerSettings,
+        inventory: &mut Inventory,
         animal_handler: &AnimalHandler,
         upgrade_handler: &UpgradeHandler,
-        sounds: &HashMap<String, Sound<'_>>,
+        market: &mut Market,
+        events: &mut Vec<SimEvent>,
     ) -> (usize, usize) {
-        if let Some(next_position) = self.path.get(0) {
-            self.position = *next_position;
-            self.path.remove(0);
+        if !self.path.is_empty() && self.path_generation != pathfinder.generation() {
+            // the island changed since the path was found, there may be a better way or none at all
+            let target = *self.path.last().unwrap();
+            self.path = pathfinder
+                .find_path(&map.dynamic_data.tiles, self.position, target)
+                .unwrap_or_default();
+            self.path_generation = pathfinder.generation();
+
+            if self.path.is_empty() {
+                job_board.release(target, self.id);
+                self.job = None;
+            }
+        }
+
+        if !self.path.is_empty() {
+            let steps = (upgrade_handler.growth_speed(UpgradeTarget::Worker)
+                + self.total_level(settings) / settings.levels_per_extra_step.max(1))
+            .min(self.path.len());
+            self.position = self.path[steps - 1];
+            self.path.drain(..steps);
+            return (0, 0);
+        }
+
+        if let Some(crop) = self.carrying.take() {
+            let mut money = 0;
+            if let Some(TileType::Trough {
+                crop: stored,
+                amount,
+            }) = map.dynamic_data.tiles.get_mut(&self.position)
+                && stored.is_none_or(|stored| stored == crop)
+                && *amount < TROUGH_CAPACITY
+            {
+                *stored = Some(crop);
+                *amount += 1;
+            } else {
+                // every trough filled up on the way, so the crop gets sold after all
+                let crop_data = &map.static_data.crops_data[crop];
+                (money, _) =
+                    upgrade_handler.apply(UpgradeTarget::Crop(crop), crop_data.sell_price, 0);
+                money *= map
+                    .biomes
+                    .climate_multiplier(self.position, crop_data.climate);
+                let price = unit_price(Produce::Crop(crop), map, animal_handler, upgrade_handler);
+                money = market.sell(Produce::Crop(crop), money, price);
+                events.push(SimEvent::Sold {
+                    position: self.position,
+                    produce: Produce::Crop(crop),
+                    money,
+                });
+            }
+
+            self.find_path(map, pathfinder, job_board, settings);
+            return (money, 0);
+        }
+
+        if let Some((produce, amount)) = self.delivering.take() {
+            let stored = if map.dynamic_data.tiles[&self.position] == TileType::Storage {
+                inventory.add(produce, amount, map)
+            } else {
+                0
+            };
+
+            // whatever doesn't fit, or has no storage to get to, gets sold after all
+            let price = unit_price(produce, map, animal_handler, upgrade_handler);
+            let money = market.sell(produce, (amount - stored) * price, price);
+            if money > 0 {
+                events.push(SimEvent::Sold {
+                    position: self.position,
+                    produce,
+                    money,
+                });
+            }
+
+            self.find_path(map, pathfinder, job_board, settings);
+            return (money, 0);
+        }
+
+        if let Some((produce, amount, origin)) = self.supplying.take() {
+            let at_building = matches!(
+                map.dynamic_data.tiles[&self.position],
+                TileType::Building { origin: building, .. } if building == origin
+            );
+            let leftover = match map.dynamic_data.buildings.get_mut(&origin) {
+                Some(state) if at_building => {
+                    state.supply(&map.recipes.buildings[state.building], produce, amount)
+                }
+                _ => amount,
+            };
+
+            // the building was taken down or couldn't be reached, so it all goes back
+            if leftover > 0 {
+                self.delivering = Some((produce, leftover));
+            }
+
+            self.find_path(map, pathfinder, job_board, settings);
+            return (0, 0);
+        }
+
+        if self.resting {
+            let rest = if map.dynamic_data.tiles[&self.position] == TileType::RestHut {
+                settings.rest_per_tick
+            } else {
+                settings.rest_per_tick_outside
+            };
+            self.tiredness = self.tiredness.saturating_sub(rest);
+
+            if self.tiredness == 0 {
+                self.resting = false;
+                self.find_path(map, pathfinder, job_board, settings);
+            }
             return (0, 0);
         }
 
-        let tile = map.dynamic_data.tiles.get_mut(&self.position).unwrap();
         let mut money = 0;
         let mut exp = 0;
+        let mut harvested = None;
+        // pieces brought in, a good climate grows more of them
+        let mut amount = 1;
+
+        // only do the work at hand if it's one of this worker's jobs and nobody else is on it
+        let job = JobType::for_tile(map, &map.dynamic_data.tiles[&self.position]);
+        if job.is_none_or(|job| self.priority(job) == 0)
+            || !job_board.is_free_for(self.position, self.id)
+        {
+            self.find_path(map, pathfinder, job_board, settings);
+            return (money, exp);
+        }
+
+        if job == Some(JobType::RunBuildings) {
+            self.run_buildings(map, pathfinder, inventory);
+            self.tiredness += settings.stamina_per_job;
+            job_board.release(self.position, self.id);
+            job_board.refresh(self.position, map);
+            if self.path.is_empty() {
+                self.find_path(map, pathfinder, job_board, settings);
+            }
+            return (money, exp);
+        }
+
+        // crops a trough is waiting for get carried there instead of sold
+        let carry_crop = match map.dynamic_data.tiles[&self.position] {
+            TileType::Farmland { crop, .. } => {
+                self.priority(JobType::FeedAnimals) > 0 && map.wants_feed(crop)
+            }
+            _ => false,
+        };
+
+        let tile = map.dynamic_data.tiles.get_mut(&self.position).unwrap();
 
-        // harvest
         match tile {
             TileType::Farmland { crop, stage } => {
                 let crop_data = &map.static_data.crops_data[*crop];
                 if *stage >= crop_data.time_to_grow {
-                    let multiplier = upgrade_handler.get_multiplier_for_crop(*crop);
-
-                    money = crop_data.sell_price * multiplier;
-                    exp = crop_data.exp * multiplier;
-                    *stage = 0;
-
-                    let sample = map
-                        .noise
-                        .get([self.position.0 as f64 * 0.05, self.position.1 as f64 * 0.05]);
-                    let tile_climate = if sample < -0.5 {
-                        Climate::Cold
-                    } else if sample > 0.5 {
-                        Climate::Warm
-                    } else {
-						Climate::Temperate
-                    };
-
-                    if tile_climate == crop_data.climate {
-                        money *= 2;
-                        exp *= 2;
+                    (money, exp) = upgrade_handler.apply(
+                        UpgradeTarget::Crop(*crop),
+                        crop_data.sell_price,
+                        crop_data.exp,
+                    );
+
+                    let multiplier = map
+                        .biomes
+                        .climate_multiplier(self.position, crop_data.climate);
+                    money *= multiplier;
+                    exp *= multiplier;
+                    amount = multiplier;
+                    harvested = Some(Produce::Crop(*crop));
+
+                    if carry_crop {
+                        self.carrying = Some(*crop);
+                        money = 0;
                     }
 
-                    // free this tile from work
-                    if let Some(occupation_tile) =
-                        map.dynamic_data.occupation_map.get_mut(&self.position)
-                    {
-                        *occupation_tile = false;
-                    };
-                    let rand = rand::random_range(0..5);
-                    let sound = sounds.get(&format!("harvest{rand}")).unwrap();
-                    sound.set_pitch(rand::random_range(0.9..1.1));
-                    sound.play();
+                    // the field stays empty until someone replants it
+                    *tile = TileType::Fallow { crop: *crop };
                 }
             }
+            TileType::Fallow { crop } | TileType::Blueprint { crop } => {
+                *tile = TileType::Farmland {
+                    crop: *crop,
+                    stage: 0,
+                };
+            }
             TileType::Tree { tree, stage, .. } => {
                 let tree_data = &map.static_data.tree_data[*tree];
                 if *stage >= tree_data.time_to_fruit {
-                    // we're basically offsetting the upgrade thingy, so uhh, still kinda hardcoded but idc
-                    let crops_len = map.static_data.crops_data.len();
-                    let multiplier = upgrade_handler.get_multiplier_for_tree(*tree, crops_len);
-
-                    money = tree_data.sell_price * multiplier;
-                    exp = tree_data.exp * multiplier;
+                    (money, exp) = upgrade_handler.apply(
+                        UpgradeTarget::Tree(*tree),
+                        tree_data.sell_price,
+                        tree_data.exp,
+                    );
                     *stage = 0;
 
-                    let tile_climate = if self.position.1 > 7 {
-                        Climate::Warm
-                    } else if self.position.1 < -7 {
-                        Climate::Cold
-                    } else {
-                        Climate::Temperate
-                    };
-
-                    if tile_climate == tree_data.climate {
-                        money *= 2;
-                        exp *= 2;
-                    }
-
-                    if let Some(occupation_tile) =
-                        map.dynamic_data.occupation_map.get_mut(&self.position)
-                    {
-                        *occupation_tile = false;
-                    };
-                    let rand = rand::random_range(0..5);
-                    let sound = sounds.get(&format!("harvest{rand}")).unwrap();
-                    sound.set_pitch(rand::random_range(0.9..1.1));
-                    sound.play();
+                    let multiplier = map
+                        .biomes
+                        .climate_multiplier(self.position, tree_data.climate);
+                    money *= multiplier;
+                    exp *= multiplier;
+                    amount = multiplier;
+                    harvested = Some(Produce::Tree(*tree));
                 }
             }
             TileType::AnimalDrop { animal } => {
-                let crops_len = map.static_data.crops_data.len();
-                let trees_len = map.static_data.tree_data.len();
-                let multiplier =
-                    upgrade_handler.get_multiplier_for_animal(*animal, crops_len, trees_len);
-
-                money =
-                    animal_handler.static_data.animal_data[*animal as usize].drop_cost * multiplier;
-                exp = animal_handler.static_data.animal_data[*animal as usize].exp * multiplier;
-
-                if let Some(occupation_tile) =
-                    map.dynamic_data.occupation_map.get_mut(&self.position)
-                {
-                    *occupation_tile = false;
-                };
+                let animal_data = &animal_handler.static_data.animal_data[*animal];
+                (money, exp) = upgrade_handler.apply(
+                    UpgradeTarget::Animal(*animal),
+                    animal_data.drop_cost,
+                    animal_data.exp,
+                );
+
+                let multiplier = map
+                    .biomes
+                    .climate_multiplier(self.position, animal_data.climate);
+                money *= multiplier;
+                exp *= multiplier;
+                amount = multiplier;
 
-                let sound = sounds.get(&format!("grass")).unwrap();
-                sound.set_pitch(rand::random_range(0.9..1.1));
-                sound.play();
+                harvested = Some(Produce::Animal(*animal));
 
                 map.dynamic_data
                     .tiles
                     .insert(self.position, TileType::Grass);
             }
-            TileType::Beehive { stage, price, xp } => {
-                if *stage >= map.static_data.hive_data[0].time_to_honey {
-                    if let Some(occupation_tile) =
-                        map.dynamic_data.occupation_map.get_mut(&self.position)
-                    {
-                        *occupation_tile = false;
-                    };
+            TileType::Beehive { stage, price, xp }
+                if *stage >= map.static_data.hive_data[0].time_to_honey =>
+            {
+                money = *price;
+                exp = *xp;
 
-                    money = *price;
-                    exp = *xp;
+                *stage = 0;
 
-                    *stage = 0;
-                    // *price = 0;
-                }
+                harvested = Some(Produce::Honey);
             }
             _ => {}
         }
 
-        self.find_path(map, JobType::Harvest);
-        return (money, exp);
-    }
-
-    pub fn find_path(&mut self, map: &mut Map, job: JobType) -> Option<Vec<(i32, i32)>> {
-        let start_position = self.position;
-        let target_position = self.find_closest_target(map, job);
-
-        if !map.dynamic_data.tiles.contains_key(&start_position)
-            || !map.dynamic_data.tiles.contains_key(&target_position)
+        if let Some(produce) = harvested
+            && let Some(skill) = Skill::of(produce)
         {
-            return None;
-        }
+            let level = self.skill_level(skill, settings);
+            let price = unit_price(produce, map, animal_handler, upgrade_handler);
 
-        let mut queue = VecDeque::new();
-        queue.push_back(start_position);
+            // the flowers around a hive make its honey worth several plain hives
+            if produce == Produce::Honey {
+                amount = (money / price).max(1);
+            }
 
-        let mut visited = HashMap::new();
-        visited.insert(start_position, None); // (position, parent)
+            // skilled workers sometimes bring in twice as much, and always a bit more xp
+            let double_chance = level as f64 * settings.double_harvest_chance_per_level;
+            if rand::random_bool(double_chance.min(1.)) {
+                money *= 2;
+                exp *= 2;
+                amount *= 2;
+            }
+            exp += (exp as f32 * level as f32 * settings.bonus_exp_per_level) as usize;
 
-        let directions = [(0, 1), (0, -1), (1, 0), (-1, 0)];
+            *self.skill_exp.entry(skill).or_insert(0) += 1;
 
-        while let Some(current_position) = queue.pop_front() {
-            if current_position == target_position {
-                let mut path = vec![];
-                let mut pos = current_position;
+            // goods the player keeps are counted in pieces and taken to storage instead of sold
+            if money > 0 && inventory.is_kept(produce) && inventory.has_room(map) {
+                self.delivering = Some((produce, amount));
+                money = 0;
+            } else {
+                money = market.sell(produce, money, price);
+            }
 
-                while let Some(parent) = visited[&pos] {
-                    path.push(pos);
-                    pos = parent;
-                }
+            events.push(SimEvent::Harvest {
+                position: self.position,
+                produce,
+                money,
+                exp,
+            });
+        }
 
-                path.push(start_position);
-                path.reverse();
-                self.path = path.clone();
-                return Some(path);
-            }
+        self.tiredness += settings.stamina_per_job;
+        job_board.release(self.position, self.id);
+        job_board.refresh(self.position, map);
+        self.find_path(map, pathfinder, job_board, settings);
+        (money, exp)
+    }
 
-            for direction in directions.iter() {
-                let next_position = (
-                    current_position.0 + direction.0,
-                    current_position.1 + direction.1,
-                );
+    /// Picks up what a building made, or at storage takes what the closest building that's short on
+    /// something needs and heads there with it
+    fn run_buildings(
+        &mut self,
+        map: &mut Map,
+        pathfinder: &mut Pathfinder,
+        inventory: &mut Inventory,
+    ) {
+        if let TileType::Building { building, origin } = map.dynamic_data.tiles[&self.position] {
+            let state = map.dynamic_data.buildings.get_mut(&origin).unwrap();
+            let good = map.recipes.buildings[state.building].output;
+            self.delivering = Some((Produce::Good(good), std::mem::take(&mut state.output)));
+            map.touch_building(building, origin);
+            return;
+        }
 
-                if visited.contains_key(&next_position)
-                    || !map.dynamic_data.tiles.contains_key(&next_position)
-                {
-                    continue;
-                }
+        let recipes = &map.recipes;
+        let buildings = &map.dynamic_data.buildings;
 
-                visited.insert(next_position, Some(current_position));
-                queue.push_back(next_position);
+        let wanted = |position: (i32, i32)| match map.dynamic_data.tiles[&position] {
+            TileType::Building { origin, .. } => {
+                let state = &buildings[&origin];
+                state
+                    .wanted_input(&recipes.buildings[state.building], inventory)
+                    .map(|wanted| (wanted, origin))
             }
-        }
+            _ => None,
+        };
+        let Some(path) =
+            pathfinder.find_nearest(&map.dynamic_data.tiles, self.position, |position| {
+                wanted(position).is_some()
+            })
+        else {
+            return;
+        };
 
-        None
+        let ((produce, amount), origin) = wanted(*path.last().unwrap()).unwrap();
+        let taken = inventory.take(produce, amount);
+        self.supplying = Some((produce, taken, origin));
+        self.job = Some(JobType::RunBuildings);
+        self.path = path;
+        self.path_generation = pathfinder.generation();
     }
 
-    pub fn draw(&mut self, rl: &mut RaylibDrawHandle, texture: &Texture2D) {
-        let pixel_position = Vector2::new(
-            (self.position.0 * TILE_SIZE) as f32,
-            (self.position.1 * TILE_SIZE) as f32,
-        );
-
-        self.display_position.0 = lerp(
-            self.display_position.0,
-            pixel_position.x,
-            10. * rl.get_frame_time(),
-        );
-        self.display_position.1 = lerp(
-            self.display_position.1,
-            pixel_position.y,
-            10. * rl.get_frame_time(),
-        );
-
-        let pixel_position = Vector2::new(self.display_position.0, self.display_position.1);
-
-        // rl.draw_texture_ex(texture, pixel_position, 0., TILE_SCALE as f32, Color::WHITE);
-
-        if let Some(next_position) = self.path.get(0) {
-            self.direction = (
-                next_position.0 - self.position.0,
-                next_position.1 - self.position.1,
-            );
+    /// Heads for the closest tile, by walking distance, with the highest priority job nobody else took
+    fn find_path(
+        &mut self,
+        map: &mut Map,
+        pathfinder: &mut Pathfinder,
+        job_board: &mut JobBoard,
+        settings: &WorkerSettings,
+    ) {
+        self.drop_job();
+        job_board.release_all(self.id);
+
+        if self.delivering.is_some() {
+            // with no storage to get to, the goods are sold on the spot next tick
+            self.path = pathfinder
+                .find_nearest(&map.dynamic_data.tiles, self.position, |position| {
+                    map.dynamic_data.tiles[&position] == TileType::Storage
+                })
+                .unwrap_or_default();
+            self.path_generation = pathfinder.generation();
+            return;
         }
 
-        let texture_index = match self.direction {
-            (0, 1) => 0,
-            (0, -1) => 1,
-            (1, 0) => 2,
-            (-1, 0) => 3,
-            _ => 4,
-        };
-
-        let source = Rectangle {
-            x: (texture_index * TILE_PIXEL_SIZE) as f32,
-            y: 0.,
-            width: TILE_PIXEL_SIZE as f32,
-            height: TILE_PIXEL_SIZE as f32,
-        };
+        if let Some(crop) = self.carrying {
+            // with no trough to get to, the crop is sold on the spot next tick
+            self.path = pathfinder
+                .find_nearest(&map.dynamic_data.tiles, self.position, |position| {
+                    map.accepts_feed(position, crop)
+                })
+                .unwrap_or_default();
+            self.path_generation = pathfinder.generation();
+            if !self.path.is_empty() {
+                self.job = Some(JobType::FeedAnimals);
+            }
+            return;
+        }
 
-        let destination = Rectangle {
-            x: pixel_position.x,
-            y: pixel_position.y,
-            width: TILE_SIZE as f32,
-            height: TILE_SIZE as f32,
-        };
+        // tired workers go to the closest rest hut, or rest where they are if there's none
+        if self.stamina(settings) < settings.tired_below {
+            self.resting = true;
+            self.path = pathfinder
+                .find_nearest(&map.dynamic_data.tiles, self.position, |position| {
+                    map.dynamic_data.tiles[&position] == TileType::RestHut
+                })
+                .unwrap_or_default();
+            self.path_generation = pathfinder.generation();
+            return;
+        }
 
-        rl.draw_texture_pro(
-            texture,
-            source,
-            destination,
-            Vector2::zero(),
-            0.,
-            Color::WHITE,
-        );
+        let is_free = |position: &(i32, i32)| job_board.is_free_for(*position, self.id);
+
+        // only search for priorities that have a free tile somewhere, highest first
+        let mut priorities: Vec<usize> = JobType::ALL
+            .into_iter()
+            .filter(|job| job_board.has_free_job(*job, self.id))
+            .map(|job| self.priority(job))
+            .filter(|priority| *priority > 0)
+            .collect();
+        priorities.sort_unstable_by(|a, b| b.cmp(a));
+        priorities.dedup();
+
+        for priority in priorities {
+            let path =
+                pathfinder.find_nearest(&map.dynamic_data.tiles, self.position, |position| {
+                    is_free(&position)
+                        && JobType::for_tile(map, &map.dynamic_data.tiles[&position])
+                            .is_some_and(|job| self.priority(job) == priority)
+                });
+
+            // a priority whose jobs can't be reached shouldn't keep the worker idle
+            let Some(path) = path else {
+                continue;
+            };
+
+            let target = *path.last().unwrap();
+            let job = JobType::for_tile(map, &map.dynamic_data.tiles[&target]).unwrap();
+            job_board.reserve(target, self.id, job, path.len());
+            self.job = Some(job);
+            self.path = path;
+            self.path_generation = pathfinder.generation();
+            return;
+        }
     }
 }
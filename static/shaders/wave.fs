@@ -22,6 +22,10 @@ uniform float ampY;
 uniform float speedX;
 uniform float speedY;
 
+// the weather: a colour mixed in by its alpha, and how much higher the waves get
+uniform vec4 tint;
+uniform float waves;
+
 void main() {
     float pixelWidth = 1.0 / size.x;
     float pixelHeight = 1.0 / size.y;
@@ -30,8 +34,9 @@ void main() {
     float boxTop = 0.0;
 
     vec2 p = fragTexCoord;
-    p.x += cos((fragTexCoord.y - boxTop) * freqX / ( pixelWidth * 750.0) + (seconds * speedX)) * ampX * pixelWidth;
-    p.y += sin((fragTexCoord.x - boxLeft) * freqY * aspect / ( pixelHeight * 750.0) + (seconds * speedY)) * ampY * pixelHeight;
+    p.x += cos((fragTexCoord.y - boxTop) * freqX / ( pixelWidth * 750.0) + (seconds * speedX)) * ampX * (1.0 + waves) * pixelWidth;
+    p.y += sin((fragTexCoord.x - boxLeft) * freqY * aspect / ( pixelHeight * 750.0) + (seconds * speedY)) * ampY * (1.0 + waves) * pixelHeight;
 
-    finalColor = texture(texture0, p)*colDiffuse*fragColor;
+    vec4 color = texture(texture0, p)*colDiffuse*fragColor;
+    finalColor = vec4(mix(color.rgb, tint.rgb, tint.a), color.a);
 }
\ No newline at end of file